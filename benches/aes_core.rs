@@ -0,0 +1,102 @@
+//! Benchmarks of the implementations of the AES core.
+//!
+//! Run with `cargo bench`, the throughput of each implementation is printed for each key size,
+//! for single blocks and for batches of blocks.
+
+
+
+
+
+// IMPORTS
+
+use std::hint::black_box;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use tinyaes::{
+    AESCore,
+    AESKey,
+    Implementation,
+};
+
+
+
+
+
+// FUNCTIONS
+
+fn main() {
+    let keys: [(&str, AESKey); 3] = [
+        ("AES-128", AESKey::AES128([0x2b; 16])),
+        ("AES-192", AESKey::AES192([0x2b; 24])),
+        ("AES-256", AESKey::AES256([0x2b; 32])),
+    ];
+    let implementations: [(&str, Implementation); 4] = [
+        ("hardware", Implementation::Hardware),
+        ("bitsliced", Implementation::Bitsliced),
+        ("t-table", Implementation::TTable),
+        ("lookup", Implementation::Lookup),
+    ];
+
+    for (key_name, key) in keys {
+        for (implementation_name, implementation) in implementations {
+            let mut aes: AESCore = AESCore::new(key);
+            aes.set_implementation(implementation);
+
+            let encryption: f64 = throughput(1, |blocks| blocks[0] = aes.encrypt(&blocks[0]));
+            let decryption: f64 = throughput(1, |blocks| blocks[0] = aes.decrypt(&blocks[0]));
+            let batch_encryption: f64 = throughput(BATCH_BLOCKS, |blocks| aes.encrypt_blocks(blocks));
+            let batch_decryption: f64 = throughput(BATCH_BLOCKS, |blocks| aes.decrypt_blocks(blocks));
+            println!(
+                "{} {:<10} encrypt: {:>9.2} MB/s, decrypt: {:>9.2} MB/s, batch encrypt: {:>9.2} MB/s, batch decrypt: {:>9.2} MB/s",
+                key_name, implementation_name, encryption, decryption, batch_encryption, batch_decryption,
+            );
+        }
+    }
+}
+
+fn throughput<F: Fn(&mut [[u8; 16]])>(block_count: usize, function: F) -> f64 {
+    //! Measures the throughput of the function, applied repeatedly to the same blocks so no call can be skipped.
+    //! # Arguments
+    //! * `block_count` - The number of blocks given to the function at once.
+    //! * `function` - The function encrypting or decrypting the blocks in place.
+    //! # Returns
+    //! The number of megabytes processed per second.
+
+    let mut blocks: Vec<[u8; 16]> = vec![[0; 16]; block_count];
+    for _ in 0..WARM_UP_CALLS {
+        function(black_box(&mut blocks));
+    }
+
+    let start: Instant = Instant::now();
+    let mut call_count: u64 = 0;
+    while start.elapsed() < MEASUREMENT_TIME {
+        for _ in 0..CALLS_PER_CHECK {
+            function(black_box(&mut blocks));
+        }
+        call_count += CALLS_PER_CHECK;
+    }
+    black_box(blocks);
+
+    (call_count * block_count as u64 * 16) as f64 / start.elapsed().as_secs_f64() / 1_000_000.0
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of blocks given to the batch functions at once.
+const BATCH_BLOCKS: usize = 64;
+
+/// The number of calls made before the measurement.
+const WARM_UP_CALLS: u64 = 1_000;
+
+/// The number of calls made between the checks of the elapsed time.
+const CALLS_PER_CHECK: u64 = 100;
+
+/// The time for which each function is measured.
+const MEASUREMENT_TIME: Duration = Duration::from_millis(500);
@@ -26,6 +26,19 @@ use core::ops::{
 
 
 
+// MODULES
+
+#[cfg(target_arch = "x86_64")]
+mod aes_ni;
+#[cfg(target_arch = "aarch64")]
+mod armv8;
+mod bitsliced;
+mod t_table;
+
+
+
+
+
 // ENUMS
 
 /// The AES key used to encrypt and decrypt data.
@@ -36,6 +49,27 @@ pub enum AESKey {
     AES256([u8; 32]),
 }
 
+/// The implementations of the AES algorithm.
+/// All of them give the same results, they differ in speed and in resistance to timing attacks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Implementation {
+    /// The AES instructions of the processor (AES-NI and VAES on x86_64, the cryptographic extension on aarch64).
+    /// The support of the processor is detected at runtime. If the instructions aren't supported,
+    /// the bitsliced implementation is used with the `constant-time` feature and the S-Box lookup tables without it.
+    /// This is the default implementation.
+    Hardware,
+    /// The bitsliced implementation processing four blocks at once.
+    /// It is constant-time, as no memory is indexed by the key or the data.
+    Bitsliced,
+    /// The T-tables on 32-bit words, combining the S-Box, shift rows and mix columns of a round.
+    /// The fastest portable implementation, but not constant-time,
+    /// so it should only be used where timing attacks aren't a concern.
+    TTable,
+    /// The S-Box lookup tables on the bytes of the state.
+    /// Not constant-time.
+    Lookup,
+}
+
 /// The round keys used in the AES algorithm.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 enum RoundKeys {
@@ -127,6 +161,8 @@ pub struct AESCore {
     key: AESKey,
     /// The round keys used in the AES algorithm.
     round_keys: RoundKeys,
+    /// The implementation used to encrypt and decrypt data.
+    implementation: Implementation,
 }
 
 /// Public functions for encrypting and decrypting data.
@@ -137,6 +173,7 @@ impl AESCore {
         Self {
             key,
             round_keys: Self::key_expansion(&key),
+            implementation: Implementation::Hardware,
         }
     }
 
@@ -153,9 +190,118 @@ impl AESCore {
         self.round_keys = Self::key_expansion(&key);
     }
 
+    pub fn implementation(&self) -> Implementation {
+        //! Returns the implementation used by this AES instance.
+
+        self.implementation
+    }
+
+    pub fn set_implementation(&mut self, implementation: Implementation) {
+        //! Changes the implementation used by this AES instance.
+
+        self.implementation = implementation;
+    }
+
+    pub fn is_hardware_supported() -> bool {
+        //! Returns whether the processor supports the AES instructions used by `Implementation::Hardware`.
+        //! If it doesn't, the hardware implementation falls back to a portable one.
+
+        #[cfg(target_arch = "x86_64")]
+        return aes_ni::is_supported();
+
+        #[cfg(target_arch = "aarch64")]
+        return armv8::is_supported();
+
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        false
+    }
+
     pub fn encrypt(&self, block: &[u8; 16]) -> [u8; 16] {
         //! Encrypts the given block of data.
 
+        let mut blocks: [[u8; 16]; 1] = [*block];
+        self.encrypt_blocks(&mut blocks);
+        blocks[0]
+    }
+
+    pub fn decrypt(&self, block: &[u8; 16]) -> [u8; 16] {
+        //! Decrypts the given block of data.
+
+        let mut blocks: [[u8; 16]; 1] = [*block];
+        self.decrypt_blocks(&mut blocks);
+        blocks[0]
+    }
+
+    pub fn encrypt_blocks(&self, blocks: &mut [[u8; 16]]) {
+        //! Encrypts the given blocks of data in place.
+        //! Gives the same result as encrypting each block separately, but the implementations
+        //! can process several blocks at once, which is much faster.
+
+        match self.supported_implementation() {
+            Implementation::Hardware => {
+                #[cfg(target_arch = "x86_64")]
+                // SAFETY: the processor supports the AES-NI instructions
+                unsafe { aes_ni::encrypt_blocks(&self.round_keys, blocks) };
+                #[cfg(target_arch = "aarch64")]
+                // SAFETY: the processor supports the AES instructions
+                unsafe { armv8::encrypt_blocks(&self.round_keys, blocks) };
+            },
+            Implementation::Bitsliced => bitsliced::encrypt_blocks(&self.round_keys, blocks),
+            Implementation::TTable => t_table::encrypt_blocks(&self.round_keys, blocks),
+            Implementation::Lookup => {
+                for block in blocks.iter_mut() {
+                    *block = self.encrypt_block(block);
+                }
+            },
+        }
+    }
+
+    pub fn decrypt_blocks(&self, blocks: &mut [[u8; 16]]) {
+        //! Decrypts the given blocks of data in place.
+        //! Gives the same result as decrypting each block separately, but the implementations
+        //! can process several blocks at once, which is much faster.
+
+        match self.supported_implementation() {
+            Implementation::Hardware => {
+                #[cfg(target_arch = "x86_64")]
+                // SAFETY: the processor supports the AES-NI instructions
+                unsafe { aes_ni::decrypt_blocks(&self.round_keys, blocks) };
+                #[cfg(target_arch = "aarch64")]
+                // SAFETY: the processor supports the AES instructions
+                unsafe { armv8::decrypt_blocks(&self.round_keys, blocks) };
+            },
+            Implementation::Bitsliced => bitsliced::decrypt_blocks(&self.round_keys, blocks),
+            Implementation::TTable => t_table::decrypt_blocks(&self.round_keys, blocks),
+            Implementation::Lookup => {
+                for block in blocks.iter_mut() {
+                    *block = self.decrypt_block(block);
+                }
+            },
+        }
+    }
+}
+
+/// Functions choosing the implementation used for encrypting and decrypting.
+impl AESCore {
+    fn supported_implementation(&self) -> Implementation {
+        //! Returns the chosen implementation, or the portable fallback
+        //! if the hardware implementation is chosen and the processor doesn't support it.
+
+        if self.implementation != Implementation::Hardware || Self::is_hardware_supported() {
+            self.implementation
+        } else if cfg!(feature = "constant-time") {
+            Implementation::Bitsliced
+        } else {
+            Implementation::Lookup
+        }
+    }
+}
+
+/// Functions for encrypting and decrypting used in the AES algorithm.
+impl AESCore {
+    fn encrypt_block(&self, block: &[u8; 16]) -> [u8; 16] {
+        //! Encrypts the given block of data with the S-Box lookup tables.
+
         // convert block to state
         let mut state: [[u8; 4]; 4] = [[0; 4]; 4];
         for r in 0..4 {
@@ -191,8 +337,8 @@ impl AESCore {
         out_block
     }
 
-    pub fn decrypt(&self, block: &[u8; 16]) -> [u8; 16] {
-        //! Decrypts the given block of data.
+    fn decrypt_block(&self, block: &[u8; 16]) -> [u8; 16] {
+        //! Decrypts the given block of data with the S-Box lookup tables.
 
         // convert block to state
         let mut state: [[u8; 4]; 4] = [[0; 4]; 4];
@@ -228,10 +374,7 @@ impl AESCore {
         }
         out_block
     }
-}
 
-/// Functions for encrypting and decrypting used in the AES algorithm.
-impl AESCore {
     fn add_round_key(state: &mut [[u8; 4]; 4], round_keys: &[[u8; 4]]) {
         //! Adds the given round key to the state.
 
@@ -458,6 +601,11 @@ impl AESCore {
     fn sub_word(word: &mut [u8; 4]) {
         //! Substitutes the bytes of the word with the S-Box.
 
+        if cfg!(feature = "constant-time") {
+            bitsliced::sub_word(word);
+            return;
+        }
+
         for i in 0..4 {
             word[i] = S_BOX[(word[i] >> 4) as usize][(word[i] & 0b00001111) as usize];
         }
@@ -714,6 +862,53 @@ mod tests {
             0xcc, 0xdd, 0xee, 0xff]);
     }
 
+    #[test]
+    fn implementations() {
+        //! Test that all implementations give the same results
+
+        for key in [
+            AESKey::AES128(core::array::from_fn(|i| i as u8)),
+            AESKey::AES192(core::array::from_fn(|i| (i * 7) as u8)),
+            AESKey::AES256(core::array::from_fn(|i| (i * 13 + 1) as u8)),
+        ] {
+            let mut aes: AESCore = AESCore::new(key);
+            assert_eq!(aes.implementation(), Implementation::Hardware);
+
+            for i in 0..16 {
+                let block: [u8; 16] = core::array::from_fn(|j| (i * 16 + j) as u8);
+                let ciphertext: [u8; 16] = aes.encrypt_block(&block);
+
+                for implementation in [
+                    Implementation::Hardware,
+                    Implementation::Bitsliced,
+                    Implementation::TTable,
+                    Implementation::Lookup,
+                ] {
+                    aes.set_implementation(implementation);
+                    assert_eq!(aes.encrypt(&block), ciphertext);
+                    assert_eq!(aes.decrypt(&ciphertext), block);
+                }
+            }
+
+            // the batches of blocks give the same results as the single blocks
+            let plaintext: Vec<[u8; 16]> = (0..20).map(|i| core::array::from_fn(|j| (i * 16 + j) as u8)).collect();
+            let ciphertext: Vec<[u8; 16]> = plaintext.iter().map(|block| aes.encrypt_block(block)).collect();
+            for implementation in [
+                Implementation::Hardware,
+                Implementation::Bitsliced,
+                Implementation::TTable,
+                Implementation::Lookup,
+            ] {
+                aes.set_implementation(implementation);
+                let mut blocks: Vec<[u8; 16]> = plaintext.clone();
+                aes.encrypt_blocks(&mut blocks);
+                assert_eq!(blocks, ciphertext);
+                aes.decrypt_blocks(&mut blocks);
+                assert_eq!(blocks, plaintext);
+            }
+        }
+    }
+
     #[test]
     fn set_key() {
         //! Test changing the key
@@ -0,0 +1,291 @@
+//! A module containing the implementation of the AES algorithm with the AES-NI instructions of x86_64 processors.
+//!
+//! Up to eight blocks are encrypted at once, so the latency of the instructions is hidden.
+//! On processors with the VAES instructions, two blocks are processed with each instruction.
+
+
+
+
+
+// IMPORTS
+
+use std::arch::x86_64::{
+    __m128i,
+    __m256i,
+    _mm256_aesdec_epi128,
+    _mm256_aesdeclast_epi128,
+    _mm256_aesenc_epi128,
+    _mm256_aesenclast_epi128,
+    _mm256_broadcastsi128_si256,
+    _mm256_loadu_si256,
+    _mm256_setzero_si256,
+    _mm256_storeu_si256,
+    _mm256_xor_si256,
+    _mm_aesdec_si128,
+    _mm_aesdeclast_si128,
+    _mm_aesenc_si128,
+    _mm_aesenclast_si128,
+    _mm_aesimc_si128,
+    _mm_loadu_si128,
+    _mm_setzero_si128,
+    _mm_storeu_si128,
+    _mm_xor_si128,
+};
+
+use super::RoundKeys;
+
+
+
+
+
+// FUNCTIONS
+
+pub(super) fn is_supported() -> bool {
+    //! Returns whether the processor supports the AES-NI instructions.
+
+    is_x86_feature_detected!("aes")
+}
+
+#[target_feature(enable = "aes")]
+pub(super) fn encrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Encrypts the given blocks in place.
+    //! The processor must support the AES-NI instructions, see `is_supported`.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to encrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [__m128i; MAX_ROUND_KEYS] = load_round_keys(round_keys);
+
+    let mut processed: usize = 0;
+    if is_vaes_supported() {
+        // SAFETY: the processor supports the VAES and AVX2 instructions
+        processed = unsafe { encrypt_pairs(&keys, rounds, blocks) };
+    }
+
+    for chunk in blocks[processed..].chunks_mut(PARALLEL_BLOCKS) {
+        let mut states: [__m128i; PARALLEL_BLOCKS] = [_mm_setzero_si128(); PARALLEL_BLOCKS];
+        for (state, block) in states.iter_mut().zip(chunk.iter()) {
+            // SAFETY: the block is 16 bytes long and unaligned loads are used
+            *state = _mm_xor_si128(unsafe { _mm_loadu_si128(block.as_ptr().cast()) }, keys[0]);
+        }
+
+        for round in 1..rounds {
+            for state in states[..chunk.len()].iter_mut() {
+                *state = _mm_aesenc_si128(*state, keys[round]);
+            }
+        }
+
+        for (state, block) in states.iter().zip(chunk.iter_mut()) {
+            // SAFETY: the block is 16 bytes long and unaligned stores are used
+            unsafe { _mm_storeu_si128(block.as_mut_ptr().cast(), _mm_aesenclast_si128(*state, keys[rounds])) };
+        }
+    }
+}
+
+#[target_feature(enable = "aes")]
+pub(super) fn decrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Decrypts the given blocks in place.
+    //! The processor must support the AES-NI instructions, see `is_supported`.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to decrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [__m128i; MAX_ROUND_KEYS] = load_decryption_keys(round_keys);
+
+    let mut processed: usize = 0;
+    if is_vaes_supported() {
+        // SAFETY: the processor supports the VAES and AVX2 instructions
+        processed = unsafe { decrypt_pairs(&keys, rounds, blocks) };
+    }
+
+    for chunk in blocks[processed..].chunks_mut(PARALLEL_BLOCKS) {
+        let mut states: [__m128i; PARALLEL_BLOCKS] = [_mm_setzero_si128(); PARALLEL_BLOCKS];
+        for (state, block) in states.iter_mut().zip(chunk.iter()) {
+            // SAFETY: the block is 16 bytes long and unaligned loads are used
+            *state = _mm_xor_si128(unsafe { _mm_loadu_si128(block.as_ptr().cast()) }, keys[0]);
+        }
+
+        for round in 1..rounds {
+            for state in states[..chunk.len()].iter_mut() {
+                *state = _mm_aesdec_si128(*state, keys[round]);
+            }
+        }
+
+        for (state, block) in states.iter().zip(chunk.iter_mut()) {
+            // SAFETY: the block is 16 bytes long and unaligned stores are used
+            unsafe { _mm_storeu_si128(block.as_mut_ptr().cast(), _mm_aesdeclast_si128(*state, keys[rounds])) };
+        }
+    }
+}
+
+fn is_vaes_supported() -> bool {
+    //! Returns whether the processor supports the VAES instructions on 256-bit registers.
+
+    is_x86_feature_detected!("vaes") && is_x86_feature_detected!("avx2")
+}
+
+#[target_feature(enable = "aes,avx2,vaes")]
+fn encrypt_pairs(keys: &[__m128i; MAX_ROUND_KEYS], rounds: usize, blocks: &mut [[u8; 16]]) -> usize {
+    //! Encrypts the blocks two at a time with the VAES instructions.
+    //! # Returns
+    //! The number of blocks encrypted, the last block is left if the number of blocks is odd.
+
+    let pair_blocks: usize = blocks.len() - blocks.len() % 2;
+    let mut wide_keys: [__m256i; MAX_ROUND_KEYS] = [_mm256_setzero_si256(); MAX_ROUND_KEYS];
+    for (wide_key, key) in wide_keys.iter_mut().zip(keys.iter()) {
+        *wide_key = _mm256_broadcastsi128_si256(*key);
+    }
+
+    for chunk in blocks[..pair_blocks].chunks_mut(PARALLEL_BLOCKS) {
+        let pairs: usize = chunk.len() / 2;
+
+        let mut states: [__m256i; PARALLEL_BLOCKS / 2] = [_mm256_setzero_si256(); PARALLEL_BLOCKS / 2];
+        for (state, pair) in states.iter_mut().zip(chunk.chunks_exact(2)) {
+            // SAFETY: the two blocks are 32 bytes long and unaligned loads are used
+            *state = _mm256_xor_si256(unsafe { _mm256_loadu_si256(pair.as_ptr().cast()) }, wide_keys[0]);
+        }
+
+        for round in 1..rounds {
+            for state in states[..pairs].iter_mut() {
+                *state = _mm256_aesenc_epi128(*state, wide_keys[round]);
+            }
+        }
+
+        for (state, pair) in states.iter().zip(chunk.chunks_exact_mut(2)) {
+            // SAFETY: the two blocks are 32 bytes long and unaligned stores are used
+            unsafe { _mm256_storeu_si256(pair.as_mut_ptr().cast(), _mm256_aesenclast_epi128(*state, wide_keys[rounds])) };
+        }
+    }
+
+    pair_blocks
+}
+
+#[target_feature(enable = "aes,avx2,vaes")]
+fn decrypt_pairs(keys: &[__m128i; MAX_ROUND_KEYS], rounds: usize, blocks: &mut [[u8; 16]]) -> usize {
+    //! Decrypts the blocks two at a time with the VAES instructions.
+    //! # Returns
+    //! The number of blocks decrypted, the last block is left if the number of blocks is odd.
+
+    let pair_blocks: usize = blocks.len() - blocks.len() % 2;
+    let mut wide_keys: [__m256i; MAX_ROUND_KEYS] = [_mm256_setzero_si256(); MAX_ROUND_KEYS];
+    for (wide_key, key) in wide_keys.iter_mut().zip(keys.iter()) {
+        *wide_key = _mm256_broadcastsi128_si256(*key);
+    }
+
+    for chunk in blocks[..pair_blocks].chunks_mut(PARALLEL_BLOCKS) {
+        let pairs: usize = chunk.len() / 2;
+
+        let mut states: [__m256i; PARALLEL_BLOCKS / 2] = [_mm256_setzero_si256(); PARALLEL_BLOCKS / 2];
+        for (state, pair) in states.iter_mut().zip(chunk.chunks_exact(2)) {
+            // SAFETY: the two blocks are 32 bytes long and unaligned loads are used
+            *state = _mm256_xor_si256(unsafe { _mm256_loadu_si256(pair.as_ptr().cast()) }, wide_keys[0]);
+        }
+
+        for round in 1..rounds {
+            for state in states[..pairs].iter_mut() {
+                *state = _mm256_aesdec_epi128(*state, wide_keys[round]);
+            }
+        }
+
+        for (state, pair) in states.iter().zip(chunk.chunks_exact_mut(2)) {
+            // SAFETY: the two blocks are 32 bytes long and unaligned stores are used
+            unsafe { _mm256_storeu_si256(pair.as_mut_ptr().cast(), _mm256_aesdeclast_epi128(*state, wide_keys[rounds])) };
+        }
+    }
+
+    pair_blocks
+}
+
+#[target_feature(enable = "aes")]
+fn load_round_keys(round_keys: &RoundKeys) -> [__m128i; MAX_ROUND_KEYS] {
+    //! Loads the round keys into registers, the unused registers are left zero.
+
+    let mut keys: [__m128i; MAX_ROUND_KEYS] = [_mm_setzero_si128(); MAX_ROUND_KEYS];
+    for round in 0..(round_keys.len() / 4) {
+        // SAFETY: the four words of the round key are 16 consecutive bytes
+        keys[round] = unsafe { _mm_loadu_si128(round_keys[(round * 4)..].as_ptr().cast()) };
+    }
+
+    keys
+}
+
+#[target_feature(enable = "aes")]
+fn load_decryption_keys(round_keys: &RoundKeys) -> [__m128i; MAX_ROUND_KEYS] {
+    //! Loads the round keys of the equivalent inverse cipher into registers.
+    //! The keys are used in the reverse order and inverse mix columns is applied to all but the first and the last one.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let encryption_keys: [__m128i; MAX_ROUND_KEYS] = load_round_keys(round_keys);
+
+    let mut keys: [__m128i; MAX_ROUND_KEYS] = [_mm_setzero_si128(); MAX_ROUND_KEYS];
+    keys[0] = encryption_keys[rounds];
+    for round in 1..rounds {
+        keys[round] = _mm_aesimc_si128(encryption_keys[rounds - round]);
+    }
+    keys[rounds] = encryption_keys[0];
+
+    keys
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of round keys of AES-256, the most of all key sizes.
+const MAX_ROUND_KEYS: usize = 15;
+
+/// The number of blocks encrypted at once.
+const PARALLEL_BLOCKS: usize = 8;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        AESCore,
+        AESKey,
+    };
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        //! Test the encryption and decryption of several blocks against the byte-oriented implementation
+
+        if !is_supported() {
+            return;
+        }
+
+        for key in [
+            AESKey::AES128(core::array::from_fn(|i| i as u8)),
+            AESKey::AES192(core::array::from_fn(|i| (i * 7) as u8)),
+            AESKey::AES256(core::array::from_fn(|i| (i * 13 + 1) as u8)),
+        ] {
+            let aes: AESCore = AESCore::new(key);
+
+            // the block counts cover full and partial groups, and an odd block left by the VAES path
+            for block_count in 0..20 {
+                let plaintext: Vec<[u8; 16]> = (0..block_count)
+                    .map(|i| core::array::from_fn(|j| (i * 16 + j) as u8))
+                    .collect();
+                let expected: Vec<[u8; 16]> = plaintext.iter().map(|block| aes.encrypt_block(block)).collect();
+
+                let mut blocks: Vec<[u8; 16]> = plaintext.clone();
+                // SAFETY: the processor supports the AES-NI instructions
+                unsafe { encrypt_blocks(&aes.round_keys, &mut blocks) };
+                assert_eq!(blocks, expected);
+
+                // SAFETY: the processor supports the AES-NI instructions
+                unsafe { decrypt_blocks(&aes.round_keys, &mut blocks) };
+                assert_eq!(blocks, plaintext);
+            }
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! A module containing the implementation of the AES algorithm with the cryptographic extension of ARMv8 processors.
+//!
+//! Up to eight blocks are encrypted at once, so the latency of the instructions is hidden.
+
+
+
+
+
+// IMPORTS
+
+use std::arch::aarch64::{
+    uint8x16_t,
+    vaesdq_u8,
+    vaeseq_u8,
+    vaesimcq_u8,
+    vaesmcq_u8,
+    vdupq_n_u8,
+    veorq_u8,
+    vld1q_u8,
+    vst1q_u8,
+};
+use std::arch::is_aarch64_feature_detected;
+
+use super::RoundKeys;
+
+
+
+
+
+// FUNCTIONS
+
+pub(super) fn is_supported() -> bool {
+    //! Returns whether the processor supports the AES instructions.
+
+    is_aarch64_feature_detected!("aes")
+}
+
+#[target_feature(enable = "aes")]
+pub(super) fn encrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Encrypts the given blocks in place.
+    //! The processor must support the AES instructions, see `is_supported`.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to encrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [uint8x16_t; MAX_ROUND_KEYS] = load_round_keys(round_keys);
+
+    for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+        let mut states: [uint8x16_t; PARALLEL_BLOCKS] = [vdupq_n_u8(0); PARALLEL_BLOCKS];
+        for (state, block) in states.iter_mut().zip(chunk.iter()) {
+            // SAFETY: the block is 16 bytes long
+            *state = unsafe { vld1q_u8(block.as_ptr()) };
+        }
+
+        // AESE adds the round key before substituting the bytes and shifting the rows
+        for round in 0..(rounds - 1) {
+            for state in states[..chunk.len()].iter_mut() {
+                *state = vaesmcq_u8(vaeseq_u8(*state, keys[round]));
+            }
+        }
+
+        for (state, block) in states.iter().zip(chunk.iter_mut()) {
+            // SAFETY: the block is 16 bytes long
+            unsafe { vst1q_u8(block.as_mut_ptr(), veorq_u8(vaeseq_u8(*state, keys[rounds - 1]), keys[rounds])) };
+        }
+    }
+}
+
+#[target_feature(enable = "aes")]
+pub(super) fn decrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Decrypts the given blocks in place.
+    //! The processor must support the AES instructions, see `is_supported`.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to decrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [uint8x16_t; MAX_ROUND_KEYS] = load_decryption_keys(round_keys);
+
+    for chunk in blocks.chunks_mut(PARALLEL_BLOCKS) {
+        let mut states: [uint8x16_t; PARALLEL_BLOCKS] = [vdupq_n_u8(0); PARALLEL_BLOCKS];
+        for (state, block) in states.iter_mut().zip(chunk.iter()) {
+            // SAFETY: the block is 16 bytes long
+            *state = unsafe { vld1q_u8(block.as_ptr()) };
+        }
+
+        // AESD adds the round key before inverse shifting the rows and inverse substituting the bytes
+        for round in 0..(rounds - 1) {
+            for state in states[..chunk.len()].iter_mut() {
+                *state = vaesimcq_u8(vaesdq_u8(*state, keys[round]));
+            }
+        }
+
+        for (state, block) in states.iter().zip(chunk.iter_mut()) {
+            // SAFETY: the block is 16 bytes long
+            unsafe { vst1q_u8(block.as_mut_ptr(), veorq_u8(vaesdq_u8(*state, keys[rounds - 1]), keys[rounds])) };
+        }
+    }
+}
+
+#[target_feature(enable = "aes")]
+fn load_round_keys(round_keys: &RoundKeys) -> [uint8x16_t; MAX_ROUND_KEYS] {
+    //! Loads the round keys into registers, the unused registers are left zero.
+
+    let mut keys: [uint8x16_t; MAX_ROUND_KEYS] = [vdupq_n_u8(0); MAX_ROUND_KEYS];
+    for round in 0..(round_keys.len() / 4) {
+        // SAFETY: the four words of the round key are 16 consecutive bytes
+        keys[round] = unsafe { vld1q_u8(round_keys[(round * 4)..].as_ptr().cast()) };
+    }
+
+    keys
+}
+
+#[target_feature(enable = "aes")]
+fn load_decryption_keys(round_keys: &RoundKeys) -> [uint8x16_t; MAX_ROUND_KEYS] {
+    //! Loads the round keys of the equivalent inverse cipher into registers.
+    //! The keys are used in the reverse order and inverse mix columns is applied to all but the first and the last one.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let encryption_keys: [uint8x16_t; MAX_ROUND_KEYS] = load_round_keys(round_keys);
+
+    let mut keys: [uint8x16_t; MAX_ROUND_KEYS] = [vdupq_n_u8(0); MAX_ROUND_KEYS];
+    keys[0] = encryption_keys[rounds];
+    for round in 1..rounds {
+        keys[round] = vaesimcq_u8(encryption_keys[rounds - round]);
+    }
+    keys[rounds] = encryption_keys[0];
+
+    keys
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of round keys of AES-256, the most of all key sizes.
+const MAX_ROUND_KEYS: usize = 15;
+
+/// The number of blocks encrypted at once.
+const PARALLEL_BLOCKS: usize = 8;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        AESCore,
+        AESKey,
+    };
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        //! Test the encryption and decryption of several blocks against the byte-oriented implementation
+
+        if !is_supported() {
+            return;
+        }
+
+        for key in [
+            AESKey::AES128(core::array::from_fn(|i| i as u8)),
+            AESKey::AES192(core::array::from_fn(|i| (i * 7) as u8)),
+            AESKey::AES256(core::array::from_fn(|i| (i * 13 + 1) as u8)),
+        ] {
+            let aes: AESCore = AESCore::new(key);
+
+            // the block counts cover full and partial groups
+            for block_count in 0..20 {
+                let plaintext: Vec<[u8; 16]> = (0..block_count)
+                    .map(|i| core::array::from_fn(|j| (i * 16 + j) as u8))
+                    .collect();
+                let expected: Vec<[u8; 16]> = plaintext.iter().map(|block| aes.encrypt_block(block)).collect();
+
+                let mut blocks: Vec<[u8; 16]> = plaintext.clone();
+                // SAFETY: the processor supports the AES instructions
+                unsafe { encrypt_blocks(&aes.round_keys, &mut blocks) };
+                assert_eq!(blocks, expected);
+
+                // SAFETY: the processor supports the AES instructions
+                unsafe { decrypt_blocks(&aes.round_keys, &mut blocks) };
+                assert_eq!(blocks, plaintext);
+            }
+        }
+    }
+}
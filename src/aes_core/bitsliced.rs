@@ -0,0 +1,546 @@
+//! A module containing a constant-time bitsliced implementation of the AES algorithm.
+//!
+//! Four blocks are processed at once. The state is stored as eight 64-bit planes,
+//! plane `i` holding the bit `i` of all 64 bytes of the blocks (bit `16 * block + position` of the plane).
+//! The S-box is computed with the circuit of Boyar and Peralta, so no memory is indexed by secret data.
+
+
+
+
+
+// DISABLED LINTS
+
+#![allow(clippy::needless_range_loop)]  // better readability
+
+
+
+
+
+// IMPORTS
+
+use super::RoundKeys;
+
+
+
+
+
+// FUNCTIONS
+
+pub(super) fn encrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Encrypts the given blocks in place, four blocks at a time.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to encrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+
+    for chunk in blocks.chunks_mut(BLOCKS_PER_STATE) {
+        let mut state: [u64; 8] = bitslice(chunk);
+
+        add_round_key(&mut state, round_keys, 0);
+        for round in 1..rounds {
+            sub_bytes(&mut state);
+            shift_rows(&mut state);
+            mix_columns(&mut state);
+            add_round_key(&mut state, round_keys, round);
+        }
+        sub_bytes(&mut state);
+        shift_rows(&mut state);
+        add_round_key(&mut state, round_keys, rounds);
+
+        unbitslice(&state, chunk);
+    }
+}
+
+pub(super) fn decrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Decrypts the given blocks in place, four blocks at a time.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to decrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+
+    for chunk in blocks.chunks_mut(BLOCKS_PER_STATE) {
+        let mut state: [u64; 8] = bitslice(chunk);
+
+        add_round_key(&mut state, round_keys, rounds);
+        for round in (1..rounds).rev() {
+            inv_shift_rows(&mut state);
+            inv_sub_bytes(&mut state);
+            add_round_key(&mut state, round_keys, round);
+            inv_mix_columns(&mut state);
+        }
+        inv_shift_rows(&mut state);
+        inv_sub_bytes(&mut state);
+        add_round_key(&mut state, round_keys, 0);
+
+        unbitslice(&state, chunk);
+    }
+}
+
+pub(super) fn sub_word(word: &mut [u8; 4]) {
+    //! Substitutes the bytes of the word with the S-Box, used in the key expansion.
+
+    let mut planes: [u64; 8] = [0; 8];
+    for i in 0..4 {
+        for bit in 0..8 {
+            planes[bit] |= (((word[i] >> bit) & 1) as u64) << i;
+        }
+    }
+
+    s_box(&mut planes);
+
+    for i in 0..4 {
+        word[i] = 0;
+        for bit in 0..8 {
+            word[i] |= (((planes[bit] >> i) & 1) as u8) << bit;
+        }
+    }
+}
+
+fn add_round_key(state: &mut [u64; 8], round_keys: &RoundKeys, round: usize) {
+    //! Adds the round key of the given round to all blocks of the state.
+
+    let mut key_block: [u8; 16] = [0; 16];
+    for (word, bytes) in round_keys[(round * 4)..((round + 1) * 4)].iter().zip(key_block.chunks_exact_mut(4)) {
+        bytes.copy_from_slice(word);
+    }
+
+    let round_key: [u64; 8] = bitslice(&[key_block; BLOCKS_PER_STATE]);
+    for i in 0..8 {
+        state[i] ^= round_key[i];
+    }
+}
+
+fn sub_bytes(state: &mut [u64; 8]) {
+    //! Substitutes the bytes of the state with the S-Box.
+
+    s_box(state);
+}
+
+fn inv_sub_bytes(state: &mut [u64; 8]) {
+    //! Inverse substitutes the bytes of the state with the inverse S-Box.
+    //! The inverse S-Box is computed with the S-Box, as the inverse of the affine transformation
+    //! applied before and after the S-Box turns it into the inverse S-Box.
+
+    inv_affine(state);
+    s_box(state);
+    inv_affine(state);
+}
+
+fn shift_rows(state: &mut [u64; 8]) {
+    //! Shifts the rows of the state.
+    //! The byte in the row `r` and the column `c` is at the position `r + 4 * c` of the block.
+
+    for plane in state.iter_mut() {
+        let x: u64 = *plane;
+        *plane = (x & lanes(0x1111))
+            | ((x >> 4) & lanes(0x0222)) | ((x << 12) & lanes(0x2000))
+            | ((x >> 8) & lanes(0x0044)) | ((x << 8) & lanes(0x4400))
+            | ((x >> 12) & lanes(0x0008)) | ((x << 4) & lanes(0x8880));
+    }
+}
+
+fn inv_shift_rows(state: &mut [u64; 8]) {
+    //! Inverse shifts the rows of the state.
+
+    for plane in state.iter_mut() {
+        let x: u64 = *plane;
+        *plane = (x & lanes(0x1111))
+            | ((x << 4) & lanes(0x2220)) | ((x >> 12) & lanes(0x0002))
+            | ((x << 8) & lanes(0x4400)) | ((x >> 8) & lanes(0x0044))
+            | ((x << 12) & lanes(0x8000)) | ((x >> 4) & lanes(0x0888));
+    }
+}
+
+fn mix_columns(state: &mut [u64; 8]) {
+    //! Mixes the columns of the state.
+    //! Every row is multiplied by 2 and added to the next row multiplied by 3 and the other two rows.
+
+    let rotated: [u64; 8] = state.map(|plane| rotate_rows(plane, 1));
+
+    let mut sum: [u64; 8] = [0; 8];
+    for i in 0..8 {
+        sum[i] = state[i] ^ rotated[i];
+    }
+    let doubled: [u64; 8] = xtime(&sum);
+
+    for i in 0..8 {
+        state[i] = doubled[i] ^ rotated[i] ^ rotate_rows(state[i], 2) ^ rotate_rows(state[i], 3);
+    }
+}
+
+fn inv_mix_columns(state: &mut [u64; 8]) {
+    //! Inverse mixes the columns of the state.
+    //! The inverse matrix is the product of the matrix of mix columns and the matrix with the rows `[5, 0, 4, 0]`,
+    //! so every row is first added to the row two places further multiplied by 4.
+
+    let mut sum: [u64; 8] = [0; 8];
+    for i in 0..8 {
+        sum[i] = state[i] ^ rotate_rows(state[i], 2);
+    }
+    let quadrupled: [u64; 8] = xtime(&xtime(&sum));
+
+    for i in 0..8 {
+        state[i] ^= quadrupled[i];
+    }
+    mix_columns(state);
+}
+
+fn rotate_rows(plane: u64, shift: u32) -> u64 {
+    //! Moves the byte of every column from the row `r + shift` to the row `r`.
+
+    ((plane >> shift) & (0x1111_1111_1111_1111 * ((1 << (4 - shift)) - 1)))
+        | ((plane << (4 - shift)) & (0x1111_1111_1111_1111 * (0xf ^ ((1 << (4 - shift)) - 1))))
+}
+
+fn xtime(state: &[u64; 8]) -> [u64; 8] {
+    //! Multiplies all bytes of the state by 2 in the Galois field.
+
+    [
+        state[7],
+        state[0] ^ state[7],
+        state[1],
+        state[2] ^ state[7],
+        state[3] ^ state[7],
+        state[4],
+        state[5],
+        state[6],
+    ]
+}
+
+fn inv_affine(state: &mut [u64; 8]) {
+    //! Applies the inverse of the affine transformation of the S-Box, including the constant 0x63.
+
+    let q: [u64; 8] = [
+        !state[0],
+        !state[1],
+        state[2],
+        state[3],
+        state[4],
+        !state[5],
+        !state[6],
+        state[7],
+    ];
+
+    state[7] = q[1] ^ q[4] ^ q[6];
+    state[6] = q[0] ^ q[3] ^ q[5];
+    state[5] = q[7] ^ q[2] ^ q[4];
+    state[4] = q[6] ^ q[1] ^ q[3];
+    state[3] = q[5] ^ q[0] ^ q[2];
+    state[2] = q[4] ^ q[7] ^ q[1];
+    state[1] = q[3] ^ q[6] ^ q[0];
+    state[0] = q[2] ^ q[5] ^ q[7];
+}
+
+fn s_box(state: &mut [u64; 8]) {
+    //! Computes the S-Box on all bytes of the state with the circuit from
+    //! "A new combinational logic minimization technique with applications to cryptology" by Boyar and Peralta.
+    //! The inputs `x` and the outputs `s` are numbered from the highest bit.
+
+    let x0: u64 = state[7];
+    let x1: u64 = state[6];
+    let x2: u64 = state[5];
+    let x3: u64 = state[4];
+    let x4: u64 = state[3];
+    let x5: u64 = state[2];
+    let x6: u64 = state[1];
+    let x7: u64 = state[0];
+
+    // top linear transformation
+    let y14: u64 = x3 ^ x5;
+    let y13: u64 = x0 ^ x6;
+    let y9: u64 = x0 ^ x3;
+    let y8: u64 = x0 ^ x5;
+    let t0: u64 = x1 ^ x2;
+    let y1: u64 = t0 ^ x7;
+    let y4: u64 = y1 ^ x3;
+    let y12: u64 = y13 ^ y14;
+    let y2: u64 = y1 ^ x0;
+    let y5: u64 = y1 ^ x6;
+    let y3: u64 = y5 ^ y8;
+    let t1: u64 = x4 ^ y12;
+    let y15: u64 = t1 ^ x5;
+    let y20: u64 = t1 ^ x1;
+    let y6: u64 = y15 ^ x7;
+    let y10: u64 = y15 ^ t0;
+    let y11: u64 = y20 ^ y9;
+    let y7: u64 = x7 ^ y11;
+    let y17: u64 = y10 ^ y11;
+    let y19: u64 = y10 ^ y8;
+    let y16: u64 = t0 ^ y11;
+    let y21: u64 = y13 ^ y16;
+    let y18: u64 = x0 ^ y16;
+
+    // non-linear section
+    let t2: u64 = y12 & y15;
+    let t3: u64 = y3 & y6;
+    let t4: u64 = t3 ^ t2;
+    let t5: u64 = y4 & x7;
+    let t6: u64 = t5 ^ t2;
+    let t7: u64 = y13 & y16;
+    let t8: u64 = y5 & y1;
+    let t9: u64 = t8 ^ t7;
+    let t10: u64 = y2 & y7;
+    let t11: u64 = t10 ^ t7;
+    let t12: u64 = y9 & y11;
+    let t13: u64 = y14 & y17;
+    let t14: u64 = t13 ^ t12;
+    let t15: u64 = y8 & y10;
+    let t16: u64 = t15 ^ t12;
+    let t17: u64 = t4 ^ t14;
+    let t18: u64 = t6 ^ t16;
+    let t19: u64 = t9 ^ t14;
+    let t20: u64 = t11 ^ t16;
+    let t21: u64 = t17 ^ y20;
+    let t22: u64 = t18 ^ y19;
+    let t23: u64 = t19 ^ y21;
+    let t24: u64 = t20 ^ y18;
+
+    let t25: u64 = t21 ^ t22;
+    let t26: u64 = t21 & t23;
+    let t27: u64 = t24 ^ t26;
+    let t28: u64 = t25 & t27;
+    let t29: u64 = t28 ^ t22;
+    let t30: u64 = t23 ^ t24;
+    let t31: u64 = t22 ^ t26;
+    let t32: u64 = t31 & t30;
+    let t33: u64 = t32 ^ t24;
+    let t34: u64 = t23 ^ t33;
+    let t35: u64 = t27 ^ t33;
+    let t36: u64 = t24 & t35;
+    let t37: u64 = t36 ^ t34;
+    let t38: u64 = t27 ^ t36;
+    let t39: u64 = t29 & t38;
+    let t40: u64 = t25 ^ t39;
+
+    let t41: u64 = t40 ^ t37;
+    let t42: u64 = t29 ^ t33;
+    let t43: u64 = t29 ^ t40;
+    let t44: u64 = t33 ^ t37;
+    let t45: u64 = t42 ^ t41;
+    let z0: u64 = t44 & y15;
+    let z1: u64 = t37 & y6;
+    let z2: u64 = t33 & x7;
+    let z3: u64 = t43 & y16;
+    let z4: u64 = t40 & y1;
+    let z5: u64 = t29 & y7;
+    let z6: u64 = t42 & y11;
+    let z7: u64 = t45 & y17;
+    let z8: u64 = t41 & y10;
+    let z9: u64 = t44 & y12;
+    let z10: u64 = t37 & y3;
+    let z11: u64 = t33 & y4;
+    let z12: u64 = t43 & y13;
+    let z13: u64 = t40 & y5;
+    let z14: u64 = t29 & y2;
+    let z15: u64 = t42 & y9;
+    let z16: u64 = t45 & y14;
+    let z17: u64 = t41 & y8;
+
+    // bottom linear transformation
+    let t46: u64 = z15 ^ z16;
+    let t47: u64 = z10 ^ z11;
+    let t48: u64 = z5 ^ z13;
+    let t49: u64 = z9 ^ z10;
+    let t50: u64 = z2 ^ z12;
+    let t51: u64 = z2 ^ z5;
+    let t52: u64 = z7 ^ z8;
+    let t53: u64 = z0 ^ z3;
+    let t54: u64 = z6 ^ z7;
+    let t55: u64 = z16 ^ z17;
+    let t56: u64 = z12 ^ t48;
+    let t57: u64 = t50 ^ t53;
+    let t58: u64 = z4 ^ t46;
+    let t59: u64 = z3 ^ t54;
+    let t60: u64 = t46 ^ t57;
+    let t61: u64 = z14 ^ t57;
+    let t62: u64 = t52 ^ t58;
+    let t63: u64 = t49 ^ t58;
+    let t64: u64 = z4 ^ t59;
+    let t65: u64 = t61 ^ t62;
+    let t66: u64 = z1 ^ t63;
+    let s0: u64 = t59 ^ t63;
+    let s6: u64 = t56 ^ !t62;
+    let s7: u64 = t48 ^ !t60;
+    let t67: u64 = t64 ^ t65;
+    let s3: u64 = t53 ^ t66;
+    let s4: u64 = t51 ^ t66;
+    let s5: u64 = t47 ^ t65;
+    let s1: u64 = t64 ^ !s3;
+    let s2: u64 = t55 ^ !t67;
+
+    *state = [s7, s6, s5, s4, s3, s2, s1, s0];
+}
+
+fn bitslice(blocks: &[[u8; 16]]) -> [u64; 8] {
+    //! Converts up to four blocks into the bitsliced state, the missing blocks are filled with zeros.
+
+    let mut state: [u64; 8] = [0; 8];
+    for (block_index, block) in blocks.iter().enumerate() {
+        for half in 0..2 {
+            let group: usize = block_index * 2 + half;
+            let transposed: u64 = transpose(u64::from_le_bytes(block[(half * 8)..((half + 1) * 8)].try_into().unwrap()));
+            for bit in 0..8 {
+                state[bit] |= ((transposed >> (bit * 8)) & 0xff) << (group * 8);
+            }
+        }
+    }
+
+    state
+}
+
+fn unbitslice(state: &[u64; 8], blocks: &mut [[u8; 16]]) {
+    //! Converts the bitsliced state back into the blocks, only as many blocks as given are written.
+
+    for (block_index, block) in blocks.iter_mut().enumerate() {
+        for half in 0..2 {
+            let group: usize = block_index * 2 + half;
+            let mut transposed: u64 = 0;
+            for bit in 0..8 {
+                transposed |= ((state[bit] >> (group * 8)) & 0xff) << (bit * 8);
+            }
+            block[(half * 8)..((half + 1) * 8)].copy_from_slice(&transpose(transposed).to_le_bytes());
+        }
+    }
+}
+
+fn transpose(x: u64) -> u64 {
+    //! Transposes the 8x8 bit matrix, the bit `j` of the byte `i` becomes the bit `i` of the byte `j`.
+
+    let mut x: u64 = x;
+    let mut t: u64;
+
+    t = (x ^ (x >> 7)) & 0x00aa_00aa_00aa_00aa;
+    x ^= t ^ (t << 7);
+    t = (x ^ (x >> 14)) & 0x0000_cccc_0000_cccc;
+    x ^= t ^ (t << 14);
+    t = (x ^ (x >> 28)) & 0x0000_0000_f0f0_f0f0;
+    x ^= t ^ (t << 28);
+
+    x
+}
+
+const fn lanes(mask: u16) -> u64 {
+    //! Repeats the 16-bit mask for each of the blocks of the state.
+
+    mask as u64 * 0x0001_0001_0001_0001
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of blocks processed at once.
+const BLOCKS_PER_STATE: usize = 4;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        AESCore,
+        AESKey,
+        INV_S_BOX,
+        S_BOX,
+    };
+
+    #[test]
+    fn s_box() {
+        //! Test the S-Box and inverse S-Box circuits on all bytes against the tables
+
+        let mut blocks: [[u8; 16]; 16] = [[0; 16]; 16];
+        for i in 0..256 {
+            blocks[i / 16][i % 16] = i as u8;
+        }
+
+        for chunk in blocks.chunks(BLOCKS_PER_STATE) {
+            let mut substituted: [u64; 8] = bitslice(chunk);
+            super::s_box(&mut substituted);
+            let mut substituted_blocks: [[u8; 16]; 4] = [[0; 16]; 4];
+            unbitslice(&substituted, &mut substituted_blocks);
+
+            let mut inverted: [u64; 8] = bitslice(chunk);
+            inv_sub_bytes(&mut inverted);
+            let mut inverted_blocks: [[u8; 16]; 4] = [[0; 16]; 4];
+            unbitslice(&inverted, &mut inverted_blocks);
+
+            for (block, (substituted, inverted)) in chunk.iter().zip(substituted_blocks.iter().zip(inverted_blocks.iter())) {
+                for i in 0..16 {
+                    assert_eq!(substituted[i], S_BOX[(block[i] >> 4) as usize][(block[i] & 0x0f) as usize]);
+                    assert_eq!(inverted[i], INV_S_BOX[(block[i] >> 4) as usize][(block[i] & 0x0f) as usize]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sub_word() {
+        //! Test the sub word function
+
+        let mut word: [u8; 4] = [0x19, 0xa0, 0x9a, 0xe9];
+        super::sub_word(&mut word);
+        assert_eq!(word, [0xd4, 0xe0, 0xb8, 0x1e]);
+    }
+
+    #[test]
+    fn shift_rows_and_mix_columns() {
+        //! Test the bitsliced shift rows and mix columns against the byte-oriented functions
+
+        let blocks: [[u8; 16]; 4] = core::array::from_fn(|i| core::array::from_fn(|j| (i * 67 + j * 29 + 5) as u8));
+        let mut expected: [[u8; 16]; 4] = blocks;
+        let mut state: [u64; 8] = bitslice(&blocks);
+
+        shift_rows(&mut state);
+        mix_columns(&mut state);
+        for block in expected.iter_mut() {
+            let mut byte_state: [[u8; 4]; 4] = core::array::from_fn(|r| core::array::from_fn(|c| block[r + c * 4]));
+            AESCore::shift_rows(&mut byte_state);
+            AESCore::mix_columns(&mut byte_state);
+            *block = core::array::from_fn(|i| byte_state[i % 4][i / 4]);
+        }
+        let mut result: [[u8; 16]; 4] = [[0; 16]; 4];
+        unbitslice(&state, &mut result);
+        assert_eq!(result, expected);
+
+        inv_mix_columns(&mut state);
+        inv_shift_rows(&mut state);
+        unbitslice(&state, &mut result);
+        assert_eq!(result, blocks);
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        //! Test the encryption and decryption of several blocks against the byte-oriented implementation
+
+        for key in [
+            AESKey::AES128(core::array::from_fn(|i| i as u8)),
+            AESKey::AES192(core::array::from_fn(|i| (i * 7) as u8)),
+            AESKey::AES256(core::array::from_fn(|i| (i * 13 + 1) as u8)),
+        ] {
+            let aes: AESCore = AESCore::new(key);
+
+            // the block counts which don't fill the last state are padded
+            for block_count in 0..10 {
+                let plaintext: Vec<[u8; 16]> = (0..block_count)
+                    .map(|i| core::array::from_fn(|j| (i * 16 + j) as u8))
+                    .collect();
+                let expected: Vec<[u8; 16]> = plaintext.iter().map(|block| aes.encrypt_block(block)).collect();
+
+                let mut blocks: Vec<[u8; 16]> = plaintext.clone();
+                encrypt_blocks(&aes.round_keys, &mut blocks);
+                assert_eq!(blocks, expected);
+
+                decrypt_blocks(&aes.round_keys, &mut blocks);
+                assert_eq!(blocks, plaintext);
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! A module containing the implementation of the AES algorithm with T-tables on 32-bit words.
+//!
+//! Every column of the state is a big-endian word. The S-Box, shift rows and mix columns of a round
+//! are combined into four lookups into the T-tables per column.
+//! The tables are indexed by the state, so this implementation isn't constant-time.
+
+
+
+
+
+// DISABLED LINTS
+
+#![allow(clippy::needless_range_loop)]  // better readability
+
+
+
+
+
+// IMPORTS
+
+use super::{
+    RoundKeys,
+    INV_S_BOX,
+    S_BOX,
+};
+
+
+
+
+
+// FUNCTIONS
+
+pub(super) fn encrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Encrypts the given blocks in place.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to encrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [u32; MAX_ROUND_KEY_WORDS] = load_round_keys(round_keys);
+
+    for block in blocks.iter_mut() {
+        let mut state: [u32; 4] = load_block(block, &keys[0..4]);
+
+        for round in 1..rounds {
+            let mut temp: [u32; 4] = [0; 4];
+            for c in 0..4 {
+                temp[c] = ENCRYPTION_TABLES[0][(state[c] >> 24) as usize]
+                    ^ ENCRYPTION_TABLES[1][((state[(c + 1) % 4] >> 16) & 0xff) as usize]
+                    ^ ENCRYPTION_TABLES[2][((state[(c + 2) % 4] >> 8) & 0xff) as usize]
+                    ^ ENCRYPTION_TABLES[3][(state[(c + 3) % 4] & 0xff) as usize]
+                    ^ keys[round * 4 + c];
+            }
+            state = temp;
+        }
+
+        let mut out: [u32; 4] = [0; 4];
+        for c in 0..4 {
+            out[c] = u32::from_be_bytes([
+                s_box(state[c] >> 24),
+                s_box(state[(c + 1) % 4] >> 16),
+                s_box(state[(c + 2) % 4] >> 8),
+                s_box(state[(c + 3) % 4]),
+            ]) ^ keys[rounds * 4 + c];
+        }
+        store_block(&out, block);
+    }
+}
+
+pub(super) fn decrypt_blocks(round_keys: &RoundKeys, blocks: &mut [[u8; 16]]) {
+    //! Decrypts the given blocks in place.
+    //! # Arguments
+    //! * `round_keys` - The expanded key.
+    //! * `blocks` - The blocks to decrypt.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let keys: [u32; MAX_ROUND_KEY_WORDS] = load_decryption_keys(round_keys);
+
+    for block in blocks.iter_mut() {
+        let mut state: [u32; 4] = load_block(block, &keys[0..4]);
+
+        for round in 1..rounds {
+            let mut temp: [u32; 4] = [0; 4];
+            for c in 0..4 {
+                temp[c] = DECRYPTION_TABLES[0][(state[c] >> 24) as usize]
+                    ^ DECRYPTION_TABLES[1][((state[(c + 3) % 4] >> 16) & 0xff) as usize]
+                    ^ DECRYPTION_TABLES[2][((state[(c + 2) % 4] >> 8) & 0xff) as usize]
+                    ^ DECRYPTION_TABLES[3][(state[(c + 1) % 4] & 0xff) as usize]
+                    ^ keys[round * 4 + c];
+            }
+            state = temp;
+        }
+
+        let mut out: [u32; 4] = [0; 4];
+        for c in 0..4 {
+            out[c] = u32::from_be_bytes([
+                inv_s_box(state[c] >> 24),
+                inv_s_box(state[(c + 3) % 4] >> 16),
+                inv_s_box(state[(c + 2) % 4] >> 8),
+                inv_s_box(state[(c + 1) % 4]),
+            ]) ^ keys[rounds * 4 + c];
+        }
+        store_block(&out, block);
+    }
+}
+
+fn load_block(block: &[u8; 16], keys: &[u32]) -> [u32; 4] {
+    //! Converts the block into the columns of the state and adds the first round key.
+
+    let mut state: [u32; 4] = [0; 4];
+    for c in 0..4 {
+        state[c] = u32::from_be_bytes([block[c * 4], block[c * 4 + 1], block[c * 4 + 2], block[c * 4 + 3]]) ^ keys[c];
+    }
+
+    state
+}
+
+fn store_block(state: &[u32; 4], block: &mut [u8; 16]) {
+    //! Converts the columns of the state into the block.
+
+    for c in 0..4 {
+        block[(c * 4)..((c + 1) * 4)].copy_from_slice(&state[c].to_be_bytes());
+    }
+}
+
+fn load_round_keys(round_keys: &RoundKeys) -> [u32; MAX_ROUND_KEY_WORDS] {
+    //! Converts the round keys into words, the unused words are left zero.
+
+    let mut keys: [u32; MAX_ROUND_KEY_WORDS] = [0; MAX_ROUND_KEY_WORDS];
+    for i in 0..round_keys.len() {
+        keys[i] = u32::from_be_bytes(round_keys[i]);
+    }
+
+    keys
+}
+
+fn load_decryption_keys(round_keys: &RoundKeys) -> [u32; MAX_ROUND_KEY_WORDS] {
+    //! Converts the round keys of the equivalent inverse cipher into words.
+    //! The round keys are used in the reverse order and inverse mix columns is applied to all but the first and the last one.
+
+    let rounds: usize = round_keys.len() / 4 - 1;
+    let encryption_keys: [u32; MAX_ROUND_KEY_WORDS] = load_round_keys(round_keys);
+    let mut keys: [u32; MAX_ROUND_KEY_WORDS] = [0; MAX_ROUND_KEY_WORDS];
+    for round in 0..=rounds {
+        for c in 0..4 {
+            let word: u32 = encryption_keys[(rounds - round) * 4 + c];
+            keys[round * 4 + c] = if round == 0 || round == rounds {
+                word
+            } else {
+                // the decryption tables contain the inverse S-Box, which is undone by the S-Box
+                DECRYPTION_TABLES[0][s_box(word >> 24) as usize]
+                    ^ DECRYPTION_TABLES[1][s_box(word >> 16) as usize]
+                    ^ DECRYPTION_TABLES[2][s_box(word >> 8) as usize]
+                    ^ DECRYPTION_TABLES[3][s_box(word) as usize]
+            };
+        }
+    }
+
+    keys
+}
+
+fn s_box(byte: u32) -> u8 {
+    //! Substitutes the lowest byte of the word with the S-Box.
+
+    S_BOX[((byte >> 4) & 0x0f) as usize][(byte & 0x0f) as usize]
+}
+
+fn inv_s_box(byte: u32) -> u8 {
+    //! Substitutes the lowest byte of the word with the inverse S-Box.
+
+    INV_S_BOX[((byte >> 4) & 0x0f) as usize][(byte & 0x0f) as usize]
+}
+
+const fn multiply(a: u8, b: u8) -> u8 {
+    //! Multiplies two bytes in the Galois field of the AES algorithm.
+
+    let mut a: u8 = a;
+    let mut b: u8 = b;
+    let mut product: u8 = 0;
+    while b != 0 {
+        if b & 1 == 1 {
+            product ^= a;
+        }
+        a = if (a >> 7) == 1 { (a << 1) ^ 0x1b } else { a << 1 };
+        b >>= 1;
+    }
+
+    product
+}
+
+const fn tables(s_box: &[[u8; 16]; 16], column: [u8; 4]) -> [[u32; 256]; 4] {
+    //! Computes the T-tables for the given S-Box and the column of the mix columns matrix.
+    //! The table `r` is used for the bytes in the row `r`, so its words are rotated right by `r` bytes.
+
+    let mut tables: [[u32; 256]; 4] = [[0; 256]; 4];
+    let mut i: usize = 0;
+    while i < 256 {
+        let s: u8 = s_box[i >> 4][i & 0x0f];
+        let word: u32 = u32::from_be_bytes([
+            multiply(s, column[0]),
+            multiply(s, column[1]),
+            multiply(s, column[2]),
+            multiply(s, column[3]),
+        ]);
+
+        let mut r: usize = 0;
+        while r < 4 {
+            tables[r][i] = word.rotate_right(r as u32 * 8);
+            r += 1;
+        }
+        i += 1;
+    }
+
+    tables
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of words of the round keys of AES-256, the most of all key sizes.
+const MAX_ROUND_KEY_WORDS: usize = 60;
+
+/// The T-tables of the encryption, combining the S-Box and the first column of the mix columns matrix.
+const ENCRYPTION_TABLES: [[u32; 256]; 4] = tables(&S_BOX, [0x02, 0x01, 0x01, 0x03]);
+
+/// The T-tables of the decryption, combining the inverse S-Box and the first column of the inverse mix columns matrix.
+const DECRYPTION_TABLES: [[u32; 256]; 4] = tables(&INV_S_BOX, [0x0e, 0x09, 0x0d, 0x0b]);
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{
+        AESCore,
+        AESKey,
+    };
+
+    #[test]
+    fn tables() {
+        //! Test entries of the T-tables against the values from the AES proposal
+
+        assert_eq!(ENCRYPTION_TABLES[0][0x00], 0xc66363a5);
+        assert_eq!(ENCRYPTION_TABLES[0][0xff], 0x2c16163a);
+        assert_eq!(ENCRYPTION_TABLES[1][0x00], 0xa5c66363);
+        assert_eq!(DECRYPTION_TABLES[0][0x00], 0x51f4a750);
+        assert_eq!(DECRYPTION_TABLES[0][0xff], 0xd0b85742);
+        assert_eq!(DECRYPTION_TABLES[3][0x00], 0xf4a75051);
+    }
+
+    #[test]
+    fn encrypt_and_decrypt() {
+        //! Test the encryption and decryption of several blocks against the byte-oriented implementation
+
+        for key in [
+            AESKey::AES128(core::array::from_fn(|i| i as u8)),
+            AESKey::AES192(core::array::from_fn(|i| (i * 7) as u8)),
+            AESKey::AES256(core::array::from_fn(|i| (i * 13 + 1) as u8)),
+        ] {
+            let aes: AESCore = AESCore::new(key);
+
+            let plaintext: Vec<[u8; 16]> = (0..10)
+                .map(|i| core::array::from_fn(|j| (i * 16 + j) as u8))
+                .collect();
+            let expected: Vec<[u8; 16]> = plaintext.iter().map(|block| aes.encrypt_block(block)).collect();
+
+            let mut blocks: Vec<[u8; 16]> = plaintext.clone();
+            encrypt_blocks(&aes.round_keys, &mut blocks);
+            assert_eq!(blocks, expected);
+
+            decrypt_blocks(&aes.round_keys, &mut blocks);
+            assert_eq!(blocks, plaintext);
+        }
+    }
+}
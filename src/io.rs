@@ -0,0 +1,412 @@
+//! A module containing `std::io` adapters which encrypt and decrypt data on the fly.
+
+
+
+
+
+// IMPORTS
+
+use std::io::{
+    self,
+    ErrorKind,
+    Read,
+    Write,
+};
+
+use crate::modes::{
+    Decryptor,
+    Encryptor,
+    ModeError,
+};
+
+
+
+
+
+// STRUCTS
+
+/// A writer which encrypts all data written to it and writes the ciphertext to the inner writer.
+/// The mode and the padding are chosen by the encryptor, see the `encryptor` functions of the modes.
+///
+/// `finish` must be called after the last write, because the padded last block is only written then.
+/// Dropping the writer without calling `finish` loses the data which doesn't fill a block yet.
+///
+/// Data given to `write` is encrypted at once, so the ciphertext which the inner writer doesn't accept is kept
+/// and written first by the next call of `write`, `flush` or `finish`, which also return the error if it persists.
+/// `finish` can therefore be called again after an error of the inner writer.
+#[derive(Debug)]
+pub struct EncryptingWriter<W: Write> {
+    /// The writer to which the ciphertext is written.
+    writer: W,
+    /// The encryptor of the data, `None` after `finish`.
+    encryptor: Option<Encryptor>,
+    /// The ciphertext which isn't written to the inner writer yet.
+    buffer: Vec<u8>,
+}
+
+/// Public functions for the encrypting writer.
+impl<W: Write> EncryptingWriter<W> {
+    pub fn new(writer: W, encryptor: Encryptor) -> Self {
+        //! Creates a new encrypting writer.
+        //! # Arguments
+        //! * `writer` - The writer to which the ciphertext is written.
+        //! * `encryptor` - The encryptor of the data, at the start of the data.
+
+        Self {
+            writer,
+            encryptor: Some(encryptor),
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        //! Returns a reference to the inner writer.
+
+        &self.writer
+    }
+
+    pub fn get_mut(&mut self) -> &mut W {
+        //! Returns a mutable reference to the inner writer.
+        //! Writing to it directly mixes unencrypted data into the ciphertext.
+
+        &mut self.writer
+    }
+
+    pub fn into_inner(self) -> W {
+        //! Returns the inner writer. The ciphertext which wasn't written yet is lost, so `finish` should be called first.
+
+        self.writer
+    }
+
+    pub fn finish(&mut self) -> io::Result<()> {
+        //! Encrypts and writes the padded last block and flushes the inner writer.
+        //! No more data can be written afterwards.
+        //! # Errors
+        //! * ErrorKind::InvalidData - No padding is used and the data isn't a multiple of 16 bytes long.
+        //! * Any error of the inner writer. The rest of the ciphertext is kept, so `finish` can be called again.
+
+        if let Some(encryptor) = self.encryptor.take() {
+            encryptor.finalize(&mut self.buffer).map_err(into_io_error)?;
+        }
+        write_buffer(&mut self.writer, &mut self.buffer)?;
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // nothing is encrypted before the ciphertext of the previous calls is written
+        write_buffer(&mut self.writer, &mut self.buffer)?;
+        let encryptor: &mut Encryptor = match self.encryptor.as_mut() {
+            Some(encryptor) => encryptor,
+            None => return Err(io::Error::new(ErrorKind::InvalidInput, "the encrypting writer is already finished")),
+        };
+        encryptor.update(buf, &mut self.buffer).map_err(into_io_error)?;
+
+        // the data is already encrypted, so it counts as written and the rest of the ciphertext is kept for the next call
+        let _ = write_buffer(&mut self.writer, &mut self.buffer);
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        write_buffer(&mut self.writer, &mut self.buffer)?;
+        self.writer.flush()
+    }
+}
+
+/// A reader which reads the ciphertext from the inner reader and returns it decrypted.
+/// The mode and the padding are chosen by the decryptor, see the `decryptor` functions of the modes.
+///
+/// The padding is removed when the inner reader reaches its end.
+/// Invalid padding or a ciphertext of invalid length is returned as an error of the kind `ErrorKind::InvalidData`,
+/// and the same error is returned by every later read.
+#[derive(Debug)]
+pub struct DecryptingReader<R: Read> {
+    /// The reader from which the ciphertext is read.
+    reader: R,
+    /// The decryptor of the data, `None` after the end of the data or an error of the mode.
+    decryptor: Option<Decryptor>,
+    /// The error of the mode, returned by every read after it.
+    error: Option<ModeError>,
+    /// The plaintext which isn't returned yet.
+    buffer: Vec<u8>,
+    /// The number of bytes of the buffer which were already returned.
+    position: usize,
+}
+
+/// Public functions for the decrypting reader.
+impl<R: Read> DecryptingReader<R> {
+    pub fn new(reader: R, decryptor: Decryptor) -> Self {
+        //! Creates a new decrypting reader.
+        //! # Arguments
+        //! * `reader` - The reader from which the ciphertext is read.
+        //! * `decryptor` - The decryptor of the data, at the start of the data.
+
+        Self {
+            reader,
+            decryptor: Some(decryptor),
+            error: None,
+            buffer: Vec::new(),
+            position: 0,
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        //! Returns a reference to the inner reader.
+
+        &self.reader
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        //! Returns a mutable reference to the inner reader.
+        //! Reading from it directly skips data of the ciphertext.
+
+        &mut self.reader
+    }
+
+    pub fn into_inner(self) -> R {
+        //! Returns the inner reader. The plaintext which wasn't read yet is lost.
+
+        self.reader
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        while self.position == self.buffer.len() {
+            if let Some(error) = self.error {
+                return Err(into_io_error(error));
+            }
+            let decryptor: &mut Decryptor = match self.decryptor.as_mut() {
+                Some(decryptor) => decryptor,
+                None => return Ok(0),
+            };
+
+            self.buffer.clear();
+            self.position = 0;
+
+            let mut ciphertext: [u8; READ_BUFFER_SIZE] = [0; READ_BUFFER_SIZE];
+            let length: usize = self.reader.read(&mut ciphertext)?;
+            let result: Result<(), ModeError> = if length == 0 {
+                self.decryptor.take().unwrap().finalize(&mut self.buffer)
+            } else {
+                decryptor.update(&ciphertext[..length], &mut self.buffer)
+            };
+
+            // the ciphertext was already consumed, so the decryption can't continue after an error
+            if let Err(error) = result {
+                self.decryptor = None;
+                self.error = Some(error);
+            }
+        }
+
+        let length: usize = buf.len().min(self.buffer.len() - self.position);
+        buf[..length].copy_from_slice(&self.buffer[self.position..(self.position + length)]);
+        self.position += length;
+
+        Ok(length)
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn write_buffer<W: Write>(writer: &mut W, buffer: &mut Vec<u8>) -> io::Result<()> {
+    //! Writes the buffered ciphertext to the writer.
+    //! Only the bytes which were written are removed from the buffer, the rest is kept after an error.
+    //! # Errors
+    //! * ErrorKind::WriteZero - The writer didn't accept any more bytes.
+    //! * Any error of the writer.
+
+    let mut written: usize = 0;
+    let result: io::Result<()> = loop {
+        if written == buffer.len() {
+            break Ok(());
+        }
+
+        match writer.write(&buffer[written..]) {
+            Ok(0) => break Err(io::Error::from(ErrorKind::WriteZero)),
+            Ok(length) => written += length,
+            Err(error) if error.kind() == ErrorKind::Interrupted => {}
+            Err(error) => break Err(error),
+        }
+    };
+    buffer.drain(..written);
+
+    result
+}
+
+fn into_io_error(error: ModeError) -> io::Error {
+    //! Converts the error of a mode into an I/O error, keeping it as the inner error.
+    //! Errors caused by the data (e.g. invalid padding) are of the kind `ErrorKind::InvalidData`.
+
+    let kind: ErrorKind = match error {
+        ModeError::InvalidInputSize
+        | ModeError::Padding(_)
+        | ModeError::AuthenticationFailed
+        | ModeError::IntegrityCheckFailed => ErrorKind::InvalidData,
+        _ => ErrorKind::Other,
+    };
+
+    io::Error::new(kind, error)
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of bytes of the ciphertext read from the inner reader at once.
+const READ_BUFFER_SIZE: usize = 4096;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use crate::aes_core::{
+        AESCore,
+        AESKey,
+    };
+    use crate::modes::CBC;
+    use crate::padding::{
+        Padding,
+        PaddingError,
+        PaddingTypes,
+    };
+
+    /// A writer which accepts at most 7 bytes at once and fails once when asked to.
+    struct FlakyWriter {
+        /// The bytes written so far.
+        data: Vec<u8>,
+        /// Whether the next write fails.
+        fail: bool,
+    }
+
+    impl Write for FlakyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.fail {
+                self.fail = false;
+                return Err(io::Error::from(ErrorKind::Other));
+            }
+
+            let length: usize = buf.len().min(7);
+            self.data.extend_from_slice(&buf[..length]);
+            Ok(length)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn cbc(padding_type: PaddingTypes) -> CBC {
+        //! Returns the CBC instance used by the tests.
+
+        CBC::new(AESCore::new(AESKey::AES128([0x2b; 16])), Padding::new(padding_type), [0x0f; 16])
+    }
+
+    #[test]
+    fn write_and_read() {
+        //! Test that the adapters give the same output as the one-shot functions
+
+        let cbc: CBC = cbc(PaddingTypes::PKCS7);
+        let plaintext: Vec<u8> = (0..10000).map(|i| i as u8).collect();
+
+        let mut writer = EncryptingWriter::new(Vec::new(), cbc.encryptor());
+        for part in plaintext.chunks(999) {
+            writer.write_all(part).unwrap();
+        }
+        writer.finish().unwrap();
+        let ciphertext: Vec<u8> = writer.into_inner();
+        assert_eq!(ciphertext, cbc.encrypt(&plaintext).unwrap());
+
+        let mut reader = DecryptingReader::new(Cursor::new(&ciphertext), cbc.decryptor());
+        let mut decrypted: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        // a small buffer is filled in several reads
+        let mut reader = DecryptingReader::new(Cursor::new(&ciphertext), cbc.decryptor());
+        let mut part: [u8; 7] = [0; 7];
+        reader.read_exact(&mut part).unwrap();
+        assert_eq!(part, plaintext[..7]);
+
+        // an empty buffer doesn't read from the inner reader
+        let mut reader = DecryptingReader::new(Cursor::new(&ciphertext), cbc.decryptor());
+        assert_eq!(reader.read(&mut []).unwrap(), 0);
+        assert_eq!(reader.get_ref().position(), 0);
+    }
+
+    #[test]
+    fn failing_writer() {
+        //! Test that the ciphertext which the inner writer doesn't accept is written by the next calls
+
+        let cbc: CBC = cbc(PaddingTypes::PKCS7);
+        let plaintext: Vec<u8> = (0..100).collect();
+        let mut writer = EncryptingWriter::new(FlakyWriter { data: Vec::new(), fail: false }, cbc.encryptor());
+        writer.write_all(&plaintext[..40]).unwrap();
+
+        // the data is encrypted before the inner writer fails, so it counts as written
+        writer.get_mut().fail = true;
+        assert_eq!(writer.write(&plaintext[40..70]).unwrap(), 30);
+
+        // the ciphertext kept from the previous call can't be written, so nothing is encrypted
+        writer.get_mut().fail = true;
+        assert_eq!(writer.write(&plaintext[70..]).unwrap_err().kind(), ErrorKind::Other);
+        writer.get_mut().fail = true;
+        assert_eq!(writer.flush().unwrap_err().kind(), ErrorKind::Other);
+
+        writer.write_all(&plaintext[70..]).unwrap();
+
+        // the padded last block can't be written at first, so finish is called again
+        writer.get_mut().fail = true;
+        assert_eq!(writer.finish().unwrap_err().kind(), ErrorKind::Other);
+        writer.finish().unwrap();
+        assert_eq!(writer.write(&[0]).unwrap_err().kind(), ErrorKind::InvalidInput);
+        assert_eq!(writer.into_inner().data, cbc.encrypt(&plaintext).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        //! Test that the errors of the mode are returned as I/O errors
+
+        let cbc: CBC = cbc(PaddingTypes::PKCS7);
+
+        // a modified first block flips the first byte of the padding in the second block
+        let mut ciphertext: Vec<u8> = cbc.encrypt(&[0; 16]).unwrap();
+        ciphertext[0] ^= 0x01;
+        let error: io::Error = DecryptingReader::new(Cursor::new(&ciphertext), cbc.decryptor()).read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(error.into_inner().unwrap().downcast_ref::<ModeError>(), Some(&ModeError::Padding(PaddingError::InvalidPadding)));
+
+        // the ciphertext isn't a multiple of 16 bytes long
+        let error: io::Error = DecryptingReader::new(Cursor::new(&ciphertext[..20]), cbc.decryptor()).read_to_end(&mut Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        // the error is returned again instead of the end of the data
+        let mut reader = DecryptingReader::new(Cursor::new(&ciphertext), cbc.decryptor());
+        assert_eq!(reader.read(&mut [0; 16]).unwrap(), 16);
+        assert_eq!(reader.read(&mut [0; 16]).unwrap_err().kind(), ErrorKind::InvalidData);
+        assert_eq!(reader.read(&mut [0; 16]).unwrap_err().kind(), ErrorKind::InvalidData);
+
+        // the plaintext isn't a multiple of 16 bytes long without padding
+        let mut writer = EncryptingWriter::new(Vec::new(), self::cbc(PaddingTypes::None).encryptor());
+        writer.write_all(&[0; 20]).unwrap();
+        assert_eq!(writer.finish().unwrap_err().kind(), ErrorKind::InvalidData);
+    }
+}
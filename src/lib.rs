@@ -1,9 +1,14 @@
 //! **tinyaes** is a pure Rust implementation of the Advanced Encryption Standard (AES).
 //!
 //! It supports AES-128, AES-192, and AES-256.
-//! This is a low-level implementation, and is currently only able to encrypt blocks of data.
-//! As of now, it is not intended to be used directly, but rather as a building block for other cryptographic libraries.
-//! Higher-level functions may be added in the future.
+//! The core of the crate is a low-level implementation which encrypts and decrypts single blocks of data.
+//! On top of it, the `modes` module provides block cipher modes of operation for encrypting data of arbitrary length,
+//! and the `io` module provides readers and writers which encrypt and decrypt data on the fly.
+//!
+//! The implementation of the core is chosen with `Implementation`. By default, the AES instructions of the processor
+//! are used (AES-NI and VAES on x86_64, the cryptographic extension on aarch64), detected at runtime.
+//! Without them, the S-Box lookup tables are used, or with the `constant-time` feature a bitsliced implementation,
+//! so the time taken and the memory accessed don't depend on the key or the data.
 //!
 //! **Example:** Encrypting a block of data with AES-256
 //! ```
@@ -25,10 +30,18 @@
 
 
 pub mod aes_core;
+pub mod io;
+pub mod modes;
 pub mod padding;
 
 #[doc(inline)]
 pub use aes_core::*;
 
+#[doc(inline)]
+pub use io::*;
+
+#[doc(inline)]
+pub use modes::*;
+
 #[doc(inline)]
 pub use padding::*;
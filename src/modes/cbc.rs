@@ -0,0 +1,303 @@
+//! A module containing the CBC (Cipher Block Chaining) mode of operation.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::Padding;
+use super::{
+    BATCH_BLOCKS,
+    Decryptor,
+    Encryptor,
+    ModeError,
+    Parallel,
+    check_padded_size,
+    remove_padding,
+    split_for_padding,
+    stream::StreamState,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The CBC (Cipher Block Chaining) mode of operation.
+/// Every plaintext block is XORed with the previous ciphertext block (the IV for the first block) before being encrypted.
+/// The last block is padded with the given padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CBC {
+    /// The AES core used to encrypt and decrypt the blocks.
+    aes_core: AESCore,
+    /// The padding applied to the last block.
+    padding: Padding,
+    /// The initialization vector.
+    iv: [u8; 16],
+}
+
+/// Public functions for encrypting and decrypting data.
+impl CBC {
+    pub fn new(aes_core: AESCore, padding: Padding, iv: [u8; 16]) -> Self {
+        //! Creates a new CBC instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to encrypt and decrypt the blocks.
+        //! * `padding` - The padding applied to the last block. With `PaddingTypes::None` the input must be a multiple of 16 bytes long.
+        //! * `iv` - The initialization vector. It should be unpredictable and never reused with the same key.
+
+        Self {
+            aes_core,
+            padding,
+            iv,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CBC instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CBC instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn padding(&self) -> Padding {
+        //! Returns the padding used by this CBC instance.
+
+        self.padding
+    }
+
+    pub fn set_padding(&mut self, padding: Padding) {
+        //! Changes the padding used by this CBC instance.
+
+        self.padding = padding;
+    }
+
+    pub fn iv(&self) -> [u8; 16] {
+        //! Returns the initialization vector used by this CBC instance.
+
+        self.iv
+    }
+
+    pub fn set_iv(&mut self, iv: [u8; 16]) {
+        //! Changes the initialization vector used by this CBC instance.
+
+        self.iv = iv;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data.
+        //! # Arguments
+        //! * `input` - The plaintext of any length (a multiple of 16 bytes with `PaddingTypes::None`).
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - No padding is used and the input isn't a multiple of 16 bytes long.
+
+        let (full_blocks, last_block) = split_for_padding(&self.padding, input)?;
+
+        let mut output: Vec<u8> = Vec::with_capacity(full_blocks.len() + 16);
+        output.extend_from_slice(full_blocks);
+        if let Some(last_block) = last_block {
+            output.extend_from_slice(&last_block);
+        }
+        encrypt_blocks(&self.aes_core, &self.iv, &mut output);
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data.
+        //! # Arguments
+        //! * `input` - The ciphertext, a multiple of 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input isn't a multiple of 16 bytes long, or is empty while padding is used.
+        //! * ModeError::Padding - The padding of the last block is invalid.
+
+        check_padded_size(&self.padding, input)?;
+
+        let mut output: Vec<u8> = input.to_vec();
+        decrypt_blocks(&self.aes_core, &self.iv, &mut output);
+        remove_padding(&self.padding, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn decrypt_parallel(&self, parallel: &Parallel, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data, splitting it into chunks which are decrypted in parallel.
+        //! Every chunk is chained to the last ciphertext block of the previous chunk, so the output is the same as the output of `decrypt`.
+        //! Encryption can't be parallelized, because every block depends on the previous ciphertext block.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `input` - The ciphertext, a multiple of 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input isn't a multiple of 16 bytes long, or is empty while padding is used.
+        //! * ModeError::Padding - The padding of the last block is invalid.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        check_padded_size(&self.padding, input)?;
+
+        let chunk_size: usize = parallel.chunk_size();
+        let chunk_ivs: Vec<[u8; 16]> = [self.iv].into_iter()
+            .chain(input.chunks(chunk_size).map(|chunk| chunk[(chunk.len() - 16)..].try_into().unwrap()))
+            .collect();
+
+        let aes_core: AESCore = self.aes_core;
+        let mut output: Vec<u8> = parallel.map_buffer(input, chunk_size, move |index, chunk| {
+            decrypt_blocks(&aes_core, &chunk_ivs[index], chunk);
+            Ok(())
+        })?;
+        remove_padding(&self.padding, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn encryptor(&self) -> Encryptor {
+        //! Returns a streaming encryptor for encrypting the data in parts, see the `Encryptor` struct.
+
+        Encryptor::new(self.aes_core, self.padding, StreamState::cbc(self.iv))
+    }
+
+    pub fn decryptor(&self) -> Decryptor {
+        //! Returns a streaming decryptor for decrypting the data in parts, see the `Decryptor` struct.
+
+        Decryptor::new(self.aes_core, self.padding, StreamState::cbc(self.iv))
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+pub(super) fn encrypt_blocks(aes_core: &AESCore, iv: &[u8; 16], buffer: &mut [u8]) {
+    //! Encrypts the blocks of the buffer in place in CBC mode.
+    //! The buffer must be a multiple of 16 bytes long.
+
+    let mut previous_block: [u8; 16] = *iv;
+    for block in buffer.chunks_exact_mut(16) {
+        let block: &mut [u8; 16] = block.try_into().unwrap();
+        xor_blocks(block, &previous_block);
+        *block = aes_core.encrypt(block);
+        previous_block = *block;
+    }
+}
+
+pub(super) fn decrypt_blocks(aes_core: &AESCore, iv: &[u8; 16], buffer: &mut [u8]) {
+    //! Decrypts the blocks of the buffer in place in CBC mode.
+    //! The buffer must be a multiple of 16 bytes long.
+
+    let mut previous_block: [u8; 16] = *iv;
+    for batch in buffer.as_chunks_mut::<16>().0.chunks_mut(BATCH_BLOCKS) {
+        let mut ciphertext_blocks: [[u8; 16]; BATCH_BLOCKS] = [[0; 16]; BATCH_BLOCKS];
+        ciphertext_blocks[..batch.len()].copy_from_slice(batch);
+
+        aes_core.decrypt_blocks(batch);
+        for (block, ciphertext_block) in batch.iter_mut().zip(ciphertext_blocks) {
+            xor_blocks(block, &previous_block);
+            previous_block = ciphertext_block;
+        }
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+    use crate::padding::PaddingTypes;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b,
+        0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    #[test]
+    fn encrypt() {
+        //! Test encryption of two blocks without padding (SP 800-38A, F.2.1)
+
+        let cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::None), IV);
+        let plaintext: [u8; 32] = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+            0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        ];
+        let ciphertext: [u8; 32] = [
+            0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d,
+            0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2,
+        ];
+
+        assert_eq!(cbc.encrypt(&plaintext).unwrap(), ciphertext);
+        assert_eq!(cbc.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn padding() {
+        //! Test that padding is added and removed for every input length
+
+        for padding_type in [PaddingTypes::PKCS7, PaddingTypes::ISO78164, PaddingTypes::X923] {
+            let cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(padding_type), IV);
+
+            for length in 0..50 {
+                let plaintext: Vec<u8> = (0..length as u8).collect();
+                let ciphertext = cbc.encrypt(&plaintext).unwrap();
+
+                assert_eq!(ciphertext.len(), (length / 16 + 1) * 16);
+                assert_eq!(cbc.decrypt(&ciphertext).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn set_iv() {
+        //! Test that the IV changes the ciphertext
+
+        let mut cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::PKCS7), IV);
+        let ciphertext = cbc.encrypt(&[0; 20]).unwrap();
+
+        cbc.set_iv([0; 16]);
+        assert_eq!(cbc.iv(), [0; 16]);
+        assert_ne!(cbc.encrypt(&[0; 20]).unwrap(), ciphertext);
+        assert_ne!(cbc.decrypt(&ciphertext).unwrap()[..16], [0; 16]);
+
+        cbc.set_iv(IV);
+        assert_eq!(cbc.decrypt(&ciphertext).unwrap(), [0; 20]);
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::None), IV);
+        assert_eq!(cbc.encrypt(&[0; 15]), Err(ModeError::InvalidInputSize));
+        assert_eq!(cbc.decrypt(&[0; 17]), Err(ModeError::InvalidInputSize));
+
+        let cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::PKCS7), IV);
+        assert_eq!(cbc.decrypt(&[]), Err(ModeError::InvalidInputSize));
+    }
+}
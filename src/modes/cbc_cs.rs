@@ -0,0 +1,311 @@
+//! A module containing the CBC mode with ciphertext stealing (CBC-CS1, CBC-CS2 and CBC-CS3).
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    ModeError,
+    cbc::{
+        decrypt_blocks,
+        encrypt_blocks,
+    },
+};
+
+
+
+
+
+// ENUMS
+
+/// The variants of ciphertext stealing, as defined in the addendum to NIST SP 800-38A.
+/// They only differ in the order of the last two ciphertext blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CiphertextStealing {
+    /// The partial block is followed by the last full block.
+    CS1,
+    /// The last two blocks are swapped if the last block is partial.
+    /// If the input is a multiple of 16 bytes long, the ciphertext is the same as with plain CBC.
+    CS2,
+    /// The last two blocks are always swapped, as in Kerberos (RFC 3962).
+    CS3,
+}
+
+
+
+
+
+// STRUCTS
+
+/// The CBC (Cipher Block Chaining) mode of operation with ciphertext stealing.
+/// The last block is padded with zeros and encrypted, and the bytes of the previous ciphertext block which are needed for decryption are left out.
+/// The ciphertext is always as long as the plaintext, which must be at least 16 bytes long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CBCCS {
+    /// The AES core used to encrypt and decrypt the blocks.
+    aes_core: AESCore,
+    /// The variant of ciphertext stealing.
+    variant: CiphertextStealing,
+    /// The initialization vector.
+    iv: [u8; 16],
+}
+
+/// Public functions for encrypting and decrypting data.
+impl CBCCS {
+    pub fn new(aes_core: AESCore, variant: CiphertextStealing, iv: [u8; 16]) -> Self {
+        //! Creates a new CBC-CS instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to encrypt and decrypt the blocks.
+        //! * `variant` - The variant of ciphertext stealing, see the `CiphertextStealing` enum.
+        //! * `iv` - The initialization vector. It should be unpredictable and never reused with the same key.
+
+        Self {
+            aes_core,
+            variant,
+            iv,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CBC-CS instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CBC-CS instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn variant(&self) -> CiphertextStealing {
+        //! Returns the variant of ciphertext stealing used by this CBC-CS instance.
+
+        self.variant
+    }
+
+    pub fn set_variant(&mut self, variant: CiphertextStealing) {
+        //! Changes the variant of ciphertext stealing used by this CBC-CS instance.
+
+        self.variant = variant;
+    }
+
+    pub fn iv(&self) -> [u8; 16] {
+        //! Returns the initialization vector used by this CBC-CS instance.
+
+        self.iv
+    }
+
+    pub fn set_iv(&mut self, iv: [u8; 16]) {
+        //! Changes the initialization vector used by this CBC-CS instance.
+
+        self.iv = iv;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data.
+        //! # Arguments
+        //! * `input` - The plaintext, at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext, as long as the plaintext, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input is shorter than 16 bytes.
+
+        let (block_count, last_length) = Self::split(input)?;
+
+        let mut output: Vec<u8> = input.to_vec();
+        output.resize(block_count * 16, 0);
+        encrypt_blocks(&self.aes_core, &self.iv, &mut output);
+
+        if block_count > 1 {
+            let stolen_start: usize = (block_count - 2) * 16;
+            let last_block: [u8; 16] = output[((block_count - 1) * 16)..].try_into().unwrap();
+
+            // the bytes of the penultimate block after the last length are recovered when decrypting the last block
+            output.truncate(stolen_start + last_length);
+            if self.swaps_last_blocks(last_length) {
+                output.splice(stolen_start..stolen_start, last_block);
+            } else {
+                output.extend_from_slice(&last_block);
+            }
+        }
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data.
+        //! # Arguments
+        //! * `input` - The ciphertext, at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext, as long as the ciphertext, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input is shorter than 16 bytes.
+
+        let (block_count, last_length) = Self::split(input)?;
+
+        if block_count == 1 {
+            let mut output: Vec<u8> = input.to_vec();
+            decrypt_blocks(&self.aes_core, &self.iv, &mut output);
+            return Ok(output);
+        }
+
+        let stolen_start: usize = (block_count - 2) * 16;
+        let (partial_block, last_block) = if self.swaps_last_blocks(last_length) {
+            let (last_block, partial_block) = input[stolen_start..].split_at(16);
+            (partial_block, last_block)
+        } else {
+            input[stolen_start..].split_at(last_length)
+        };
+
+        // the decrypted last block is the penultimate ciphertext block XORed with the zero padded last plaintext block
+        let mut penultimate_block: [u8; 16] = self.aes_core.decrypt(last_block.try_into().unwrap());
+        let last_plaintext: Vec<u8> = penultimate_block.iter().zip(partial_block).map(|(x, y)| x ^ y).collect();
+        penultimate_block[..last_length].copy_from_slice(partial_block);
+
+        let mut output: Vec<u8> = Vec::with_capacity(input.len());
+        output.extend_from_slice(&input[..stolen_start]);
+        output.extend_from_slice(&penultimate_block);
+        decrypt_blocks(&self.aes_core, &self.iv, &mut output);
+        output.extend_from_slice(&last_plaintext);
+
+        Ok(output)
+    }
+}
+
+/// Functions for handling the last two blocks.
+impl CBCCS {
+    fn split(input: &[u8]) -> Result<(usize, usize), ModeError> {
+        //! Returns the number of blocks of the input and the length of the last (possibly partial) block.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input is shorter than 16 bytes.
+
+        if input.len() < 16 {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let block_count: usize = input.len().div_ceil(16);
+
+        Ok((block_count, input.len() - (block_count - 1) * 16))
+    }
+
+    fn swaps_last_blocks(&self, last_length: usize) -> bool {
+        //! Returns whether the last full ciphertext block comes before the partial one.
+
+        match self.variant {
+            CiphertextStealing::CS1 => false,
+            CiphertextStealing::CS2 => last_length != 16,
+            CiphertextStealing::CS3 => true,
+        }
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+    use crate::modes::CBC;
+    use crate::padding::{
+        Padding,
+        PaddingTypes,
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b,
+        0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    const PLAINTEXT: [u8; 40] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11,
+    ];
+
+    // the CBC encryption (SP 800-38A, F.2.1) of the plaintext padded with zeros to 48 bytes
+    const C1: [u8; 16] = [0x76, 0x49, 0xab, 0xac, 0x81, 0x19, 0xb2, 0x46, 0xce, 0xe9, 0x8e, 0x9b, 0x12, 0xe9, 0x19, 0x7d];
+    const C2: [u8; 16] = [0x50, 0x86, 0xcb, 0x9b, 0x50, 0x72, 0x19, 0xee, 0x95, 0xdb, 0x11, 0x3a, 0x91, 0x76, 0x78, 0xb2];
+    const C3: [u8; 16] = [0x49, 0x31, 0xb7, 0xeb, 0xe3, 0x95, 0x9b, 0x02, 0xa0, 0x72, 0x45, 0xed, 0x3e, 0xed, 0x3d, 0x3e];
+
+    fn cbc_cs(variant: CiphertextStealing) -> CBCCS {
+        CBCCS::new(AESCore::new(AESKey::AES128(KEY)), variant, IV)
+    }
+
+    #[test]
+    fn partial_block() {
+        //! Test the order of the last two blocks when the last block is partial
+
+        let expected: [(CiphertextStealing, Vec<u8>); 3] = [
+            (CiphertextStealing::CS1, [&C1[..], &C2[..8], &C3[..]].concat()),
+            (CiphertextStealing::CS2, [&C1[..], &C3[..], &C2[..8]].concat()),
+            (CiphertextStealing::CS3, [&C1[..], &C3[..], &C2[..8]].concat()),
+        ];
+
+        for (variant, ciphertext) in expected {
+            let cbc_cs = cbc_cs(variant);
+            assert_eq!(cbc_cs.encrypt(&PLAINTEXT).unwrap(), ciphertext);
+            assert_eq!(cbc_cs.decrypt(&ciphertext).unwrap(), PLAINTEXT);
+        }
+    }
+
+    #[test]
+    fn full_blocks() {
+        //! Test that only CS3 swaps the last two blocks when the input is a multiple of 16 bytes long
+
+        let cbc = CBC::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::None), IV);
+        assert_eq!(cbc.encrypt(&PLAINTEXT[..32]).unwrap(), [C1, C2].concat());
+
+        assert_eq!(cbc_cs(CiphertextStealing::CS1).encrypt(&PLAINTEXT[..32]).unwrap(), [C1, C2].concat());
+        assert_eq!(cbc_cs(CiphertextStealing::CS2).encrypt(&PLAINTEXT[..32]).unwrap(), [C1, C2].concat());
+        assert_eq!(cbc_cs(CiphertextStealing::CS3).encrypt(&PLAINTEXT[..32]).unwrap(), [C2, C1].concat());
+
+        // a single block is encrypted as in CBC by every variant
+        for variant in [CiphertextStealing::CS1, CiphertextStealing::CS2, CiphertextStealing::CS3] {
+            assert_eq!(cbc_cs(variant).encrypt(&PLAINTEXT[..16]).unwrap(), C1);
+            assert_eq!(cbc_cs(variant).decrypt(&C1).unwrap(), PLAINTEXT[..16]);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        //! Test that every length from 16 bytes round-trips with every variant
+
+        for variant in [CiphertextStealing::CS1, CiphertextStealing::CS2, CiphertextStealing::CS3] {
+            let cbc_cs = cbc_cs(variant);
+            for length in 16..=PLAINTEXT.len() {
+                let ciphertext: Vec<u8> = cbc_cs.encrypt(&PLAINTEXT[..length]).unwrap();
+                assert_eq!(ciphertext.len(), length);
+                assert_eq!(cbc_cs.decrypt(&ciphertext).unwrap(), PLAINTEXT[..length]);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on inputs shorter than a block
+
+        let mut cbc_cs = cbc_cs(CiphertextStealing::CS1);
+        cbc_cs.set_variant(CiphertextStealing::CS3);
+        assert_eq!(cbc_cs.variant(), CiphertextStealing::CS3);
+
+        assert_eq!(cbc_cs.encrypt(&PLAINTEXT[..15]), Err(ModeError::InvalidInputSize));
+        assert_eq!(cbc_cs.decrypt(&[]), Err(ModeError::InvalidInputSize));
+    }
+}
@@ -0,0 +1,213 @@
+//! A module containing the raw CBC-MAC message authentication code.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    MacChain,
+    ModeError,
+    check_mac_tag_length,
+    constant_time_eq,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The raw CBC-MAC message authentication code, the last block of the CBC encryption of the message with a zero IV.
+///
+/// CBC-MAC is only secure for messages of a single fixed length, so the length is part of the instance.
+/// The message length must be a non-zero multiple of 16 bytes, and tags are only returned for messages of exactly that length.
+/// For messages of variable length, use `CMAC` instead.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CBCMAC {
+    /// The AES core used for the CBC chaining.
+    aes_core: AESCore,
+    /// The fixed length of the messages in bytes.
+    message_length: usize,
+    /// The number of bytes of the message given so far.
+    processed_length: usize,
+    /// The CBC chaining of the data given so far.
+    chain: MacChain,
+}
+
+/// Public functions for computing and verifying tags.
+impl CBCMAC {
+    pub fn new(aes_core: AESCore, message_length: usize) -> Result<Self, ModeError> {
+        //! Creates a new CBC-MAC instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for the CBC chaining.
+        //! * `message_length` - The fixed length of the messages in bytes, a non-zero multiple of 16.
+        //! # Returns
+        //! * Result<CBCMAC, ModeError> - The CBC-MAC instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The message length is zero or isn't a multiple of 16.
+
+        if message_length == 0 || !message_length.is_multiple_of(16) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        Ok(Self {
+            aes_core,
+            message_length,
+            processed_length: 0,
+            chain: MacChain::new(),
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CBC-MAC instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CBC-MAC instance.
+        //! The data given so far is discarded.
+
+        self.aes_core = aes_core;
+        self.reset();
+    }
+
+    pub fn message_length(&self) -> usize {
+        //! Returns the fixed length of the messages in bytes.
+
+        self.message_length
+    }
+
+    pub fn reset(&mut self) {
+        //! Discards the data given so far, so a new tag can be computed with the same key.
+
+        self.processed_length = 0;
+        self.chain = MacChain::new();
+    }
+
+    pub fn update(&mut self, data: &[u8]) -> Result<(), ModeError> {
+        //! Adds the next part of the message.
+        //! # Arguments
+        //! * `data` - The next part of the message, of any length.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The message would be longer than the fixed length. The data isn't added.
+
+        if data.len() > self.message_length - self.processed_length {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        self.chain.update(&self.aes_core, data);
+        self.processed_length += data.len();
+
+        Ok(())
+    }
+
+    pub fn finalize(&self) -> Result<[u8; 16], ModeError> {
+        //! Returns the tag of the message.
+        //! # Returns
+        //! * Result<[u8; 16], ModeError> - The tag or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The message is shorter than the fixed length.
+
+        if self.processed_length != self.message_length {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        // the last block is always complete, so no subkey is needed
+        Ok(self.chain.finalize(&self.aes_core, &[0; 16], &[0; 16]))
+    }
+
+    pub fn verify(&self, tag: &[u8]) -> Result<(), ModeError> {
+        //! Verifies the given (possibly truncated) tag against the message, in constant time.
+        //! # Arguments
+        //! * `tag` - The tag, from 4 to 16 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag isn't between 4 and 16 bytes long.
+        //! * ModeError::InvalidInputSize - The message is shorter than the fixed length.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the message.
+
+        check_mac_tag_length(tag.len())?;
+
+        if !constant_time_eq(&self.finalize()?[..tag.len()], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+    use crate::modes::CBC;
+    use crate::padding::{
+        Padding,
+        PaddingTypes,
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const MESSAGE: [u8; 32] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    ];
+
+    #[test]
+    fn finalize() {
+        //! Test that the tag is the last block of the CBC encryption with a zero IV
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        let cbc = CBC::new(aes_core, Padding::new(PaddingTypes::None), [0; 16]);
+        let tag: Vec<u8> = cbc.encrypt(&MESSAGE).unwrap()[16..].to_vec();
+
+        let mut cbc_mac = CBCMAC::new(aes_core, 32).unwrap();
+        for part in [&MESSAGE[..7], &MESSAGE[7..16], &MESSAGE[16..]] {
+            cbc_mac.update(part).unwrap();
+        }
+        assert_eq!(cbc_mac.finalize().unwrap().to_vec(), tag);
+        assert!(cbc_mac.verify(&tag).is_ok());
+        assert!(cbc_mac.verify(&tag[..8]).is_ok());
+
+        cbc_mac.reset();
+        cbc_mac.update(&MESSAGE).unwrap();
+        assert_eq!(cbc_mac.finalize().unwrap().to_vec(), tag);
+    }
+
+    #[test]
+    fn length_restrictions() {
+        //! Test that messages of other lengths than the fixed one are rejected
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        assert_eq!(CBCMAC::new(aes_core, 0), Err(ModeError::InvalidInputSize));
+        assert_eq!(CBCMAC::new(aes_core, 20), Err(ModeError::InvalidInputSize));
+
+        let mut cbc_mac = CBCMAC::new(aes_core, 16).unwrap();
+        assert_eq!(cbc_mac.message_length(), 16);
+        assert_eq!(cbc_mac.finalize(), Err(ModeError::InvalidInputSize));
+
+        cbc_mac.update(&MESSAGE[..10]).unwrap();
+        assert_eq!(cbc_mac.finalize(), Err(ModeError::InvalidInputSize));
+        assert_eq!(cbc_mac.verify(&[0; 16]), Err(ModeError::InvalidInputSize));
+        assert_eq!(cbc_mac.update(&MESSAGE[10..17]), Err(ModeError::InvalidInputSize));
+
+        cbc_mac.update(&MESSAGE[10..16]).unwrap();
+        assert!(cbc_mac.finalize().is_ok());
+        assert_eq!(cbc_mac.update(&MESSAGE[..1]), Err(ModeError::InvalidInputSize));
+        assert_eq!(cbc_mac.verify(&[0; 3]), Err(ModeError::InvalidTagLength));
+    }
+}
@@ -0,0 +1,372 @@
+//! A module containing the CCM (Counter with CBC-MAC) authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    ModeError,
+    constant_time_eq,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The CCM (Counter with CBC-MAC) authenticated encryption mode, as defined in RFC 3610 and NIST SP 800-38C.
+/// The data and the associated data are authenticated with CBC-MAC, and the data and the tag are encrypted in counter mode.
+///
+/// The sealed output is the ciphertext followed by the authentication tag.
+/// The nonce must never be reused with the same key.
+/// A longer nonce leaves fewer bytes for the length of the data: with an n-byte nonce, the data must be shorter than 2^(8 * (15 - n)) bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CCM {
+    /// The AES core used for the CBC-MAC and for the encryption.
+    aes_core: AESCore,
+    /// The length of the nonce in bytes.
+    nonce_length: usize,
+    /// The length of the authentication tag in bytes.
+    tag_length: usize,
+}
+
+/// Public functions for sealing and opening data.
+impl CCM {
+    pub fn new(aes_core: AESCore, nonce_length: usize, tag_length: usize) -> Result<Self, ModeError> {
+        //! Creates a new CCM instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for the CBC-MAC and for the encryption.
+        //! * `nonce_length` - The length of the nonce in bytes, from 7 to 13.
+        //! * `tag_length` - The length of the authentication tag in bytes, an even number from 4 to 16.
+        //! # Returns
+        //! * Result<CCM, ModeError> - The CCM instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce length isn't from 7 to 13.
+        //! * ModeError::InvalidTagLength - The tag length isn't an even number from 4 to 16.
+
+        Self::check_nonce_length(nonce_length)?;
+        Self::check_tag_length(tag_length)?;
+
+        Ok(Self {
+            aes_core,
+            nonce_length,
+            tag_length,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CCM instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CCM instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn nonce_length(&self) -> usize {
+        //! Returns the length of the nonce in bytes.
+
+        self.nonce_length
+    }
+
+    pub fn set_nonce_length(&mut self, nonce_length: usize) -> Result<(), ModeError> {
+        //! Changes the length of the nonce in bytes.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce length isn't from 7 to 13.
+
+        Self::check_nonce_length(nonce_length)?;
+        self.nonce_length = nonce_length;
+
+        Ok(())
+    }
+
+    pub fn tag_length(&self) -> usize {
+        //! Returns the length of the authentication tag in bytes.
+
+        self.tag_length
+    }
+
+    pub fn set_tag_length(&mut self, tag_length: usize) -> Result<(), ModeError> {
+        //! Changes the length of the authentication tag in bytes.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't an even number from 4 to 16.
+
+        Self::check_tag_length(tag_length)?;
+        self.tag_length = tag_length;
+
+        Ok(())
+    }
+
+    pub fn seal(&self, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, and authenticates the associated data.
+        //! # Arguments
+        //! * `nonce` - The nonce, `nonce_length` bytes long.
+        //! * `associated_data` - The data which is authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext followed by the tag, or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't `nonce_length` bytes long.
+        //! * ModeError::InvalidInputSize - The plaintext is too long for the nonce length.
+
+        self.check_input(nonce, plaintext.len())?;
+
+        let tag: [u8; 16] = self.cbc_mac(nonce, associated_data, plaintext);
+
+        let mut output: Vec<u8> = plaintext.to_vec();
+        output.extend_from_slice(&tag[..self.tag_length]);
+        self.ctr(nonce, &mut output);
+
+        Ok(output)
+    }
+
+    pub fn open(&self, nonce: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the ciphertext and verifies the tag.
+        //! The plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `nonce` - The nonce used for sealing.
+        //! * `associated_data` - The associated data used for sealing.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't `nonce_length` bytes long.
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag or too long for the nonce length.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if sealed.len() < self.tag_length {
+            return Err(ModeError::InvalidInputSize);
+        }
+        self.check_input(nonce, sealed.len() - self.tag_length)?;
+
+        let mut output: Vec<u8> = sealed.to_vec();
+        self.ctr(nonce, &mut output);
+        let tag: Vec<u8> = output.split_off(sealed.len() - self.tag_length);
+
+        let expected_tag: [u8; 16] = self.cbc_mac(nonce, associated_data, &output);
+        if !constant_time_eq(&expected_tag[..self.tag_length], &tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(output)
+    }
+}
+
+/// Functions used in the CCM algorithm.
+impl CCM {
+    fn check_nonce_length(nonce_length: usize) -> Result<(), ModeError> {
+        //! Checks that the nonce length is from 7 to 13 bytes.
+
+        match nonce_length {
+            7..=13 => Ok(()),
+            _ => Err(ModeError::InvalidNonceSize),
+        }
+    }
+
+    fn check_tag_length(tag_length: usize) -> Result<(), ModeError> {
+        //! Checks that the tag length is an even number from 4 to 16 bytes.
+
+        match tag_length {
+            4 | 6 | 8 | 10 | 12 | 14 | 16 => Ok(()),
+            _ => Err(ModeError::InvalidTagLength),
+        }
+    }
+
+    fn check_input(&self, nonce: &[u8], length: usize) -> Result<(), ModeError> {
+        //! Checks the nonce size and that the length of the data fits into the length field.
+
+        if nonce.len() != self.nonce_length {
+            return Err(ModeError::InvalidNonceSize);
+        }
+
+        let length_field_size: usize = 15 - self.nonce_length;
+        if length_field_size < 8 && (length as u64) >> (8 * length_field_size) != 0 {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        Ok(())
+    }
+
+    fn cbc_mac(&self, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> [u8; 16] {
+        //! Computes the full 16-byte CBC-MAC of the formatted input.
+
+        let length_field_size: usize = 15 - self.nonce_length;
+
+        // the first block holds the flags, the nonce and the length of the plaintext
+        let mut first_block: [u8; 16] = [0; 16];
+        first_block[0] = (if associated_data.is_empty() { 0 } else { 0x40 })
+            | (((self.tag_length - 2) / 2) << 3) as u8
+            | (length_field_size - 1) as u8;
+        first_block[1..(1 + self.nonce_length)].copy_from_slice(nonce);
+        first_block[(1 + self.nonce_length)..].copy_from_slice(&(plaintext.len() as u64).to_be_bytes()[(8 - length_field_size)..]);
+
+        let mut mac: [u8; 16] = self.aes_core.encrypt(&first_block);
+
+        // the associated data is prefixed with its encoded length
+        if !associated_data.is_empty() {
+            let length: u64 = associated_data.len() as u64;
+            let mut encoded: Vec<u8> = if length < 0xff00 {
+                (length as u16).to_be_bytes().to_vec()
+            } else if length <= u32::MAX as u64 {
+                [&[0xff, 0xfe], &(length as u32).to_be_bytes()[..]].concat()
+            } else {
+                [&[0xff, 0xff], &length.to_be_bytes()[..]].concat()
+            };
+            encoded.extend_from_slice(associated_data);
+            self.cbc_mac_update(&mut mac, &encoded);
+        }
+
+        self.cbc_mac_update(&mut mac, plaintext);
+
+        mac
+    }
+
+    fn cbc_mac_update(&self, mac: &mut [u8; 16], data: &[u8]) {
+        //! Absorbs the data into the CBC-MAC, padding the last block with zeros.
+
+        for chunk in data.chunks(16) {
+            let mut block: [u8; 16] = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            xor_blocks(mac, &block);
+            *mac = self.aes_core.encrypt(mac);
+        }
+    }
+
+    fn ctr(&self, nonce: &[u8], buffer: &mut [u8]) {
+        //! Encrypts or decrypts the data followed by the tag in counter mode.
+        //! The tag is encrypted with the counter block 0, and the data with the counter blocks from 1 on.
+
+        let length_field_size: usize = 15 - self.nonce_length;
+        let data_length: usize = buffer.len() - self.tag_length;
+
+        let mut counter_block: [u8; 16] = [0; 16];
+        counter_block[0] = (length_field_size - 1) as u8;
+        counter_block[1..(1 + self.nonce_length)].copy_from_slice(nonce);
+
+        let (data, tag) = buffer.split_at_mut(data_length);
+        for (counter, chunk) in [tag].into_iter().chain(data.chunks_mut(16)).enumerate() {
+            counter_block[(1 + self.nonce_length)..].copy_from_slice(&(counter as u64).to_be_bytes()[(8 - length_field_size)..]);
+            let keystream_block: [u8; 16] = self.aes_core.encrypt(&counter_block);
+            for (byte, keystream_byte) in chunk.iter_mut().zip(keystream_block) {
+                *byte ^= keystream_byte;
+            }
+        }
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0xc0, 0xc1, 0xc2, 0xc3, 0xc4, 0xc5, 0xc6, 0xc7, 0xc8, 0xc9, 0xca, 0xcb, 0xcc, 0xcd, 0xce, 0xcf,
+    ];
+
+    const NONCE: [u8; 13] = [0x00, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0xa0, 0xa1, 0xa2, 0xa3, 0xa4, 0xa5];
+
+    #[test]
+    fn seal() {
+        //! Test sealing and opening (RFC 3610, packet vector #1)
+
+        let ccm = CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 8).unwrap();
+        let packet: Vec<u8> = (0..31).collect();
+        let sealed: [u8; 31] = [
+            0x58, 0x8c, 0x97, 0x9a, 0x61, 0xc6, 0x63, 0xd2, 0xf0, 0x66, 0xd0, 0xc2, 0xc0, 0xf9, 0x89, 0x80,
+            0x6d, 0x5f, 0x6b, 0x61, 0xda, 0xc3, 0x84, 0x17, 0xe8, 0xd1, 0x2c, 0xfd, 0xf9, 0x26, 0xe0,
+        ];
+
+        assert_eq!(ccm.seal(&NONCE, &packet[..8], &packet[8..]).unwrap(), sealed);
+        assert_eq!(ccm.open(&NONCE, &packet[..8], &sealed).unwrap(), packet[8..]);
+    }
+
+    #[test]
+    fn parameters() {
+        //! Test all nonce and tag lengths
+
+        let mut ccm = CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 16).unwrap();
+        let data: Vec<u8> = (0..40).collect();
+
+        for nonce_length in 7..=13 {
+            ccm.set_nonce_length(nonce_length).unwrap();
+            assert_eq!(ccm.nonce_length(), nonce_length);
+
+            for tag_length in [4, 6, 8, 10, 12, 14, 16] {
+                ccm.set_tag_length(tag_length).unwrap();
+                assert_eq!(ccm.tag_length(), tag_length);
+
+                let sealed: Vec<u8> = ccm.seal(&NONCE[..nonce_length], &data[..5], &data).unwrap();
+                assert_eq!(sealed.len(), data.len() + tag_length);
+                assert_eq!(ccm.open(&NONCE[..nonce_length], &data[..5], &sealed).unwrap(), data);
+            }
+        }
+
+        for nonce_length in [0, 6, 14, 16] {
+            assert_eq!(ccm.set_nonce_length(nonce_length), Err(ModeError::InvalidNonceSize));
+        }
+        for tag_length in [0, 2, 5, 15, 18] {
+            assert_eq!(ccm.set_tag_length(tag_length), Err(ModeError::InvalidTagLength));
+        }
+    }
+
+    #[test]
+    fn associated_data_length() {
+        //! Test the two-byte and the six-byte encodings of the associated data length
+
+        let ccm = CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 16).unwrap();
+
+        assert_eq!(ccm.seal(&NONCE, &[0; 0xfeff], &[]).unwrap(), [
+            0x0e, 0x5f, 0x09, 0x6f, 0x88, 0x58, 0x6f, 0x8a, 0x9c, 0x25, 0xff, 0x22, 0xf6, 0x7a, 0x69, 0x0c,
+        ]);
+        assert_eq!(ccm.seal(&NONCE, &[0; 0xff00], &[]).unwrap(), [
+            0x3f, 0x2a, 0x35, 0x75, 0x5a, 0xf2, 0xda, 0x89, 0xfe, 0xe8, 0xdb, 0x15, 0x9e, 0xd1, 0x83, 0x4b,
+        ]);
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that any modification is detected
+
+        let ccm = CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 8).unwrap();
+        let sealed: Vec<u8> = ccm.seal(&NONCE, b"header", b"authenticated").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(ccm.open(&NONCE, b"header", &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(ccm.open(&NONCE, b"Header", &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(ccm.open(&NONCE, b"", &sealed), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let ccm = CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 8).unwrap();
+        assert_eq!(ccm.seal(&NONCE[..12], &[], &[]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(ccm.open(&NONCE[..12], &[], &[0; 8]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(ccm.open(&NONCE, &[], &[0; 7]), Err(ModeError::InvalidInputSize));
+
+        // a 13-byte nonce leaves 2 bytes for the length of the data
+        assert_eq!(ccm.seal(&NONCE, &[], &[0; 0x10000]), Err(ModeError::InvalidInputSize));
+        assert!(ccm.seal(&NONCE, &[], &[0; 0xffff]).is_ok());
+
+        assert_eq!(CCM::new(AESCore::new(AESKey::AES128(KEY)), 6, 8), Err(ModeError::InvalidNonceSize));
+        assert_eq!(CCM::new(AESCore::new(AESKey::AES128(KEY)), 13, 7), Err(ModeError::InvalidTagLength));
+    }
+}
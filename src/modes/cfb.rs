@@ -0,0 +1,329 @@
+//! A module containing the CFB (Cipher Feedback) mode of operation.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::{
+    Padding,
+    PaddingTypes,
+};
+use super::{
+    Decryptor,
+    Encryptor,
+    ModeError,
+    stream::StreamState,
+};
+
+
+
+
+
+// ENUMS
+
+/// The segment sizes of the CFB mode.
+/// The segment size is the number of bits encrypted with each encryption of the shift register.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentSize {
+    /// 1-bit segments.
+    /// The data is processed bit by bit, so it doesn't have to be a whole number of bytes long.
+    CFB1,
+    /// 8-bit segments.
+    CFB8,
+    /// 128-bit segments.
+    /// The last segment may be shorter than 128 bits.
+    CFB128,
+}
+
+
+
+
+
+// STRUCTS
+
+/// The CFB (Cipher Feedback) mode of operation.
+/// The shift register (initially the IV) is encrypted, and the leftmost bits of the result are XORed with the next segment of data.
+/// The resulting ciphertext segment is then shifted into the register.
+/// No padding is needed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CFB {
+    /// The AES core used to encrypt the shift register.
+    aes_core: AESCore,
+    /// The segment size.
+    segment_size: SegmentSize,
+    /// The initialization vector.
+    iv: [u8; 16],
+}
+
+/// Public functions for encrypting and decrypting data.
+impl CFB {
+    pub fn new(aes_core: AESCore, segment_size: SegmentSize, iv: [u8; 16]) -> Self {
+        //! Creates a new CFB instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to encrypt the shift register.
+        //! * `segment_size` - The segment size, see the `SegmentSize` enum.
+        //! * `iv` - The initialization vector. It should be unpredictable and never reused with the same key.
+
+        Self {
+            aes_core,
+            segment_size,
+            iv,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CFB instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CFB instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn segment_size(&self) -> SegmentSize {
+        //! Returns the segment size used by this CFB instance.
+
+        self.segment_size
+    }
+
+    pub fn set_segment_size(&mut self, segment_size: SegmentSize) {
+        //! Changes the segment size used by this CFB instance.
+
+        self.segment_size = segment_size;
+    }
+
+    pub fn iv(&self) -> [u8; 16] {
+        //! Returns the initialization vector used by this CFB instance.
+
+        self.iv
+    }
+
+    pub fn set_iv(&mut self, iv: [u8; 16]) {
+        //! Changes the initialization vector used by this CFB instance.
+
+        self.iv = iv;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        //! Encrypts the given data.
+        //! # Arguments
+        //! * `input` - The plaintext of any length.
+        //! # Returns
+        //! * Vec<u8> - The ciphertext.
+
+        self.process(input, input.len() * 8, true)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Vec<u8> {
+        //! Decrypts the given data.
+        //! # Arguments
+        //! * `input` - The ciphertext of any length.
+        //! # Returns
+        //! * Vec<u8> - The plaintext.
+
+        self.process(input, input.len() * 8, false)
+    }
+
+    pub fn encrypt_bits(&self, input: &[u8], bit_length: usize) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the first `bit_length` bits of the given data.
+        //! The bits are taken from the most significant bit of each byte.
+        //! # Arguments
+        //! * `input` - The plaintext, at least `bit_length` bits long.
+        //! * `bit_length` - The number of bits to encrypt. Must be a multiple of 8 unless `SegmentSize::CFB1` is used.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext, with the bits after `bit_length` in the last byte set to zero, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input is shorter than `bit_length` bits or the bit length doesn't fit the segment size.
+
+        self.check_bit_length(input, bit_length)?;
+
+        Ok(self.process(input, bit_length, true))
+    }
+
+    pub fn decrypt_bits(&self, input: &[u8], bit_length: usize) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the first `bit_length` bits of the given data.
+        //! The bits are taken from the most significant bit of each byte.
+        //! # Arguments
+        //! * `input` - The ciphertext, at least `bit_length` bits long.
+        //! * `bit_length` - The number of bits to decrypt. Must be a multiple of 8 unless `SegmentSize::CFB1` is used.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext, with the bits after `bit_length` in the last byte set to zero, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input is shorter than `bit_length` bits or the bit length doesn't fit the segment size.
+
+        self.check_bit_length(input, bit_length)?;
+
+        Ok(self.process(input, bit_length, false))
+    }
+
+    pub fn encryptor(&self) -> Encryptor {
+        //! Returns a streaming encryptor for encrypting the data in parts, see the `Encryptor` struct.
+
+        Encryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::cfb(self.segment_size, self.iv))
+    }
+
+    pub fn decryptor(&self) -> Decryptor {
+        //! Returns a streaming decryptor for decrypting the data in parts, see the `Decryptor` struct.
+
+        Decryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::cfb(self.segment_size, self.iv))
+    }
+}
+
+/// Functions for processing the segments.
+impl CFB {
+    fn check_bit_length(&self, input: &[u8], bit_length: usize) -> Result<(), ModeError> {
+        //! Checks that the bit length is valid for the input and the segment size.
+
+        if bit_length > input.len() * 8 || (self.segment_size != SegmentSize::CFB1 && !bit_length.is_multiple_of(8)) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        Ok(())
+    }
+
+    fn process(&self, input: &[u8], bit_length: usize, encrypt: bool) -> Vec<u8> {
+        //! Encrypts or decrypts the first `bit_length` bits of the input.
+
+        let mut output: Vec<u8> = vec![0; bit_length.div_ceil(8)];
+        let mut register: [u8; 16] = self.iv;
+
+        match self.segment_size {
+            SegmentSize::CFB1 => {
+                for i in 0..bit_length {
+                    let input_bit: u8 = (input[i / 8] >> (7 - i % 8)) & 1;
+                    let output_bit: u8 = input_bit ^ (self.aes_core.encrypt(&register)[0] >> 7);
+                    output[i / 8] |= output_bit << (7 - i % 8);
+
+                    let ciphertext_bit: u8 = if encrypt { output_bit } else { input_bit };
+                    for j in 0..15 {
+                        register[j] = (register[j] << 1) | (register[j + 1] >> 7);
+                    }
+                    register[15] = (register[15] << 1) | ciphertext_bit;
+                }
+            }
+            SegmentSize::CFB8 => {
+                for i in 0..(bit_length / 8) {
+                    output[i] = input[i] ^ self.aes_core.encrypt(&register)[0];
+
+                    register.copy_within(1.., 0);
+                    register[15] = if encrypt { output[i] } else { input[i] };
+                }
+            }
+            SegmentSize::CFB128 => {
+                for (input_chunk, output_chunk) in input[..(bit_length / 8)].chunks(16).zip(output.chunks_mut(16)) {
+                    let keystream_block: [u8; 16] = self.aes_core.encrypt(&register);
+                    for j in 0..input_chunk.len() {
+                        output_chunk[j] = input_chunk[j] ^ keystream_block[j];
+                    }
+
+                    // a partial segment can only be the last one, so the register isn't needed after it
+                    if input_chunk.len() == 16 {
+                        register.copy_from_slice(if encrypt { output_chunk } else { input_chunk });
+                    }
+                }
+            }
+        }
+
+        output
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b,
+        0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    const PLAINTEXT: [u8; 32] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    ];
+
+    #[test]
+    fn cfb1() {
+        //! Test CFB1 encryption (SP 800-38A, F.3.1)
+
+        let cfb = CFB::new(AESCore::new(AESKey::AES128(KEY)), SegmentSize::CFB1, IV);
+
+        // the first 16 bits of the plaintext, 0110 1011 1100 0001
+        assert_eq!(cfb.encrypt_bits(&PLAINTEXT, 16).unwrap(), [0b01101000, 0b10110011]);
+        assert_eq!(cfb.decrypt_bits(&[0b01101000, 0b10110011], 16).unwrap(), PLAINTEXT[..2]);
+        assert_eq!(cfb.encrypt(&PLAINTEXT[..2]), [0b01101000, 0b10110011]);
+
+        // bits after the bit length are zero
+        assert_eq!(cfb.encrypt_bits(&PLAINTEXT, 5).unwrap(), [0b01101000]);
+        assert_eq!(cfb.encrypt_bits(&PLAINTEXT, 11).unwrap(), [0b01101000, 0b10100000]);
+    }
+
+    #[test]
+    fn cfb8() {
+        //! Test CFB8 encryption (SP 800-38A, F.3.7)
+
+        let cfb = CFB::new(AESCore::new(AESKey::AES128(KEY)), SegmentSize::CFB8, IV);
+        let ciphertext: [u8; 18] = [
+            0x3b, 0x79, 0x42, 0x4c, 0x9c, 0x0d, 0xd4, 0x36, 0xba, 0xce, 0x9e, 0x0e, 0xd4, 0x58, 0x6a, 0x4f,
+            0x32, 0xb9,
+        ];
+
+        assert_eq!(cfb.encrypt(&PLAINTEXT[..18]), ciphertext);
+        assert_eq!(cfb.decrypt(&ciphertext), PLAINTEXT[..18]);
+    }
+
+    #[test]
+    fn cfb128() {
+        //! Test CFB128 encryption (SP 800-38A, F.3.13)
+
+        let cfb = CFB::new(AESCore::new(AESKey::AES128(KEY)), SegmentSize::CFB128, IV);
+        let ciphertext: [u8; 32] = [
+            0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20, 0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+            0xc8, 0xa6, 0x45, 0x37, 0xa0, 0xb3, 0xa9, 0x3f, 0xcd, 0xe3, 0xcd, 0xad, 0x9f, 0x1c, 0xe5, 0x8b,
+        ];
+
+        assert_eq!(cfb.encrypt(&PLAINTEXT), ciphertext);
+        assert_eq!(cfb.decrypt(&ciphertext), PLAINTEXT);
+
+        // the last segment may be partial
+        assert_eq!(cfb.encrypt(&PLAINTEXT[..21]), ciphertext[..21]);
+        assert_eq!(cfb.decrypt(&ciphertext[..21]), PLAINTEXT[..21]);
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid bit lengths
+
+        let mut cfb = CFB::new(AESCore::new(AESKey::AES128(KEY)), SegmentSize::CFB1, IV);
+        assert_eq!(cfb.encrypt_bits(&[0; 2], 17), Err(ModeError::InvalidInputSize));
+        assert_eq!(cfb.decrypt_bits(&[0; 2], 17), Err(ModeError::InvalidInputSize));
+        assert!(cfb.encrypt_bits(&[0; 2], 9).is_ok());
+
+        cfb.set_segment_size(SegmentSize::CFB8);
+        assert_eq!(cfb.segment_size(), SegmentSize::CFB8);
+        assert_eq!(cfb.encrypt_bits(&[0; 2], 9), Err(ModeError::InvalidInputSize));
+        assert!(cfb.encrypt_bits(&[0; 2], 8).is_ok());
+    }
+}
@@ -0,0 +1,278 @@
+//! A module containing the CMAC (OMAC1) message authentication code.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::{
+    AESCore,
+    AESKey,
+    Implementation,
+};
+use super::{
+    MacChain,
+    ModeError,
+    check_mac_tag_length,
+    constant_time_eq,
+    double,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The CMAC message authentication code, as defined in NIST SP 800-38B and RFC 4493 (also known as OMAC1).
+/// The data is chained with CBC, and the last block is XORed with one of two subkeys derived from the key before being encrypted.
+///
+/// The data can be given in several parts with `update`, and the tag is returned by `finalize`.
+/// `finalize` doesn't change the state, so more data can be added afterwards.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CMAC {
+    /// The AES core used for chaining and for deriving the subkeys.
+    aes_core: AESCore,
+    /// The subkey XORed with a complete last block.
+    first_subkey: [u8; 16],
+    /// The subkey XORed with a padded last block.
+    second_subkey: [u8; 16],
+    /// The CBC chaining of the data given so far.
+    chain: MacChain,
+}
+
+/// Public functions for computing and verifying tags.
+impl CMAC {
+    pub fn new(aes_core: AESCore) -> Self {
+        //! Creates a new CMAC instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for chaining and for deriving the subkeys.
+
+        let first_subkey: [u8; 16] = double(&aes_core.encrypt(&[0; 16]));
+
+        Self {
+            aes_core,
+            first_subkey,
+            second_subkey: double(&first_subkey),
+            chain: MacChain::new(),
+        }
+    }
+
+    pub fn prf_128(variable_key: &[u8]) -> Self {
+        //! Creates a new CMAC instance for the AES-CMAC-PRF-128 pseudo-random function (RFC 4615).
+        //! A key which isn't 16 bytes long is first hashed to 16 bytes with AES-CMAC under the zero key.
+        //! # Arguments
+        //! * `variable_key` - The key of any length.
+
+        let key: [u8; 16] = match variable_key.try_into() {
+            Ok(key) => key,
+            Err(_) => {
+                let mut cmac: CMAC = CMAC::new(AESCore::new(AESKey::AES128([0; 16])));
+                cmac.update(variable_key);
+                cmac.finalize()
+            }
+        };
+
+        Self::new(AESCore::new(AESKey::AES128(key)))
+    }
+
+    pub fn implementation(&self) -> Implementation {
+        //! Returns the implementation of the AES core used by this CMAC instance.
+
+        self.aes_core.implementation()
+    }
+
+    pub fn set_implementation(&mut self, implementation: Implementation) {
+        //! Changes the implementation of the AES core used by this CMAC instance.
+        //! The subkeys and the data given so far are kept, as every implementation gives the same output.
+
+        self.aes_core.set_implementation(implementation);
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CMAC instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CMAC instance.
+        //! The data given so far is discarded.
+
+        *self = Self::new(aes_core);
+    }
+
+    pub fn reset(&mut self) {
+        //! Discards the data given so far, so a new tag can be computed with the same key.
+
+        self.chain = MacChain::new();
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        //! Adds the next part of the data.
+        //! # Arguments
+        //! * `data` - The next part of the data, of any length.
+
+        self.chain.update(&self.aes_core, data);
+    }
+
+    pub fn finalize(&self) -> [u8; 16] {
+        //! Returns the full 16-byte tag of the data given so far.
+
+        self.chain.finalize(&self.aes_core, &self.first_subkey, &self.second_subkey)
+    }
+
+    pub fn finalize_truncated(&self, tag_length: usize) -> Result<Vec<u8>, ModeError> {
+        //! Returns the tag of the data given so far, truncated to the given length.
+        //! # Arguments
+        //! * `tag_length` - The length of the tag in bytes, from 4 to 16. At least 8 bytes are recommended.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The leftmost bytes of the tag or an error.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't between 4 and 16 bytes.
+
+        check_mac_tag_length(tag_length)?;
+
+        Ok(self.finalize()[..tag_length].to_vec())
+    }
+
+    pub fn verify(&self, tag: &[u8]) -> Result<(), ModeError> {
+        //! Verifies the given (possibly truncated) tag against the data given so far, in constant time.
+        //! # Arguments
+        //! * `tag` - The tag, from 4 to 16 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag isn't between 4 and 16 bytes long.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        check_mac_tag_length(tag.len())?;
+
+        if !constant_time_eq(&self.finalize()[..tag.len()], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const MESSAGE: [u8; 64] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+        0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+    ];
+
+    const TAGS: [(usize, [u8; 16]); 4] = [
+        (0, [0xbb, 0x1d, 0x69, 0x29, 0xe9, 0x59, 0x37, 0x28, 0x7f, 0xa3, 0x7d, 0x12, 0x9b, 0x75, 0x67, 0x46]),
+        (16, [0x07, 0x0a, 0x16, 0xb4, 0x6b, 0x4d, 0x41, 0x44, 0xf7, 0x9b, 0xdd, 0x9d, 0xd0, 0x4a, 0x28, 0x7c]),
+        (40, [0xdf, 0xa6, 0x67, 0x47, 0xde, 0x9a, 0xe6, 0x30, 0x30, 0xca, 0x32, 0x61, 0x14, 0x97, 0xc8, 0x27]),
+        (64, [0x51, 0xf0, 0xbe, 0xbf, 0x7e, 0x3b, 0x9d, 0x92, 0xfc, 0x49, 0x74, 0x17, 0x79, 0x36, 0x3c, 0xfe]),
+    ];
+
+    #[test]
+    fn finalize() {
+        //! Test the tags of the four examples (RFC 4493, section 4)
+
+        for (length, tag) in TAGS {
+            let mut cmac = CMAC::new(AESCore::new(AESKey::AES128(KEY)));
+            cmac.update(&MESSAGE[..length]);
+            assert_eq!(cmac.finalize(), tag);
+            assert!(cmac.verify(&tag).is_ok());
+        }
+    }
+
+    #[test]
+    fn update() {
+        //! Test that the tag doesn't depend on how the data is split into parts
+
+        let mut cmac = CMAC::new(AESCore::new(AESKey::AES128(KEY)));
+        for part in [&MESSAGE[..5], &MESSAGE[5..5], &MESSAGE[5..16], &MESSAGE[16..33], &MESSAGE[33..40]] {
+            cmac.update(part);
+        }
+        assert_eq!(cmac.finalize(), TAGS[2].1);
+
+        // finalize doesn't change the state
+        cmac.update(&MESSAGE[40..]);
+        assert_eq!(cmac.finalize(), TAGS[3].1);
+
+        cmac.reset();
+        assert_eq!(cmac.finalize(), TAGS[0].1);
+    }
+
+    #[test]
+    fn truncated_tags() {
+        //! Test truncated tags and the errors returned on invalid tags
+
+        let mut cmac = CMAC::new(AESCore::new(AESKey::AES128(KEY)));
+        cmac.update(&MESSAGE[..16]);
+
+        assert_eq!(cmac.finalize_truncated(8).unwrap(), TAGS[1].1[..8]);
+        assert!(cmac.verify(&TAGS[1].1[..12]).is_ok());
+        assert!(cmac.verify(&TAGS[1].1[..4]).is_ok());
+
+        assert_eq!(cmac.finalize_truncated(3), Err(ModeError::InvalidTagLength));
+        assert_eq!(cmac.finalize_truncated(17), Err(ModeError::InvalidTagLength));
+        assert_eq!(cmac.verify(&TAGS[1].1[..3]), Err(ModeError::InvalidTagLength));
+        assert_eq!(cmac.verify(&TAGS[0].1), Err(ModeError::AuthenticationFailed));
+        assert_eq!(cmac.verify(&TAGS[0].1[..8]), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn prf_128() {
+        //! Test AES-CMAC-PRF-128 with keys of 18, 16 and 10 bytes (RFC 4615, section 4)
+
+        let message: Vec<u8> = (0..20).collect();
+        let outputs: [(&[u8], [u8; 16]); 3] = [
+            (
+                &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f, 0xed, 0xcb],
+                [0x84, 0xa3, 0x48, 0xa4, 0xa4, 0x5d, 0x23, 0x5b, 0xab, 0xff, 0xfc, 0x0d, 0x2b, 0x4d, 0xa0, 0x9a],
+            ),
+            (
+                &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f],
+                [0x98, 0x0a, 0xe8, 0x7b, 0x5f, 0x4c, 0x9c, 0x52, 0x14, 0xf5, 0xb6, 0xa8, 0x45, 0x5e, 0x4c, 0x2d],
+            ),
+            (
+                &[0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09],
+                [0x29, 0x0d, 0x9e, 0x11, 0x2e, 0xdb, 0x09, 0xee, 0x14, 0x1f, 0xcf, 0x64, 0xc0, 0xb7, 0x2f, 0x3d],
+            ),
+        ];
+
+        for (variable_key, output) in outputs {
+            let mut prf = CMAC::prf_128(variable_key);
+            prf.update(&message);
+            assert_eq!(prf.finalize(), output);
+        }
+    }
+
+    #[test]
+    fn implementation() {
+        //! Test that changing the implementation keeps the data given so far
+
+        let mut cmac = CMAC::new(AESCore::new(AESKey::AES128(KEY)));
+        assert_eq!(cmac.implementation(), Implementation::Hardware);
+        cmac.update(&MESSAGE[..20]);
+
+        cmac.set_implementation(Implementation::Bitsliced);
+        assert_eq!(cmac.implementation(), Implementation::Bitsliced);
+        assert_eq!(cmac.aes_core().implementation(), Implementation::Bitsliced);
+        cmac.update(&MESSAGE[20..40]);
+        assert_eq!(cmac.finalize(), TAGS[2].1);
+    }
+}
@@ -0,0 +1,371 @@
+//! A module containing the CTR (Counter) mode of operation.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::{
+    Padding,
+    PaddingTypes,
+};
+use super::{
+    BATCH_BLOCKS,
+    Decryptor,
+    Encryptor,
+    ModeError,
+    Parallel,
+    stream::StreamState,
+};
+
+
+
+
+
+// ENUMS
+
+/// The layouts of the counter block used in the CTR mode.
+/// The nonce occupies the leftmost bytes of the counter block and is never changed.
+/// The counter occupies the rightmost bytes of the counter block and is incremented as a big-endian integer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CounterLayout {
+    /// A 96-bit nonce followed by a 32-bit counter.
+    /// This layout is used by RFC 3686 and GCM.
+    Nonce96Counter32,
+    /// A 64-bit nonce followed by a 64-bit counter.
+    Nonce64Counter64,
+    /// A 128-bit counter without a nonce.
+    Counter128,
+}
+
+impl CounterLayout {
+    fn counter_size(&self) -> usize {
+        //! Returns the size of the counter in bytes.
+
+        match self {
+            CounterLayout::Nonce96Counter32 => 4,
+            CounterLayout::Nonce64Counter64 => 8,
+            CounterLayout::Counter128 => 16,
+        }
+    }
+
+    fn max_counter(&self) -> u128 {
+        //! Returns the largest value of the counter.
+
+        u128::MAX >> (128 - 8 * self.counter_size())
+    }
+}
+
+
+
+
+
+// STRUCTS
+
+/// The CTR (Counter) mode of operation.
+/// The keystream is made by encrypting successive counter blocks and is XORed with the data.
+/// No padding is needed, so data of any length is supported, and encryption and decryption are the same operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CTR {
+    /// The AES core used to encrypt the counter blocks.
+    aes_core: AESCore,
+    /// The layout of the counter block.
+    counter_layout: CounterLayout,
+    /// The first counter block (nonce and initial counter value).
+    initial_counter_block: [u8; 16],
+}
+
+/// Public functions for encrypting and decrypting data.
+impl CTR {
+    pub fn new(aes_core: AESCore, counter_layout: CounterLayout, initial_counter_block: [u8; 16]) -> Self {
+        //! Creates a new CTR instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to encrypt the counter blocks.
+        //! * `counter_layout` - The layout of the counter block, see the `CounterLayout` enum.
+        //! * `initial_counter_block` - The first counter block. The nonce part must never be reused with the same key.
+
+        Self {
+            aes_core,
+            counter_layout,
+            initial_counter_block,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this CTR instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this CTR instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn counter_layout(&self) -> CounterLayout {
+        //! Returns the layout of the counter block used by this CTR instance.
+
+        self.counter_layout
+    }
+
+    pub fn set_counter_layout(&mut self, counter_layout: CounterLayout) {
+        //! Changes the layout of the counter block used by this CTR instance.
+
+        self.counter_layout = counter_layout;
+    }
+
+    pub fn initial_counter_block(&self) -> [u8; 16] {
+        //! Returns the first counter block used by this CTR instance.
+
+        self.initial_counter_block
+    }
+
+    pub fn set_initial_counter_block(&mut self, initial_counter_block: [u8; 16]) {
+        //! Changes the first counter block used by this CTR instance.
+
+        self.initial_counter_block = initial_counter_block;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data.
+        //! # Arguments
+        //! * `input` - The plaintext of any length.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The counter would wrap around.
+
+        let mut output: Vec<u8> = input.to_vec();
+        self.apply_keystream(0, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data.
+        //! # Arguments
+        //! * `input` - The ciphertext of any length.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The counter would wrap around.
+
+        self.encrypt(input)
+    }
+
+    pub fn encrypt_parallel(&self, parallel: &Parallel, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data, splitting it into chunks which are encrypted in parallel.
+        //! Every chunk seeks to its own counter block, so the output is the same as the output of `encrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `input` - The plaintext of any length.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The counter would wrap around.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        let ctr: CTR = *self;
+        let blocks_per_chunk: u128 = (parallel.chunk_size() / 16) as u128;
+        parallel.map_buffer(input, parallel.chunk_size(), move |index, chunk| {
+            ctr.apply_keystream(index as u128 * blocks_per_chunk, chunk)
+        })
+    }
+
+    pub fn decrypt_parallel(&self, parallel: &Parallel, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data, splitting it into chunks which are decrypted in parallel.
+        //! The output is the same as the output of `decrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `input` - The ciphertext of any length.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The counter would wrap around.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        self.encrypt_parallel(parallel, input)
+    }
+
+    pub fn encryptor(&self) -> Encryptor {
+        //! Returns a streaming encryptor for encrypting the data in parts, see the `Encryptor` struct.
+
+        Encryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::ctr(*self))
+    }
+
+    pub fn decryptor(&self) -> Decryptor {
+        //! Returns a streaming decryptor for decrypting the data in parts, see the `Decryptor` struct.
+
+        Decryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::ctr(*self))
+    }
+
+    pub fn apply_keystream(&self, block_offset: u128, buffer: &mut [u8]) -> Result<(), ModeError> {
+        //! XORs the keystream into the buffer in place, which both encrypts and decrypts.
+        //! # Arguments
+        //! * `block_offset` - The number of keystream blocks to skip, used to seek into the data.
+        //! * `buffer` - The data to be encrypted or decrypted.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The counter would wrap around. The buffer is left unchanged.
+
+        if buffer.is_empty() {
+            return Ok(());
+        }
+
+        let first_counter: u128 = self.initial_counter()
+            .checked_add(block_offset)
+            .ok_or(ModeError::CounterOverflow)?;
+        let last_counter: u128 = first_counter
+            .checked_add(((buffer.len() - 1) / 16) as u128)
+            .ok_or(ModeError::CounterOverflow)?;
+        if last_counter > self.counter_layout.max_counter() {
+            return Err(ModeError::CounterOverflow);
+        }
+
+        for (batch_index, batch) in buffer.chunks_mut(BATCH_BLOCKS * 16).enumerate() {
+            let block_count: usize = batch.len().div_ceil(16);
+            let mut keystream: [[u8; 16]; BATCH_BLOCKS] = [[0; 16]; BATCH_BLOCKS];
+            for (i, keystream_block) in keystream[..block_count].iter_mut().enumerate() {
+                *keystream_block = self.counter_block(first_counter + (batch_index * BATCH_BLOCKS + i) as u128);
+            }
+
+            self.aes_core.encrypt_blocks(&mut keystream[..block_count]);
+            for (byte, keystream_byte) in batch.iter_mut().zip(keystream.as_flattened()) {
+                *byte ^= keystream_byte;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Functions for handling the counter block.
+impl CTR {
+    fn initial_counter(&self) -> u128 {
+        //! Returns the value of the counter in the first counter block.
+
+        let counter_size: usize = self.counter_layout.counter_size();
+        let mut counter_bytes: [u8; 16] = [0; 16];
+        counter_bytes[(16 - counter_size)..].copy_from_slice(&self.initial_counter_block[(16 - counter_size)..]);
+
+        u128::from_be_bytes(counter_bytes)
+    }
+
+    fn counter_block(&self, counter: u128) -> [u8; 16] {
+        //! Returns the counter block with the given counter value.
+
+        let counter_size: usize = self.counter_layout.counter_size();
+        let mut counter_block: [u8; 16] = self.initial_counter_block;
+        counter_block[(16 - counter_size)..].copy_from_slice(&counter.to_be_bytes()[(16 - counter_size)..]);
+
+        counter_block
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    #[test]
+    fn encrypt() {
+        //! Test encryption with a 128-bit counter (SP 800-38A, F.5.1)
+
+        let ctr = CTR::new(
+            AESCore::new(AESKey::AES128([
+                0x2b, 0x7e, 0x15, 0x16, 0x28, 0xae, 0xd2, 0xa6, 0xab, 0xf7, 0x15, 0x88, 0x09, 0xcf, 0x4f, 0x3c,
+            ])),
+            CounterLayout::Counter128,
+            [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff],
+        );
+        let plaintext: [u8; 64] = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+            0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+            0x30, 0xc8, 0x1c, 0x46, 0xa3, 0x5c, 0xe4, 0x11, 0xe5, 0xfb, 0xc1, 0x19, 0x1a, 0x0a, 0x52, 0xef,
+            0xf6, 0x9f, 0x24, 0x45, 0xdf, 0x4f, 0x9b, 0x17, 0xad, 0x2b, 0x41, 0x7b, 0xe6, 0x6c, 0x37, 0x10,
+        ];
+        let ciphertext: [u8; 64] = [
+            0x87, 0x4d, 0x61, 0x91, 0xb6, 0x20, 0xe3, 0x26, 0x1b, 0xef, 0x68, 0x64, 0x99, 0x0d, 0xb6, 0xce,
+            0x98, 0x06, 0xf6, 0x6b, 0x79, 0x70, 0xfd, 0xff, 0x86, 0x17, 0x18, 0x7b, 0xb9, 0xff, 0xfd, 0xff,
+            0x5a, 0xe4, 0xdf, 0x3e, 0xdb, 0xd5, 0xd3, 0x5e, 0x5b, 0x4f, 0x09, 0x02, 0x0d, 0xb0, 0x3e, 0xab,
+            0x1e, 0x03, 0x1d, 0xda, 0x2f, 0xbe, 0x03, 0xd1, 0x79, 0x21, 0x70, 0xa0, 0xf3, 0x00, 0x9c, 0xee,
+        ];
+
+        assert_eq!(ctr.encrypt(&plaintext).unwrap(), ciphertext);
+        assert_eq!(ctr.decrypt(&ciphertext).unwrap(), plaintext);
+
+        // partial blocks are encrypted with a truncated keystream block
+        assert_eq!(ctr.encrypt(&plaintext[..37]).unwrap(), ciphertext[..37]);
+    }
+
+    #[test]
+    fn nonce_and_counter() {
+        //! Test encryption with a 96-bit nonce and a 32-bit counter (RFC 3686, test vector #1)
+
+        let ctr = CTR::new(
+            AESCore::new(AESKey::AES128([
+                0xae, 0x68, 0x52, 0xf8, 0x12, 0x10, 0x67, 0xcc, 0x4b, 0xf7, 0xa5, 0x76, 0x55, 0x77, 0xf3, 0x9e,
+            ])),
+            CounterLayout::Nonce96Counter32,
+            [0x00, 0x00, 0x00, 0x30, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01],
+        );
+
+        assert_eq!(ctr.encrypt("Single block msg".as_bytes()).unwrap(), [
+            0xe4, 0x09, 0x5d, 0x4f, 0xb7, 0xa7, 0xb3, 0x79, 0x2d, 0x61, 0x75, 0xa3, 0x26, 0x13, 0x11, 0xb8,
+        ]);
+    }
+
+    #[test]
+    fn apply_keystream() {
+        //! Test in-place encryption and seeking to a block offset
+
+        let ctr = CTR::new(AESCore::new(AESKey::AES128([0x42; 16])), CounterLayout::Nonce64Counter64, [0x24; 16]);
+        let plaintext: Vec<u8> = (0..100).collect();
+        let ciphertext: Vec<u8> = ctr.encrypt(&plaintext).unwrap();
+
+        let mut buffer: Vec<u8> = plaintext.clone();
+        ctr.apply_keystream(0, &mut buffer).unwrap();
+        assert_eq!(buffer, ciphertext);
+
+        let mut buffer: Vec<u8> = plaintext[48..].to_vec();
+        ctr.apply_keystream(3, &mut buffer).unwrap();
+        assert_eq!(buffer, ciphertext[48..]);
+        ctr.apply_keystream(3, &mut buffer).unwrap();
+        assert_eq!(buffer, plaintext[48..]);
+    }
+
+    #[test]
+    fn counter_overflow() {
+        //! Test that wrapping the counter around is detected
+
+        let mut initial_counter_block: [u8; 16] = [0x11; 16];
+        initial_counter_block[12..].fill(0xff);
+        let mut ctr = CTR::new(AESCore::new(AESKey::AES128([0; 16])), CounterLayout::Nonce96Counter32, initial_counter_block);
+
+        // only the last counter value is left
+        assert!(ctr.encrypt(&[0; 16]).is_ok());
+        assert_eq!(ctr.encrypt(&[0; 17]), Err(ModeError::CounterOverflow));
+        assert_eq!(ctr.apply_keystream(1, &mut [0; 1]), Err(ModeError::CounterOverflow));
+
+        // the same counter block has plenty of room with a wider counter
+        ctr.set_counter_layout(CounterLayout::Nonce64Counter64);
+        assert!(ctr.encrypt(&[0; 1000]).is_ok());
+
+        ctr.set_counter_layout(CounterLayout::Counter128);
+        ctr.set_initial_counter_block([0xff; 16]);
+        assert!(ctr.encrypt(&[0; 16]).is_ok());
+        assert_eq!(ctr.encrypt(&[0; 17]), Err(ModeError::CounterOverflow));
+        assert_eq!(ctr.apply_keystream(u128::MAX, &mut [0; 1]), Err(ModeError::CounterOverflow));
+
+        // nothing is encrypted, so nothing can overflow
+        assert_eq!(ctr.encrypt(&[]), Ok(Vec::new()));
+    }
+}
@@ -0,0 +1,388 @@
+//! A module containing the EAX authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    CMAC,
+    ModeError,
+    check_mac_tag_length,
+    constant_time_eq,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The EAX authenticated encryption mode, as defined by Bellare, Rogaway and Wagner.
+/// The nonce, the header (associated data) and the ciphertext are authenticated with OMAC (CMAC) tweaked with a different block each,
+/// and the data is encrypted in counter mode, starting with the OMAC of the nonce.
+///
+/// The sealed output is the ciphertext followed by the authentication tag.
+/// The nonce can be of any length, but must never be reused with the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EAX {
+    /// The CMAC instance with no data, holding the AES core and the subkeys.
+    cmac: CMAC,
+    /// The length of the authentication tag in bytes.
+    tag_length: usize,
+}
+
+/// Public functions for sealing and opening data.
+impl EAX {
+    pub fn new(aes_core: AESCore, tag_length: usize) -> Result<Self, ModeError> {
+        //! Creates a new EAX instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for OMAC and for the encryption.
+        //! * `tag_length` - The length of the authentication tag in bytes, from 4 to 16.
+        //! # Returns
+        //! * Result<EAX, ModeError> - The EAX instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't between 4 and 16 bytes.
+
+        check_mac_tag_length(tag_length)?;
+
+        Ok(Self {
+            cmac: CMAC::new(aes_core),
+            tag_length,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this EAX instance.
+
+        self.cmac.aes_core()
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this EAX instance.
+
+        self.cmac.set_aes_core(aes_core);
+    }
+
+    pub fn tag_length(&self) -> usize {
+        //! Returns the length of the authentication tag in bytes.
+
+        self.tag_length
+    }
+
+    pub fn set_tag_length(&mut self, tag_length: usize) -> Result<(), ModeError> {
+        //! Changes the length of the authentication tag in bytes.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't between 4 and 16 bytes.
+
+        check_mac_tag_length(tag_length)?;
+        self.tag_length = tag_length;
+
+        Ok(())
+    }
+
+    pub fn seal(&self, nonce: &[u8], header: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        //! Encrypts and authenticates the plaintext, and authenticates the header.
+        //! # Arguments
+        //! * `nonce` - The nonce of any length.
+        //! * `header` - The associated data which is authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Vec<u8> - The ciphertext followed by the tag.
+
+        let nonce_mac: [u8; 16] = self.omac(0, nonce);
+
+        let mut output: Vec<u8> = plaintext.to_vec();
+        self.ctr(&nonce_mac, &mut output);
+        let tag: [u8; 16] = self.tag(&nonce_mac, header, &output);
+        output.extend_from_slice(&tag[..self.tag_length]);
+
+        output
+    }
+
+    pub fn open(&self, nonce: &[u8], header: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Verifies the tag and decrypts the ciphertext.
+        //! The plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `nonce` - The nonce used for sealing.
+        //! * `header` - The associated data used for sealing.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if sealed.len() < self.tag_length {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let nonce_mac: [u8; 16] = self.omac(0, nonce);
+
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - self.tag_length);
+        let expected_tag: [u8; 16] = self.tag(&nonce_mac, header, ciphertext);
+        if !constant_time_eq(&expected_tag[..self.tag_length], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        let mut output: Vec<u8> = ciphertext.to_vec();
+        self.ctr(&nonce_mac, &mut output);
+
+        Ok(output)
+    }
+}
+
+/// Functions used in the EAX algorithm.
+impl EAX {
+    fn omac(&self, tweak: u8, data: &[u8]) -> [u8; 16] {
+        //! Computes OMAC of the data prefixed with the tweak encoded as a full block.
+
+        let mut tweak_block: [u8; 16] = [0; 16];
+        tweak_block[15] = tweak;
+
+        let mut cmac: CMAC = self.cmac.clone();
+        cmac.update(&tweak_block);
+        cmac.update(data);
+
+        cmac.finalize()
+    }
+
+    fn tag(&self, nonce_mac: &[u8; 16], header: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+        //! Computes the full 16-byte authentication tag, the XOR of the OMACs of the nonce, the header and the ciphertext.
+
+        let mut tag: [u8; 16] = *nonce_mac;
+        xor_blocks(&mut tag, &self.omac(1, header));
+        xor_blocks(&mut tag, &self.omac(2, ciphertext));
+
+        tag
+    }
+
+    fn ctr(&self, nonce_mac: &[u8; 16], buffer: &mut [u8]) {
+        //! Encrypts or decrypts the buffer in counter mode, starting with the OMAC of the nonce.
+        //! The whole block is incremented as a big-endian number modulo 2^128.
+
+        let aes_core: AESCore = self.cmac.aes_core();
+
+        let mut counter: u128 = u128::from_be_bytes(*nonce_mac);
+        for chunk in buffer.chunks_mut(16) {
+            let keystream_block: [u8; 16] = aes_core.encrypt(&counter.to_be_bytes());
+            for (byte, keystream_byte) in chunk.iter_mut().zip(keystream_block) {
+                *byte ^= keystream_byte;
+            }
+            counter = counter.wrapping_add(1);
+        }
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    /// A test vector of the EAX paper.
+    struct Vector {
+        /// The plaintext.
+        message: &'static [u8],
+        /// The AES-128 key.
+        key: [u8; 16],
+        /// The nonce.
+        nonce: [u8; 16],
+        /// The associated data.
+        header: [u8; 8],
+        /// The ciphertext followed by the tag.
+        sealed: &'static [u8],
+    }
+
+    /// The test vectors from the EAX paper (appendix).
+    const VECTORS: [Vector; 10] = [
+        Vector {
+            message: &[],
+            key: [0x23, 0x39, 0x52, 0xde, 0xe4, 0xd5, 0xed, 0x5f, 0x9b, 0x9c, 0x6d, 0x6f, 0xf8, 0x0f, 0xf4, 0x78],
+            nonce: [0x62, 0xec, 0x67, 0xf9, 0xc3, 0xa4, 0xa4, 0x07, 0xfc, 0xb2, 0xa8, 0xc4, 0x90, 0x31, 0xa8, 0xb3],
+            header: [0x6b, 0xfb, 0x91, 0x4f, 0xd0, 0x7e, 0xae, 0x6b],
+            sealed: &[0xe0, 0x37, 0x83, 0x0e, 0x83, 0x89, 0xf2, 0x7b, 0x02, 0x5a, 0x2d, 0x65, 0x27, 0xe7, 0x9d, 0x01],
+        },
+        Vector {
+            message: &[0xf7, 0xfb],
+            key: [0x91, 0x94, 0x5d, 0x3f, 0x4d, 0xcb, 0xee, 0x0b, 0xf4, 0x5e, 0xf5, 0x22, 0x55, 0xf0, 0x95, 0xa4],
+            nonce: [0xbe, 0xca, 0xf0, 0x43, 0xb0, 0xa2, 0x3d, 0x84, 0x31, 0x94, 0xba, 0x97, 0x2c, 0x66, 0xde, 0xbd],
+            header: [0xfa, 0x3b, 0xfd, 0x48, 0x06, 0xeb, 0x53, 0xfa],
+            sealed: &[
+                0x19, 0xdd, 0x5c, 0x4c, 0x93, 0x31, 0x04, 0x9d, 0x0b, 0xda, 0xb0, 0x27, 0x74, 0x08, 0xf6, 0x79,
+                0x67, 0xe5,
+            ],
+        },
+        Vector {
+            message: &[0x1a, 0x47, 0xcb, 0x49, 0x33],
+            key: [0x01, 0xf7, 0x4a, 0xd6, 0x40, 0x77, 0xf2, 0xe7, 0x04, 0xc0, 0xf6, 0x0a, 0xda, 0x3d, 0xd5, 0x23],
+            nonce: [0x70, 0xc3, 0xdb, 0x4f, 0x0d, 0x26, 0x36, 0x84, 0x00, 0xa1, 0x0e, 0xd0, 0x5d, 0x2b, 0xff, 0x5e],
+            header: [0x23, 0x4a, 0x34, 0x63, 0xc1, 0x26, 0x4a, 0xc6],
+            sealed: &[
+                0xd8, 0x51, 0xd5, 0xba, 0xe0, 0x3a, 0x59, 0xf2, 0x38, 0xa2, 0x3e, 0x39, 0x19, 0x9d, 0xc9, 0x26,
+                0x66, 0x26, 0xc4, 0x0f, 0x80,
+            ],
+        },
+        Vector {
+            message: &[0x48, 0x1c, 0x9e, 0x39, 0xb1],
+            key: [0xd0, 0x7c, 0xf6, 0xcb, 0xb7, 0xf3, 0x13, 0xbd, 0xde, 0x66, 0xb7, 0x27, 0xaf, 0xd3, 0xc5, 0xe8],
+            nonce: [0x84, 0x08, 0xdf, 0xff, 0x3c, 0x1a, 0x2b, 0x12, 0x92, 0xdc, 0x19, 0x9e, 0x46, 0xb7, 0xd6, 0x17],
+            header: [0x33, 0xcc, 0xe2, 0xea, 0xbf, 0xf5, 0xa7, 0x9d],
+            sealed: &[
+                0x63, 0x2a, 0x9d, 0x13, 0x1a, 0xd4, 0xc1, 0x68, 0xa4, 0x22, 0x5d, 0x8e, 0x1f, 0xf7, 0x55, 0x93,
+                0x99, 0x74, 0xa7, 0xbe, 0xde,
+            ],
+        },
+        Vector {
+            message: &[0x40, 0xd0, 0xc0, 0x7d, 0xa5, 0xe4],
+            key: [0x35, 0xb6, 0xd0, 0x58, 0x00, 0x05, 0xbb, 0xc1, 0x2b, 0x05, 0x87, 0x12, 0x45, 0x57, 0xd2, 0xc2],
+            nonce: [0xfd, 0xb6, 0xb0, 0x66, 0x76, 0xee, 0xdc, 0x5c, 0x61, 0xd7, 0x42, 0x76, 0xe1, 0xf8, 0xe8, 0x16],
+            header: [0xae, 0xb9, 0x6e, 0xae, 0xbe, 0x29, 0x70, 0xe9],
+            sealed: &[
+                0x07, 0x1d, 0xfe, 0x16, 0xc6, 0x75, 0xcb, 0x06, 0x77, 0xe5, 0x36, 0xf7, 0x3a, 0xfe, 0x6a, 0x14,
+                0xb7, 0x4e, 0xe4, 0x98, 0x44, 0xdd,
+            ],
+        },
+        Vector {
+            message: &[0x4d, 0xe3, 0xb3, 0x5c, 0x3f, 0xc0, 0x39, 0x24, 0x5b, 0xd1, 0xfb, 0x7d],
+            key: [0xbd, 0x8e, 0x6e, 0x11, 0x47, 0x5e, 0x60, 0xb2, 0x68, 0x78, 0x4c, 0x38, 0xc6, 0x2f, 0xeb, 0x22],
+            nonce: [0x6e, 0xac, 0x5c, 0x93, 0x07, 0x2d, 0x8e, 0x85, 0x13, 0xf7, 0x50, 0x93, 0x5e, 0x46, 0xda, 0x1b],
+            header: [0xd4, 0x48, 0x2d, 0x1c, 0xa7, 0x8d, 0xce, 0x0f],
+            sealed: &[
+                0x83, 0x5b, 0xb4, 0xf1, 0x5d, 0x74, 0x3e, 0x35, 0x0e, 0x72, 0x84, 0x14, 0xab, 0xb8, 0x64, 0x4f,
+                0xd6, 0xcc, 0xb8, 0x69, 0x47, 0xc5, 0xe1, 0x05, 0x90, 0x21, 0x0a, 0x4f,
+            ],
+        },
+        Vector {
+            message: &[
+                0x8b, 0x0a, 0x79, 0x30, 0x6c, 0x9c, 0xe7, 0xed, 0x99, 0xda, 0xe4, 0xf8, 0x7f, 0x8d, 0xd6, 0x16,
+                0x36,
+            ],
+            key: [0x7c, 0x77, 0xd6, 0xe8, 0x13, 0xbe, 0xd5, 0xac, 0x98, 0xba, 0xa4, 0x17, 0x47, 0x7a, 0x2e, 0x7d],
+            nonce: [0x1a, 0x8c, 0x98, 0xdc, 0xd7, 0x3d, 0x38, 0x39, 0x3b, 0x2b, 0xf1, 0x56, 0x9d, 0xee, 0xfc, 0x19],
+            header: [0x65, 0xd2, 0x01, 0x79, 0x90, 0xd6, 0x25, 0x28],
+            sealed: &[
+                0x02, 0x08, 0x3e, 0x39, 0x79, 0xda, 0x01, 0x48, 0x12, 0xf5, 0x9f, 0x11, 0xd5, 0x26, 0x30, 0xda,
+                0x30, 0x13, 0x73, 0x27, 0xd1, 0x06, 0x49, 0xb0, 0xaa, 0x6e, 0x1c, 0x18, 0x1d, 0xb6, 0x17, 0xd7,
+                0xf2,
+            ],
+        },
+        Vector {
+            message: &[
+                0x1b, 0xda, 0x12, 0x2b, 0xce, 0x8a, 0x8d, 0xba, 0xf1, 0x87, 0x7d, 0x96, 0x2b, 0x85, 0x92, 0xdd,
+                0x2d, 0x56,
+            ],
+            key: [0x5f, 0xff, 0x20, 0xca, 0xfa, 0xb1, 0x19, 0xca, 0x2f, 0xc7, 0x35, 0x49, 0xe2, 0x0f, 0x5b, 0x0d],
+            nonce: [0xdd, 0xe5, 0x9b, 0x97, 0xd7, 0x22, 0x15, 0x6d, 0x4d, 0x9a, 0xff, 0x2b, 0xc7, 0x55, 0x98, 0x26],
+            header: [0x54, 0xb9, 0xf0, 0x4e, 0x6a, 0x09, 0x18, 0x9a],
+            sealed: &[
+                0x2e, 0xc4, 0x7b, 0x2c, 0x49, 0x54, 0xa4, 0x89, 0xaf, 0xc7, 0xba, 0x48, 0x97, 0xed, 0xcd, 0xae,
+                0x8c, 0xc3, 0x3b, 0x60, 0x45, 0x05, 0x99, 0xbd, 0x02, 0xc9, 0x63, 0x82, 0x90, 0x2a, 0xef, 0x7f,
+                0x83, 0x2a,
+            ],
+        },
+        Vector {
+            message: &[
+                0x6c, 0xf3, 0x67, 0x20, 0x87, 0x2b, 0x85, 0x13, 0xf6, 0xea, 0xb1, 0xa8, 0xa4, 0x44, 0x38, 0xd5,
+                0xef, 0x11,
+            ],
+            key: [0xa4, 0xa4, 0x78, 0x2b, 0xcf, 0xfd, 0x3e, 0xc5, 0xe7, 0xef, 0x6d, 0x8c, 0x34, 0xa5, 0x61, 0x23],
+            nonce: [0xb7, 0x81, 0xfc, 0xf2, 0xf7, 0x5f, 0xa5, 0xa8, 0xde, 0x97, 0xa9, 0xca, 0x48, 0xe5, 0x22, 0xec],
+            header: [0x89, 0x9a, 0x17, 0x58, 0x97, 0x56, 0x1d, 0x7e],
+            sealed: &[
+                0x0d, 0xe1, 0x8f, 0xd0, 0xfd, 0xd9, 0x1e, 0x7a, 0xf1, 0x9f, 0x1d, 0x8e, 0xe8, 0x73, 0x39, 0x38,
+                0xb1, 0xe8, 0xe7, 0xf6, 0xd2, 0x23, 0x16, 0x18, 0x10, 0x2f, 0xdb, 0x7f, 0xe5, 0x5f, 0xf1, 0x99,
+                0x17, 0x00,
+            ],
+        },
+        Vector {
+            message: &[
+                0xca, 0x40, 0xd7, 0x44, 0x6e, 0x54, 0x5f, 0xfa, 0xed, 0x3b, 0xd1, 0x2a, 0x74, 0x0a, 0x65, 0x9f,
+                0xfb, 0xbb, 0x3c, 0xea, 0xb7,
+            ],
+            key: [0x83, 0x95, 0xfc, 0xf1, 0xe9, 0x5b, 0xeb, 0xd6, 0x97, 0xbd, 0x01, 0x0b, 0xc7, 0x66, 0xaa, 0xc3],
+            nonce: [0x22, 0xe7, 0xad, 0xd9, 0x3c, 0xfc, 0x63, 0x93, 0xc5, 0x7e, 0xc0, 0xb3, 0xc1, 0x7d, 0x6b, 0x44],
+            header: [0x12, 0x67, 0x35, 0xfc, 0xc3, 0x20, 0xd2, 0x5a],
+            sealed: &[
+                0xcb, 0x89, 0x20, 0xf8, 0x7a, 0x6c, 0x75, 0xcf, 0xf3, 0x96, 0x27, 0xb5, 0x6e, 0x3e, 0xd1, 0x97,
+                0xc5, 0x52, 0xd2, 0x95, 0xa7, 0xcf, 0xc4, 0x6a, 0xfc, 0x25, 0x3b, 0x46, 0x52, 0xb1, 0xaf, 0x37,
+                0x95, 0xb1, 0x24, 0xab, 0x6e,
+            ],
+        },
+    ];
+
+    #[test]
+    fn seal() {
+        //! Test sealing and opening (EAX paper, all ten test vectors)
+
+        for vector in VECTORS {
+            let eax = EAX::new(AESCore::new(AESKey::AES128(vector.key)), 16).unwrap();
+
+            assert_eq!(eax.seal(&vector.nonce, &vector.header, vector.message), vector.sealed);
+            assert_eq!(eax.open(&vector.nonce, &vector.header, vector.sealed).unwrap(), vector.message);
+        }
+    }
+
+    #[test]
+    fn nonce_length() {
+        //! Test that nonces of any length, including the empty one, can be used
+
+        let eax = EAX::new(AESCore::new(AESKey::AES256([0x42; 32])), 16).unwrap();
+        let data: Vec<u8> = (0..50).collect();
+
+        let mut sealed_outputs: Vec<Vec<u8>> = Vec::new();
+        for nonce_length in [0, 1, 7, 12, 16, 17, 40] {
+            let sealed: Vec<u8> = eax.seal(&data[..nonce_length], b"header", &data);
+            assert_eq!(eax.open(&data[..nonce_length], b"header", &sealed).unwrap(), data);
+            assert!(!sealed_outputs.contains(&sealed));
+            sealed_outputs.push(sealed);
+        }
+    }
+
+    #[test]
+    fn tag_length() {
+        //! Test that shorter tags are the leftmost bytes of the full tag
+
+        let mut eax = EAX::new(AESCore::new(AESKey::AES192([0x24; 24])), 16).unwrap();
+        let sealed: Vec<u8> = eax.seal(b"nonce", b"header", b"tag length");
+
+        for tag_length in 4..=16 {
+            eax.set_tag_length(tag_length).unwrap();
+            assert_eq!(eax.tag_length(), tag_length);
+
+            let truncated: Vec<u8> = eax.seal(b"nonce", b"header", b"tag length");
+            assert_eq!(truncated, sealed[..(10 + tag_length)]);
+            assert_eq!(eax.open(b"nonce", b"header", &truncated).unwrap(), b"tag length");
+        }
+
+        for tag_length in [0, 3, 17] {
+            assert_eq!(eax.set_tag_length(tag_length), Err(ModeError::InvalidTagLength));
+            assert_eq!(EAX::new(AESCore::new(AESKey::AES128([0; 16])), tag_length), Err(ModeError::InvalidTagLength));
+        }
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that any modification is detected
+
+        let eax = EAX::new(AESCore::new(AESKey::AES128([0x11; 16])), 16).unwrap();
+        let sealed: Vec<u8> = eax.seal(b"nonce", b"header", b"authenticated");
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(eax.open(b"nonce", b"header", &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(eax.open(b"nonce", b"Header", &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(eax.open(b"Nonce", b"header", &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(eax.open(b"nonce", b"header", &sealed[..15]), Err(ModeError::InvalidInputSize));
+    }
+}
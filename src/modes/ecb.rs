@@ -0,0 +1,283 @@
+//! A module containing the ECB (Electronic Codebook) mode of operation.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::Padding;
+use super::{
+    Decryptor,
+    Encryptor,
+    ModeError,
+    Parallel,
+    check_padded_size,
+    remove_padding,
+    split_for_padding,
+    stream::StreamState,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The ECB (Electronic Codebook) mode of operation.
+/// Every block is encrypted independently, so identical plaintext blocks give identical ciphertext blocks.
+/// The last block is padded with the given padding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ECB {
+    /// The AES core used to encrypt and decrypt the blocks.
+    aes_core: AESCore,
+    /// The padding applied to the last block.
+    padding: Padding,
+}
+
+/// Public functions for encrypting and decrypting data.
+impl ECB {
+    pub fn new(aes_core: AESCore, padding: Padding) -> Self {
+        //! Creates a new ECB instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to encrypt and decrypt the blocks.
+        //! * `padding` - The padding applied to the last block. With `PaddingTypes::None` the input must be a multiple of 16 bytes long.
+
+        Self {
+            aes_core,
+            padding,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this ECB instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this ECB instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn padding(&self) -> Padding {
+        //! Returns the padding used by this ECB instance.
+
+        self.padding
+    }
+
+    pub fn set_padding(&mut self, padding: Padding) {
+        //! Changes the padding used by this ECB instance.
+
+        self.padding = padding;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data.
+        //! # Arguments
+        //! * `input` - The plaintext of any length (a multiple of 16 bytes with `PaddingTypes::None`).
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - No padding is used and the input isn't a multiple of 16 bytes long.
+
+        let (full_blocks, last_block) = split_for_padding(&self.padding, input)?;
+
+        let mut output: Vec<u8> = Vec::with_capacity(full_blocks.len() + 16);
+        output.extend_from_slice(full_blocks);
+        encrypt_blocks(&self.aes_core, &mut output);
+        if let Some(last_block) = last_block {
+            output.extend_from_slice(&self.aes_core.encrypt(&last_block));
+        }
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data.
+        //! # Arguments
+        //! * `input` - The ciphertext, a multiple of 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input isn't a multiple of 16 bytes long, or is empty while padding is used.
+        //! * ModeError::Padding - The padding of the last block is invalid.
+
+        check_padded_size(&self.padding, input)?;
+
+        let mut output: Vec<u8> = input.to_vec();
+        decrypt_blocks(&self.aes_core, &mut output);
+        remove_padding(&self.padding, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn encrypt_parallel(&self, parallel: &Parallel, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given data, splitting it into chunks which are encrypted in parallel.
+        //! The output is the same as the output of `encrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `input` - The plaintext of any length (a multiple of 16 bytes with `PaddingTypes::None`).
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - No padding is used and the input isn't a multiple of 16 bytes long.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        let (full_blocks, last_block) = split_for_padding(&self.padding, input)?;
+
+        let aes_core: AESCore = self.aes_core;
+        let mut output: Vec<u8> = parallel.map_buffer(full_blocks, parallel.chunk_size(), move |_, chunk| {
+            encrypt_blocks(&aes_core, chunk);
+            Ok(())
+        })?;
+        if let Some(last_block) = last_block {
+            output.extend_from_slice(&self.aes_core.encrypt(&last_block));
+        }
+
+        Ok(output)
+    }
+
+    pub fn decrypt_parallel(&self, parallel: &Parallel, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given data, splitting it into chunks which are decrypted in parallel.
+        //! The output is the same as the output of `decrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `input` - The ciphertext, a multiple of 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The input isn't a multiple of 16 bytes long, or is empty while padding is used.
+        //! * ModeError::Padding - The padding of the last block is invalid.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        check_padded_size(&self.padding, input)?;
+
+        let aes_core: AESCore = self.aes_core;
+        let mut output: Vec<u8> = parallel.map_buffer(input, parallel.chunk_size(), move |_, chunk| {
+            decrypt_blocks(&aes_core, chunk);
+            Ok(())
+        })?;
+        remove_padding(&self.padding, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn encryptor(&self) -> Encryptor {
+        //! Returns a streaming encryptor for encrypting the data in parts, see the `Encryptor` struct.
+
+        Encryptor::new(self.aes_core, self.padding, StreamState::ecb())
+    }
+
+    pub fn decryptor(&self) -> Decryptor {
+        //! Returns a streaming decryptor for decrypting the data in parts, see the `Decryptor` struct.
+
+        Decryptor::new(self.aes_core, self.padding, StreamState::ecb())
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn encrypt_blocks(aes_core: &AESCore, buffer: &mut [u8]) {
+    //! Encrypts the blocks of the buffer in place in ECB mode.
+    //! The buffer must be a multiple of 16 bytes long.
+
+    aes_core.encrypt_blocks(buffer.as_chunks_mut().0);
+}
+
+fn decrypt_blocks(aes_core: &AESCore, buffer: &mut [u8]) {
+    //! Decrypts the blocks of the buffer in place in ECB mode.
+    //! The buffer must be a multiple of 16 bytes long.
+
+    aes_core.decrypt_blocks(buffer.as_chunks_mut().0);
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+    use crate::padding::{
+        PaddingError,
+        PaddingTypes,
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    #[test]
+    fn encrypt() {
+        //! Test encryption of two blocks without padding (SP 800-38A, F.1.1)
+
+        let ecb = ECB::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::None));
+        let plaintext: [u8; 32] = [
+            0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+            0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+        ];
+        let ciphertext: [u8; 32] = [
+            0x3a, 0xd7, 0x7b, 0xb4, 0x0d, 0x7a, 0x36, 0x60, 0xa8, 0x9e, 0xca, 0xf3, 0x24, 0x66, 0xef, 0x97,
+            0xf5, 0xd3, 0xd5, 0x85, 0x03, 0xb9, 0x69, 0x9d, 0xe7, 0x85, 0x89, 0x5a, 0x96, 0xfd, 0xba, 0xaf,
+        ];
+
+        assert_eq!(ecb.encrypt(&plaintext).unwrap(), ciphertext);
+        assert_eq!(ecb.decrypt(&ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn padding() {
+        //! Test that padding is added and removed for every input length
+
+        for padding_type in [PaddingTypes::PKCS7, PaddingTypes::ISO78164, PaddingTypes::X923] {
+            let ecb = ECB::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(padding_type));
+
+            for length in 0..50 {
+                let plaintext: Vec<u8> = (0..length as u8).collect();
+                let ciphertext = ecb.encrypt(&plaintext).unwrap();
+
+                assert_eq!(ciphertext.len(), (length / 16 + 1) * 16);
+                assert_eq!(ecb.decrypt(&ciphertext).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let mut ecb = ECB::new(AESCore::new(AESKey::AES128(KEY)), Padding::new(PaddingTypes::None));
+        assert_eq!(ecb.encrypt(&[0; 15]), Err(ModeError::InvalidInputSize));
+        assert_eq!(ecb.decrypt(&[0; 17]), Err(ModeError::InvalidInputSize));
+        assert_eq!(ecb.encrypt(&[]), Ok(Vec::new()));
+        assert_eq!(ecb.decrypt(&[]), Ok(Vec::new()));
+
+        let ciphertext = ecb.encrypt(&[0x11; 16]).unwrap();
+
+        ecb.set_padding(Padding::new(PaddingTypes::PKCS7));
+        assert_eq!(ecb.padding(), Padding::new(PaddingTypes::PKCS7));
+        assert_eq!(ecb.decrypt(&[]), Err(ModeError::InvalidInputSize));
+        assert_eq!(ecb.decrypt(&ciphertext), Err(ModeError::Padding(PaddingError::InvalidPadding)));
+
+        // a last block decrypting to zeros has no padding marker or length
+        ecb.set_padding(Padding::new(PaddingTypes::None));
+        let ciphertext = ecb.encrypt(&[0; 16]).unwrap();
+        for padding_type in [PaddingTypes::PKCS7, PaddingTypes::ISO78164, PaddingTypes::X923] {
+            ecb.set_padding(Padding::new(padding_type));
+            assert_eq!(ecb.decrypt(&ciphertext), Err(ModeError::Padding(PaddingError::InvalidPadding)));
+        }
+    }
+}
@@ -0,0 +1,533 @@
+//! A module containing the GCM (Galois/Counter Mode) authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    ModeError,
+    Parallel,
+    constant_time_eq,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The GCM (Galois/Counter Mode) authenticated encryption mode, as defined in NIST SP 800-38D.
+/// The data is encrypted in counter mode, and the ciphertext and the associated data are authenticated with GHASH.
+///
+/// The sealed output is the ciphertext followed by the authentication tag.
+/// The IV (nonce) must never be reused with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GCM {
+    /// The AES core used for encryption and for deriving the hash subkey.
+    aes_core: AESCore,
+    /// The hash subkey H, the encryption of the zero block.
+    hash_subkey: [u8; 16],
+    /// The length of the authentication tag in bytes.
+    tag_length: usize,
+}
+
+/// Public functions for sealing and opening data.
+impl GCM {
+    pub fn new(aes_core: AESCore, tag_length: usize) -> Result<Self, ModeError> {
+        //! Creates a new GCM instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for encryption and for deriving the hash subkey.
+        //! * `tag_length` - The length of the authentication tag in bytes. Must be 16, 15, 14, 13, 12, 8 or 4.
+        //! # Returns
+        //! * Result<GCM, ModeError> - The GCM instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't allowed by SP 800-38D.
+
+        Self::check_tag_length(tag_length)?;
+
+        Ok(Self {
+            aes_core,
+            hash_subkey: aes_core.encrypt(&[0; 16]),
+            tag_length,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this GCM instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this GCM instance.
+
+        self.aes_core = aes_core;
+        self.hash_subkey = aes_core.encrypt(&[0; 16]);
+    }
+
+    pub fn tag_length(&self) -> usize {
+        //! Returns the length of the authentication tag in bytes.
+
+        self.tag_length
+    }
+
+    pub fn set_tag_length(&mut self, tag_length: usize) -> Result<(), ModeError> {
+        //! Changes the length of the authentication tag in bytes.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't 16, 15, 14, 13, 12, 8 or 4.
+
+        Self::check_tag_length(tag_length)?;
+        self.tag_length = tag_length;
+
+        Ok(())
+    }
+
+    pub fn seal(&self, iv: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, and authenticates the associated data.
+        //! # Arguments
+        //! * `iv` - The initialization vector (nonce) of any non-zero length. 12 bytes is recommended.
+        //! * `associated_data` - The data which is authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext followed by the tag, or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty.
+        //! * ModeError::InvalidInputSize - The plaintext is longer than 2^36 - 32 bytes.
+
+        if plaintext.len() as u64 > MAX_PLAINTEXT_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let pre_counter_block: [u8; 16] = pre_counter_block(&self.hash_subkey, iv)?;
+
+        let mut output: Vec<u8> = plaintext.to_vec();
+        self.gctr(&pre_counter_block, 0, &mut output);
+        let tag: [u8; 16] = self.tag(&pre_counter_block, associated_data, &output);
+        output.extend_from_slice(&tag[..self.tag_length]);
+
+        Ok(output)
+    }
+
+    pub fn open(&self, iv: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Verifies the tag and decrypts the ciphertext.
+        //! The plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `iv` - The initialization vector (nonce) used for sealing.
+        //! * `associated_data` - The associated data used for sealing.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty.
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag or too long.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if sealed.len() < self.tag_length || (sealed.len() - self.tag_length) as u64 > MAX_PLAINTEXT_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let pre_counter_block: [u8; 16] = pre_counter_block(&self.hash_subkey, iv)?;
+
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - self.tag_length);
+        let expected_tag: [u8; 16] = self.tag(&pre_counter_block, associated_data, ciphertext);
+        if !constant_time_eq(&expected_tag[..self.tag_length], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        let mut output: Vec<u8> = ciphertext.to_vec();
+        self.gctr(&pre_counter_block, 0, &mut output);
+
+        Ok(output)
+    }
+
+    pub fn seal_parallel(&self, parallel: &Parallel, iv: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, splitting it into chunks which are encrypted and hashed in parallel.
+        //! The hashes of the chunks are combined into the hash of the whole ciphertext, so the output is the same as the output of `seal`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `iv` - The initialization vector (nonce) of any non-zero length. 12 bytes is recommended.
+        //! * `associated_data` - The data which is authenticated, but not encrypted. It is hashed on the calling thread.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext followed by the tag, or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty.
+        //! * ModeError::InvalidInputSize - The plaintext is longer than 2^36 - 32 bytes.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        if plaintext.len() as u64 > MAX_PLAINTEXT_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let pre_counter_block: [u8; 16] = pre_counter_block(&self.hash_subkey, iv)?;
+
+        let mut output: Vec<u8> = self.gctr_parallel(parallel, &pre_counter_block, plaintext)?;
+        let tag: [u8; 16] = self.tag_parallel(parallel, &pre_counter_block, associated_data, &output)?;
+        output.extend_from_slice(&tag[..self.tag_length]);
+
+        Ok(output)
+    }
+
+    pub fn open_parallel(&self, parallel: &Parallel, iv: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Verifies the tag and decrypts the ciphertext, splitting it into chunks which are hashed and decrypted in parallel.
+        //! The output is the same as the output of `open`, and the plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct.
+        //! * `iv` - The initialization vector (nonce) used for sealing.
+        //! * `associated_data` - The associated data used for sealing. It is hashed on the calling thread.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty.
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag or too long.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        if sealed.len() < self.tag_length || (sealed.len() - self.tag_length) as u64 > MAX_PLAINTEXT_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let pre_counter_block: [u8; 16] = pre_counter_block(&self.hash_subkey, iv)?;
+
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - self.tag_length);
+        let expected_tag: [u8; 16] = self.tag_parallel(parallel, &pre_counter_block, associated_data, ciphertext)?;
+        if !constant_time_eq(&expected_tag[..self.tag_length], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        self.gctr_parallel(parallel, &pre_counter_block, ciphertext)
+    }
+}
+
+/// Functions used in the GCM algorithm.
+impl GCM {
+    pub(super) fn check_tag_length(tag_length: usize) -> Result<(), ModeError> {
+        //! Checks that the tag length is allowed by SP 800-38D.
+
+        match tag_length {
+            4 | 8 | 12..=16 => Ok(()),
+            _ => Err(ModeError::InvalidTagLength),
+        }
+    }
+
+    fn gctr(&self, pre_counter_block: &[u8; 16], block_offset: u32, buffer: &mut [u8]) {
+        //! Encrypts or decrypts the buffer in counter mode, starting with the counter block `block_offset` blocks after the one after J0.
+        //! The rightmost 32 bits of the counter block are incremented modulo 2^32.
+
+        let mut counter_block: [u8; 16] = *pre_counter_block;
+        let counter: u32 = u32::from_be_bytes(counter_block[12..].try_into().unwrap()).wrapping_add(block_offset);
+        counter_block[12..].copy_from_slice(&counter.to_be_bytes());
+        for chunk in buffer.chunks_mut(16) {
+            inc32(&mut counter_block);
+            let keystream_block: [u8; 16] = self.aes_core.encrypt(&counter_block);
+            for (byte, keystream_byte) in chunk.iter_mut().zip(keystream_block) {
+                *byte ^= keystream_byte;
+            }
+        }
+    }
+
+    fn tag(&self, pre_counter_block: &[u8; 16], associated_data: &[u8], ciphertext: &[u8]) -> [u8; 16] {
+        //! Computes the full 16-byte authentication tag.
+
+        let mut ghash: GHash = GHash::new(&self.hash_subkey);
+        ghash.update_padded(associated_data);
+        ghash.update_padded(ciphertext);
+
+        self.finish_tag(pre_counter_block, ghash, associated_data.len(), ciphertext.len())
+    }
+
+    fn gctr_parallel(&self, parallel: &Parallel, pre_counter_block: &[u8; 16], input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts or decrypts the input in counter mode on the thread pool, every chunk starting with its own counter block.
+
+        let gcm: GCM = *self;
+        let pre_counter_block: [u8; 16] = *pre_counter_block;
+        let blocks_per_chunk: usize = parallel.chunk_size() / 16;
+        parallel.map_buffer(input, parallel.chunk_size(), move |index, chunk| {
+            gcm.gctr(&pre_counter_block, (index * blocks_per_chunk) as u32, chunk);
+            Ok(())
+        })
+    }
+
+    fn tag_parallel(&self, parallel: &Parallel, pre_counter_block: &[u8; 16], associated_data: &[u8], ciphertext: &[u8]) -> Result<[u8; 16], ModeError> {
+        //! Computes the full 16-byte authentication tag, hashing the chunks of the ciphertext on the thread pool.
+
+        let mut ghash: GHash = GHash::new(&self.hash_subkey);
+        ghash.update_padded(associated_data);
+
+        let hash_subkey: [u8; 16] = self.hash_subkey;
+        let chunk_hashes: Vec<(GHash, u64)> = parallel.map_chunks(ciphertext, parallel.chunk_size(), move |_, chunk| {
+            let mut chunk_hash: GHash = GHash::new(&hash_subkey);
+            chunk_hash.update_padded(chunk);
+            Ok((chunk_hash, chunk.len().div_ceil(16) as u64))
+        })?;
+        for (chunk_hash, block_count) in chunk_hashes {
+            ghash.append(&chunk_hash, block_count);
+        }
+
+        Ok(self.finish_tag(pre_counter_block, ghash, associated_data.len(), ciphertext.len()))
+    }
+
+    fn finish_tag(&self, pre_counter_block: &[u8; 16], mut ghash: GHash, associated_data_length: usize, ciphertext_length: usize) -> [u8; 16] {
+        //! Absorbs the lengths into the hash of the associated data and the ciphertext, and encrypts the hash into the tag.
+
+        ghash.update_lengths(associated_data_length as u64, ciphertext_length as u64);
+
+        let mut tag: [u8; 16] = ghash.finalize();
+        for (byte, mask_byte) in tag.iter_mut().zip(self.aes_core.encrypt(pre_counter_block)) {
+            *byte ^= mask_byte;
+        }
+
+        tag
+    }
+}
+
+/// The GHASH function, a universal hash over GF(2^128) keyed with the hash subkey.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct GHash {
+    /// The hash subkey H.
+    hash_subkey: u128,
+    /// The current hash value.
+    state: u128,
+}
+
+impl GHash {
+    pub(crate) fn new(hash_subkey: &[u8; 16]) -> Self {
+        //! Creates a new GHASH instance with the given hash subkey.
+
+        Self {
+            hash_subkey: u128::from_be_bytes(*hash_subkey),
+            state: 0,
+        }
+    }
+
+    pub(crate) fn update_block(&mut self, block: &[u8; 16]) {
+        //! Absorbs a single block.
+
+        self.state = gf_multiply(self.state ^ u128::from_be_bytes(*block), self.hash_subkey);
+    }
+
+    pub(crate) fn update_padded(&mut self, data: &[u8]) {
+        //! Absorbs the data, padding the last block with zeros.
+
+        for chunk in data.chunks(16) {
+            let mut block: [u8; 16] = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    pub(crate) fn update_lengths(&mut self, associated_data_length: u64, data_length: u64) {
+        //! Absorbs the block with the bit lengths of the associated data and of the data.
+
+        let mut block: [u8; 16] = [0; 16];
+        block[..8].copy_from_slice(&(associated_data_length * 8).to_be_bytes());
+        block[8..].copy_from_slice(&(data_length * 8).to_be_bytes());
+        self.update_block(&block);
+    }
+
+    pub(crate) fn append(&mut self, other: &GHash, block_count: u64) {
+        //! Continues the hash with the blocks absorbed by the other instance, which started with no data and the same hash subkey.
+        //! GHASH is linear, so absorbing n more blocks multiplies the current value by H^n before the hash of the blocks is added.
+
+        self.state = gf_multiply(self.state, gf_power(self.hash_subkey, block_count)) ^ other.state;
+    }
+
+    pub(crate) fn finalize(&self) -> [u8; 16] {
+        //! Returns the hash value.
+
+        self.state.to_be_bytes()
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn gf_multiply(x: u128, y: u128) -> u128 {
+    //! Multiplies two elements of GF(2^128) in the bit order used by GCM.
+    //! Masks are used instead of branches, so the running time doesn't depend on the values.
+
+    let mut product: u128 = 0;
+    let mut v: u128 = y;
+    for i in 0..128 {
+        product ^= v & 0u128.wrapping_sub((x >> (127 - i)) & 1);
+        v = (v >> 1) ^ (REDUCTION_POLYNOMIAL & 0u128.wrapping_sub(v & 1));
+    }
+
+    product
+}
+
+fn gf_power(x: u128, exponent: u64) -> u128 {
+    //! Raises an element of GF(2^128) to the given power by repeated squaring, in the bit order used by GCM.
+
+    // the leftmost bit is the constant term, so this is the element 1
+    let mut power: u128 = 1 << 127;
+    let mut square: u128 = x;
+    let mut exponent: u64 = exponent;
+    while exponent != 0 {
+        if exponent & 1 == 1 {
+            power = gf_multiply(power, square);
+        }
+        square = gf_multiply(square, square);
+        exponent >>= 1;
+    }
+
+    power
+}
+
+pub(super) fn pre_counter_block(hash_subkey: &[u8; 16], iv: &[u8]) -> Result<[u8; 16], ModeError> {
+    //! Derives the pre-counter block J0 from the IV.
+    //! # Errors
+    //! * ModeError::InvalidNonceSize - The IV is empty.
+
+    if iv.is_empty() {
+        return Err(ModeError::InvalidNonceSize);
+    }
+
+    if iv.len() == 12 {
+        let mut pre_counter_block: [u8; 16] = [0; 16];
+        pre_counter_block[..12].copy_from_slice(iv);
+        pre_counter_block[15] = 1;
+        return Ok(pre_counter_block);
+    }
+
+    let mut ghash: GHash = GHash::new(hash_subkey);
+    ghash.update_padded(iv);
+    ghash.update_lengths(0, iv.len() as u64);
+    Ok(ghash.finalize())
+}
+
+pub(crate) fn multiply_by_x(block: &[u8; 16]) -> [u8; 16] {
+    //! Multiplies the block by x in GF(2^128), in the bit order used by GCM.
+
+    let value: u128 = u128::from_be_bytes(*block);
+    ((value >> 1) ^ (REDUCTION_POLYNOMIAL & 0u128.wrapping_sub(value & 1))).to_be_bytes()
+}
+
+fn inc32(counter_block: &mut [u8; 16]) {
+    //! Increments the rightmost 32 bits of the counter block modulo 2^32.
+
+    let counter: u32 = u32::from_be_bytes(counter_block[12..].try_into().unwrap()).wrapping_add(1);
+    counter_block[12..].copy_from_slice(&counter.to_be_bytes());
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The reduction polynomial of GF(2^128), x^128 + x^7 + x^2 + x + 1, in the bit order used by GCM.
+const REDUCTION_POLYNOMIAL: u128 = 0xe1 << 120;
+
+/// The maximum length of the plaintext in bytes (2^39 - 256 bits).
+const MAX_PLAINTEXT_LENGTH: u64 = (1 << 36) - 32;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0xfe, 0xff, 0xe9, 0x92, 0x86, 0x65, 0x73, 0x1c, 0x6d, 0x6a, 0x8f, 0x94, 0x67, 0x30, 0x83, 0x08,
+    ];
+
+    const IV: [u8; 12] = [0xca, 0xfe, 0xba, 0xbe, 0xfa, 0xce, 0xdb, 0xad, 0xde, 0xca, 0xf8, 0x88];
+
+    const ASSOCIATED_DATA: [u8; 20] = [
+        0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef, 0xfe, 0xed, 0xfa, 0xce, 0xde, 0xad, 0xbe, 0xef,
+        0xab, 0xad, 0xda, 0xd2,
+    ];
+
+    #[test]
+    fn seal() {
+        //! Test sealing and opening (GCM specification, test case 4)
+
+        let gcm = GCM::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let plaintext: [u8; 60] = [
+            0xd9, 0x31, 0x32, 0x25, 0xf8, 0x84, 0x06, 0xe5, 0xa5, 0x59, 0x09, 0xc5, 0xaf, 0xf5, 0x26, 0x9a,
+            0x86, 0xa7, 0xa9, 0x53, 0x15, 0x34, 0xf7, 0xda, 0x2e, 0x4c, 0x30, 0x3d, 0x8a, 0x31, 0x8a, 0x72,
+            0x1c, 0x3c, 0x0c, 0x95, 0x95, 0x68, 0x09, 0x53, 0x2f, 0xcf, 0x0e, 0x24, 0x49, 0xa6, 0xb5, 0x25,
+            0xb1, 0x6a, 0xed, 0xf5, 0xaa, 0x0d, 0xe6, 0x57, 0xba, 0x63, 0x7b, 0x39,
+        ];
+        let sealed: [u8; 76] = [
+            0x42, 0x83, 0x1e, 0xc2, 0x21, 0x77, 0x74, 0x24, 0x4b, 0x72, 0x21, 0xb7, 0x84, 0xd0, 0xd4, 0x9c,
+            0xe3, 0xaa, 0x21, 0x2f, 0x2c, 0x02, 0xa4, 0xe0, 0x35, 0xc1, 0x7e, 0x23, 0x29, 0xac, 0xa1, 0x2e,
+            0x21, 0xd5, 0x14, 0xb2, 0x54, 0x66, 0x93, 0x1c, 0x7d, 0x8f, 0x6a, 0x5a, 0xac, 0x84, 0xaa, 0x05,
+            0x1b, 0xa3, 0x0b, 0x39, 0x6a, 0x0a, 0xac, 0x97, 0x3d, 0x58, 0xe0, 0x91,
+            0x5b, 0xc9, 0x4f, 0xbc, 0x32, 0x21, 0xa5, 0xdb, 0x94, 0xfa, 0xe9, 0x5a, 0xe7, 0x12, 0x1a, 0x47,
+        ];
+
+        assert_eq!(gcm.seal(&IV, &ASSOCIATED_DATA, &plaintext).unwrap(), sealed);
+        assert_eq!(gcm.open(&IV, &ASSOCIATED_DATA, &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn tag_length() {
+        //! Test that shorter tags are the leftmost bytes of the full tag
+
+        let mut gcm = GCM::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let sealed: Vec<u8> = gcm.seal(&IV, &ASSOCIATED_DATA, b"tag length").unwrap();
+
+        for tag_length in [4, 8, 12, 13, 14, 15] {
+            gcm.set_tag_length(tag_length).unwrap();
+            assert_eq!(gcm.tag_length(), tag_length);
+
+            let truncated: Vec<u8> = gcm.seal(&IV, &ASSOCIATED_DATA, b"tag length").unwrap();
+            assert_eq!(truncated, sealed[..(10 + tag_length)]);
+            assert_eq!(gcm.open(&IV, &ASSOCIATED_DATA, &truncated).unwrap(), b"tag length");
+        }
+
+        for tag_length in [0, 3, 5, 11, 17] {
+            assert_eq!(gcm.set_tag_length(tag_length), Err(ModeError::InvalidTagLength));
+            assert_eq!(GCM::new(AESCore::new(AESKey::AES128(KEY)), tag_length), Err(ModeError::InvalidTagLength));
+        }
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that any modification is detected
+
+        let gcm = GCM::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let sealed: Vec<u8> = gcm.seal(&IV, &ASSOCIATED_DATA, b"authenticated").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x80;
+            assert_eq!(gcm.open(&IV, &ASSOCIATED_DATA, &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(gcm.open(&IV, &ASSOCIATED_DATA[1..], &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(gcm.open(&IV[1..], &ASSOCIATED_DATA, &sealed), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let gcm = GCM::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        assert_eq!(gcm.seal(&[], &[], &[]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gcm.open(&[], &[], &[0; 16]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gcm.open(&IV, &[], &[0; 15]), Err(ModeError::InvalidInputSize));
+    }
+
+    #[test]
+    fn inc32() {
+        //! Test that only the rightmost 32 bits are incremented
+
+        let mut counter_block: [u8; 16] = [0xff; 16];
+        super::inc32(&mut counter_block);
+        assert_eq!(counter_block, [0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,440 @@
+//! A module containing the AES-GCM-SIV nonce misuse-resistant authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::{
+    AESCore,
+    AESKey,
+};
+use super::{
+    ModeError,
+    constant_time_eq,
+    gcm::{
+        GHash,
+        multiply_by_x,
+    },
+};
+
+
+
+
+
+// STRUCTS
+
+/// The AES-GCM-SIV authenticated encryption mode, as defined in RFC 8452.
+/// For every nonce, fresh authentication and encryption keys are derived from the key.
+/// The tag is computed with POLYVAL over the associated data and the plaintext, and is used as the initial counter block.
+///
+/// The sealed output is the ciphertext followed by the 16-byte tag.
+/// Reusing a nonce only reveals whether the same associated data and plaintext were sealed again.
+/// Only 128-bit and 256-bit keys are supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GCMSIV {
+    /// The AES core used to derive the per-nonce keys.
+    aes_core: AESCore,
+}
+
+/// Public functions for sealing and opening data.
+impl GCMSIV {
+    pub fn new(aes_core: AESCore) -> Result<Self, ModeError> {
+        //! Creates a new GCM-SIV instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to derive the per-nonce keys.
+        //! # Returns
+        //! * Result<GCMSIV, ModeError> - The GCM-SIV instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - The AES core uses a 192-bit key.
+
+        Self::check_key(&aes_core)?;
+
+        Ok(Self {
+            aes_core,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this GCM-SIV instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) -> Result<(), ModeError> {
+        //! Changes the AES core used by this GCM-SIV instance.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - The AES core uses a 192-bit key.
+
+        Self::check_key(&aes_core)?;
+        self.aes_core = aes_core;
+
+        Ok(())
+    }
+
+    pub fn seal(&self, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, and authenticates the associated data.
+        //! # Arguments
+        //! * `nonce` - The 12-byte nonce.
+        //! * `associated_data` - The data which is authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext followed by the tag, or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't 12 bytes long.
+        //! * ModeError::InvalidInputSize - The plaintext or the associated data is longer than 2^36 bytes.
+
+        Self::check_input(nonce, associated_data, plaintext.len())?;
+
+        let (authentication_key, encryption_core) = self.derive_keys(nonce);
+        let tag: [u8; 16] = Self::tag(&authentication_key, &encryption_core, nonce, associated_data, plaintext);
+
+        let mut output: Vec<u8> = plaintext.to_vec();
+        Self::ctr(&encryption_core, &tag, &mut output);
+        output.extend_from_slice(&tag);
+
+        Ok(output)
+    }
+
+    pub fn open(&self, nonce: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the ciphertext and verifies the tag.
+        //! The plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `nonce` - The nonce used for sealing.
+        //! * `associated_data` - The associated data used for sealing.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't 12 bytes long.
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag, or the data is too long.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if sealed.len() < 16 {
+            return Err(ModeError::InvalidInputSize);
+        }
+        Self::check_input(nonce, associated_data, sealed.len() - 16)?;
+
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - 16);
+        let tag: [u8; 16] = tag.try_into().unwrap();
+
+        let (authentication_key, encryption_core) = self.derive_keys(nonce);
+        let mut output: Vec<u8> = ciphertext.to_vec();
+        Self::ctr(&encryption_core, &tag, &mut output);
+
+        let expected_tag: [u8; 16] = Self::tag(&authentication_key, &encryption_core, nonce, associated_data, &output);
+        if !constant_time_eq(&expected_tag, &tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(output)
+    }
+}
+
+/// Functions used in the GCM-SIV algorithm.
+impl GCMSIV {
+    fn check_key(aes_core: &AESCore) -> Result<(), ModeError> {
+        //! Checks that the AES core uses a 128-bit or a 256-bit key.
+
+        match aes_core.key() {
+            AESKey::AES192(_) => Err(ModeError::InvalidKeySize),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_input(nonce: &[u8], associated_data: &[u8], data_length: usize) -> Result<(), ModeError> {
+        //! Checks the nonce size and the lengths of the associated data and of the data.
+
+        if nonce.len() != 12 {
+            return Err(ModeError::InvalidNonceSize);
+        }
+        if associated_data.len() as u64 > MAX_INPUT_LENGTH || data_length as u64 > MAX_INPUT_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        Ok(())
+    }
+
+    fn derive_keys(&self, nonce: &[u8]) -> ([u8; 16], AESCore) {
+        //! Derives the message authentication key and the message encryption core for the nonce.
+        //! Each key is made of the first halves of encryptions of a little-endian counter followed by the nonce.
+
+        let derive_half = |index: u32| -> [u8; 8] {
+            let mut block: [u8; 16] = [0; 16];
+            block[..4].copy_from_slice(&index.to_le_bytes());
+            block[4..].copy_from_slice(nonce);
+            self.aes_core.encrypt(&block)[..8].try_into().unwrap()
+        };
+
+        let mut authentication_key: [u8; 16] = [0; 16];
+        authentication_key[..8].copy_from_slice(&derive_half(0));
+        authentication_key[8..].copy_from_slice(&derive_half(1));
+
+        let mut encryption_core: AESCore = match self.aes_core.key() {
+            AESKey::AES256(_) => {
+                let mut key: [u8; 32] = [0; 32];
+                for (i, chunk) in key.chunks_mut(8).enumerate() {
+                    chunk.copy_from_slice(&derive_half(2 + i as u32));
+                }
+                AESCore::new(AESKey::AES256(key))
+            }
+            _ => {
+                let mut key: [u8; 16] = [0; 16];
+                key[..8].copy_from_slice(&derive_half(2));
+                key[8..].copy_from_slice(&derive_half(3));
+                AESCore::new(AESKey::AES128(key))
+            }
+        };
+        encryption_core.set_implementation(self.aes_core.implementation());
+
+        (authentication_key, encryption_core)
+    }
+
+    fn tag(authentication_key: &[u8; 16], encryption_core: &AESCore, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> [u8; 16] {
+        //! Computes the tag, the encryption of the POLYVAL hash XORed with the nonce.
+
+        let mut polyval: Polyval = Polyval::new(authentication_key);
+        polyval.update_padded(associated_data);
+        polyval.update_padded(plaintext);
+
+        let mut length_block: [u8; 16] = [0; 16];
+        length_block[..8].copy_from_slice(&(associated_data.len() as u64 * 8).to_le_bytes());
+        length_block[8..].copy_from_slice(&(plaintext.len() as u64 * 8).to_le_bytes());
+        polyval.update_block(&length_block);
+
+        let mut hash: [u8; 16] = polyval.finalize();
+        for (byte, nonce_byte) in hash.iter_mut().zip(nonce) {
+            *byte ^= nonce_byte;
+        }
+        hash[15] &= 0x7f;
+
+        encryption_core.encrypt(&hash)
+    }
+
+    fn ctr(encryption_core: &AESCore, tag: &[u8; 16], buffer: &mut [u8]) {
+        //! Encrypts or decrypts the buffer in counter mode, starting with the tag with its most significant bit set.
+        //! The first 32 bits of the counter block are a little-endian counter incremented modulo 2^32.
+
+        let mut counter_block: [u8; 16] = *tag;
+        counter_block[15] |= 0x80;
+
+        for chunk in buffer.chunks_mut(16) {
+            let keystream_block: [u8; 16] = encryption_core.encrypt(&counter_block);
+            for (byte, keystream_byte) in chunk.iter_mut().zip(keystream_block) {
+                *byte ^= keystream_byte;
+            }
+
+            let counter: u32 = u32::from_le_bytes(counter_block[..4].try_into().unwrap()).wrapping_add(1);
+            counter_block[..4].copy_from_slice(&counter.to_le_bytes());
+        }
+    }
+}
+
+/// The POLYVAL function, computed with GHASH on byte-reversed blocks (RFC 8452, appendix A).
+struct Polyval {
+    /// The GHASH instance keyed with mulX_GHASH(ByteReverse(H)).
+    ghash: GHash,
+}
+
+impl Polyval {
+    fn new(key: &[u8; 16]) -> Self {
+        //! Creates a new POLYVAL instance with the given key.
+
+        let mut reversed_key: [u8; 16] = *key;
+        reversed_key.reverse();
+
+        Self {
+            ghash: GHash::new(&multiply_by_x(&reversed_key)),
+        }
+    }
+
+    fn update_block(&mut self, block: &[u8; 16]) {
+        //! Absorbs a single block.
+
+        let mut reversed_block: [u8; 16] = *block;
+        reversed_block.reverse();
+        self.ghash.update_block(&reversed_block);
+    }
+
+    fn update_padded(&mut self, data: &[u8]) {
+        //! Absorbs the data, padding the last block with zeros.
+
+        for chunk in data.chunks(16) {
+            let mut block: [u8; 16] = [0; 16];
+            block[..chunk.len()].copy_from_slice(chunk);
+            self.update_block(&block);
+        }
+    }
+
+    fn finalize(&self) -> [u8; 16] {
+        //! Returns the hash value.
+
+        let mut hash: [u8; 16] = self.ghash.finalize();
+        hash.reverse();
+        hash
+    }
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The maximum length of the plaintext and of the associated data in bytes.
+const MAX_INPUT_LENGTH: u64 = 1 << 36;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::Implementation;
+
+    const NONCE: [u8; 12] = [0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+
+    fn key_128() -> AESCore {
+        let mut key: [u8; 16] = [0; 16];
+        key[0] = 0x01;
+        AESCore::new(AESKey::AES128(key))
+    }
+
+    fn key_256() -> AESCore {
+        let mut key: [u8; 32] = [0; 32];
+        key[0] = 0x01;
+        AESCore::new(AESKey::AES256(key))
+    }
+
+    #[test]
+    fn seal_128() {
+        //! Test sealing and opening with a 128-bit key (RFC 8452, appendix C.1)
+
+        let gcm_siv = GCMSIV::new(key_128()).unwrap();
+
+        let sealed: [u8; 16] = [
+            0xdc, 0x20, 0xe2, 0xd8, 0x3f, 0x25, 0x70, 0x5b, 0xb4, 0x9e, 0x43, 0x9e, 0xca, 0x56, 0xde, 0x25,
+        ];
+        assert_eq!(gcm_siv.seal(&NONCE, &[], &[]).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&NONCE, &[], &sealed).unwrap(), []);
+
+        let plaintext: [u8; 12] = [0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let sealed: [u8; 28] = [
+            0x73, 0x23, 0xea, 0x61, 0xd0, 0x59, 0x32, 0x26, 0x00, 0x47, 0xd9, 0x42,
+            0xa4, 0x97, 0x8d, 0xb3, 0x57, 0x39, 0x1a, 0x0b, 0xc4, 0xfd, 0xec, 0x8b, 0x0d, 0x10, 0x66, 0x39,
+        ];
+        assert_eq!(gcm_siv.seal(&NONCE, &[], &plaintext).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&NONCE, &[], &sealed).unwrap(), plaintext);
+
+        let plaintext: [u8; 8] = [0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let sealed: [u8; 24] = [
+            0x1e, 0x6d, 0xab, 0xa3, 0x56, 0x69, 0xf4, 0x27,
+            0x3b, 0x0a, 0x1a, 0x25, 0x60, 0x96, 0x9c, 0xdf, 0x79, 0x0d, 0x99, 0x75, 0x9a, 0xbd, 0x15, 0x08,
+        ];
+        assert_eq!(gcm_siv.seal(&NONCE, &[0x01], &plaintext).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&NONCE, &[0x01], &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn seal_256() {
+        //! Test sealing and opening with a 256-bit key (RFC 8452, appendix C.2)
+
+        let gcm_siv = GCMSIV::new(key_256()).unwrap();
+
+        let sealed: [u8; 16] = [
+            0x07, 0xf5, 0xf4, 0x16, 0x9b, 0xbf, 0x55, 0xa8, 0x40, 0x0c, 0xd4, 0x7e, 0xa6, 0xfd, 0x40, 0x0f,
+        ];
+        assert_eq!(gcm_siv.seal(&NONCE, &[], &[]).unwrap(), sealed);
+
+        let plaintext: [u8; 8] = [0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let sealed: [u8; 24] = [
+            0xc2, 0xef, 0x32, 0x8e, 0x5c, 0x71, 0xc8, 0x3b,
+            0x84, 0x31, 0x22, 0x13, 0x0f, 0x73, 0x64, 0xb7, 0x61, 0xe0, 0xb9, 0x74, 0x27, 0xe3, 0xdf, 0x28,
+        ];
+        assert_eq!(gcm_siv.seal(&NONCE, &[], &plaintext).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&NONCE, &[], &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn counter_wrap() {
+        //! Test that the 32-bit counter wraps around (RFC 8452, appendix C.3)
+
+        let gcm_siv = GCMSIV::new(AESCore::new(AESKey::AES256([0; 32]))).unwrap();
+
+        let plaintext: [u8; 32] = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            0x4d, 0xb9, 0x23, 0xdc, 0x79, 0x3e, 0xe6, 0x49, 0x7c, 0x76, 0xdc, 0xc0, 0x3a, 0x98, 0xe1, 0x08,
+        ];
+        let sealed: [u8; 48] = [
+            0xf3, 0xf8, 0x0f, 0x2c, 0xf0, 0xcb, 0x2d, 0xd9, 0xc5, 0x98, 0x4f, 0xcd, 0xa9, 0x08, 0x45, 0x6c,
+            0xc5, 0x37, 0x70, 0x3b, 0x5b, 0xa7, 0x03, 0x24, 0xa6, 0x79, 0x3a, 0x7b, 0xf2, 0x18, 0xd3, 0xea,
+            0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(gcm_siv.seal(&[0; 12], &[], &plaintext).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&[0; 12], &[], &sealed).unwrap(), plaintext);
+
+        let plaintext: [u8; 24] = [
+            0xeb, 0x36, 0x40, 0x27, 0x7c, 0x7f, 0xfd, 0x13, 0x03, 0xc7, 0xa5, 0x42, 0xd0, 0x2d, 0x3e, 0x4c,
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let sealed: [u8; 40] = [
+            0x18, 0xce, 0x4f, 0x0b, 0x8c, 0xb4, 0xd0, 0xca, 0xc6, 0x5f, 0xea, 0x8f, 0x79, 0x25, 0x7b, 0x20,
+            0x88, 0x8e, 0x53, 0xe7, 0x22, 0x99, 0xe5, 0x6d,
+            0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(gcm_siv.seal(&[0; 12], &[], &plaintext).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&[0; 12], &[], &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn implementation() {
+        //! Test that the derived encryption core keeps the implementation of the AES core
+
+        for aes_core in [key_128(), key_256()] {
+            let expected: Vec<u8> = GCMSIV::new(aes_core).unwrap().seal(&NONCE, b"header", b"secret message").unwrap();
+
+            let mut aes_core: AESCore = aes_core;
+            aes_core.set_implementation(Implementation::TTable);
+            let gcm_siv = GCMSIV::new(aes_core).unwrap();
+            assert_eq!(gcm_siv.derive_keys(&NONCE).1.implementation(), Implementation::TTable);
+            assert_eq!(gcm_siv.seal(&NONCE, b"header", b"secret message").unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that modified data, associated data or nonce is rejected
+
+        let gcm_siv = GCMSIV::new(key_128()).unwrap();
+        let sealed: Vec<u8> = gcm_siv.seal(&NONCE, b"header", b"secret message").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(gcm_siv.open(&NONCE, b"header", &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(gcm_siv.open(&NONCE, b"Header", &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(gcm_siv.open(&[0; 12], b"header", &sealed), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid keys, nonces and inputs
+
+        assert_eq!(GCMSIV::new(AESCore::new(AESKey::AES192([0; 24]))), Err(ModeError::InvalidKeySize));
+
+        let mut gcm_siv = GCMSIV::new(key_128()).unwrap();
+        assert_eq!(gcm_siv.set_aes_core(AESCore::new(AESKey::AES192([0; 24]))), Err(ModeError::InvalidKeySize));
+        assert_eq!(gcm_siv.aes_core(), key_128());
+        assert!(gcm_siv.set_aes_core(key_256()).is_ok());
+
+        assert_eq!(gcm_siv.seal(&[0; 11], &[], &[]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gcm_siv.seal(&[0; 16], &[], &[]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gcm_siv.open(&[0; 13], &[], &[0; 16]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gcm_siv.open(&NONCE, &[], &[0; 15]), Err(ModeError::InvalidInputSize));
+    }
+}
@@ -0,0 +1,237 @@
+//! A module containing the GMAC message authentication code.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    GCM,
+    ModeError,
+    constant_time_eq,
+    gcm::{
+        GHash,
+        pre_counter_block,
+    },
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The GMAC message authentication code, GCM with data which is only authenticated (NIST SP 800-38D).
+/// The data is hashed with GHASH, and the hash is encrypted with the pre-counter block derived from the IV.
+///
+/// The data can be given in several parts with `update`, and the tag is returned by `finalize`.
+/// Every message must use a new IV, which is set with `set_iv`. The IV must never be reused with the same key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GMAC {
+    /// The AES core used for deriving the hash subkey and for encrypting the hash.
+    aes_core: AESCore,
+    /// The hash subkey H, the encryption of the zero block.
+    hash_subkey: [u8; 16],
+    /// The encryption of the pre-counter block, XORed with the hash.
+    tag_mask: [u8; 16],
+    /// The GHASH of the complete blocks given so far.
+    ghash: GHash,
+    /// The data which doesn't fill a block yet.
+    buffer: [u8; 16],
+    /// The number of bytes in the buffer.
+    buffer_length: usize,
+    /// The number of bytes of data given so far.
+    data_length: u64,
+}
+
+/// Public functions for computing and verifying tags.
+impl GMAC {
+    pub fn new(aes_core: AESCore, iv: &[u8]) -> Result<Self, ModeError> {
+        //! Creates a new GMAC instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for deriving the hash subkey and for encrypting the hash.
+        //! * `iv` - The initialization vector (nonce) of the first message, of any non-zero length. 12 bytes is recommended.
+        //! # Returns
+        //! * Result<GMAC, ModeError> - The GMAC instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty.
+
+        let hash_subkey: [u8; 16] = aes_core.encrypt(&[0; 16]);
+        let tag_mask: [u8; 16] = aes_core.encrypt(&pre_counter_block(&hash_subkey, iv)?);
+
+        Ok(Self {
+            aes_core,
+            hash_subkey,
+            tag_mask,
+            ghash: GHash::new(&hash_subkey),
+            buffer: [0; 16],
+            buffer_length: 0,
+            data_length: 0,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this GMAC instance.
+
+        self.aes_core
+    }
+
+    pub fn set_iv(&mut self, iv: &[u8]) -> Result<(), ModeError> {
+        //! Starts a new message with the given IV.
+        //! The data given so far is discarded.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The IV is empty. The instance isn't changed.
+
+        self.tag_mask = self.aes_core.encrypt(&pre_counter_block(&self.hash_subkey, iv)?);
+        self.ghash = GHash::new(&self.hash_subkey);
+        self.buffer_length = 0;
+        self.data_length = 0;
+
+        Ok(())
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        //! Adds the next part of the data.
+        //! # Arguments
+        //! * `data` - The next part of the data, of any length.
+
+        self.data_length += data.len() as u64;
+
+        let mut data: &[u8] = data;
+        while !data.is_empty() {
+            let length: usize = data.len().min(16 - self.buffer_length);
+            self.buffer[self.buffer_length..(self.buffer_length + length)].copy_from_slice(&data[..length]);
+            self.buffer_length += length;
+            data = &data[length..];
+
+            if self.buffer_length == 16 {
+                self.ghash.update_block(&self.buffer);
+                self.buffer_length = 0;
+            }
+        }
+    }
+
+    pub fn finalize(&self) -> [u8; 16] {
+        //! Returns the full 16-byte tag of the data given so far.
+
+        let mut ghash: GHash = self.ghash;
+        ghash.update_padded(&self.buffer[..self.buffer_length]);
+        ghash.update_lengths(self.data_length, 0);
+
+        let mut tag: [u8; 16] = ghash.finalize();
+        xor_blocks(&mut tag, &self.tag_mask);
+
+        tag
+    }
+
+    pub fn finalize_truncated(&self, tag_length: usize) -> Result<Vec<u8>, ModeError> {
+        //! Returns the tag of the data given so far, truncated to the given length.
+        //! # Arguments
+        //! * `tag_length` - The length of the tag in bytes. Must be 16, 15, 14, 13, 12, 8 or 4.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The leftmost bytes of the tag or an error.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't allowed by SP 800-38D.
+
+        GCM::check_tag_length(tag_length)?;
+
+        Ok(self.finalize()[..tag_length].to_vec())
+    }
+
+    pub fn verify(&self, tag: &[u8]) -> Result<(), ModeError> {
+        //! Verifies the given (possibly truncated) tag against the data given so far, in constant time.
+        //! # Arguments
+        //! * `tag` - The tag, 16, 15, 14, 13, 12, 8 or 4 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't allowed by SP 800-38D.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        GCM::check_tag_length(tag.len())?;
+
+        if !constant_time_eq(&self.finalize()[..tag.len()], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0x77, 0xbe, 0x63, 0x70, 0x89, 0x71, 0xc4, 0xe2, 0x40, 0xd1, 0xcb, 0x79, 0xe8, 0xd7, 0x7f, 0xeb,
+    ];
+
+    const IV: [u8; 12] = [0xe0, 0xe0, 0x0f, 0x19, 0xfe, 0xd7, 0xba, 0x01, 0x36, 0xa7, 0x97, 0xf3];
+
+    const DATA: [u8; 16] = [
+        0x7a, 0x43, 0xec, 0x1d, 0x9c, 0x0a, 0x5a, 0x78, 0xa0, 0xb1, 0x65, 0x33, 0xa6, 0x21, 0x3c, 0xab,
+    ];
+
+    const TAG: [u8; 16] = [
+        0x20, 0x9f, 0xcc, 0x8d, 0x36, 0x75, 0xed, 0x93, 0x8e, 0x9c, 0x71, 0x66, 0x70, 0x9d, 0xd9, 0x46,
+    ];
+
+    #[test]
+    fn finalize() {
+        //! Test the tag of a single block (NIST CAVP gcmEncryptExtIV128, PTlen = 0, AADlen = 128)
+
+        let mut gmac = GMAC::new(AESCore::new(AESKey::AES128(KEY)), &IV).unwrap();
+        gmac.update(&DATA);
+
+        assert_eq!(gmac.finalize(), TAG);
+        assert!(gmac.verify(&TAG).is_ok());
+        assert!(gmac.verify(&TAG[..8]).is_ok());
+        assert_eq!(gmac.finalize_truncated(12).unwrap(), TAG[..12]);
+    }
+
+    #[test]
+    fn update() {
+        //! Test that the tag matches sealing an empty plaintext with GCM, however the data is split
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        let gcm = GCM::new(aes_core, 16).unwrap();
+        let data: Vec<u8> = (0..50).collect();
+
+        let mut gmac = GMAC::new(aes_core, &IV).unwrap();
+        for part in [&data[..3], &data[3..3], &data[3..16], &data[16..37], &data[37..]] {
+            gmac.update(part);
+        }
+        assert_eq!(gmac.finalize().to_vec(), gcm.seal(&IV, &data, &[]).unwrap());
+
+        // a new IV starts a new message
+        gmac.set_iv(&[0x5a; 20]).unwrap();
+        gmac.update(&data[..7]);
+        assert_eq!(gmac.finalize().to_vec(), gcm.seal(&[0x5a; 20], &data[..7], &[]).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid IVs and tags
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        assert_eq!(GMAC::new(aes_core, &[]), Err(ModeError::InvalidNonceSize));
+
+        let mut gmac = GMAC::new(aes_core, &IV).unwrap();
+        gmac.update(&DATA);
+        assert_eq!(gmac.set_iv(&[]), Err(ModeError::InvalidNonceSize));
+        assert_eq!(gmac.finalize(), TAG);
+
+        assert_eq!(gmac.finalize_truncated(10), Err(ModeError::InvalidTagLength));
+        assert_eq!(gmac.verify(&TAG[..10]), Err(ModeError::InvalidTagLength));
+        gmac.update(&[0]);
+        assert_eq!(gmac.verify(&TAG), Err(ModeError::AuthenticationFailed));
+    }
+}
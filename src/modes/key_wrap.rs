@@ -0,0 +1,368 @@
+//! A module containing the AES key wrap modes KW and KWP.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    ModeError,
+    constant_time_eq,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The AES key wrap mode KW, as defined in RFC 3394 and NIST SP 800-38F.
+/// It protects the confidentiality and integrity of keys with a key-encryption key.
+/// The key data must be a multiple of 8 bytes long and at least 16 bytes long, and the wrapped key is 8 bytes longer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KW {
+    /// The AES core made from the key-encryption key.
+    aes_core: AESCore,
+    /// The initial value, checked when unwrapping.
+    iv: [u8; 8],
+}
+
+/// The AES key wrap with padding mode KWP, as defined in RFC 5649 and NIST SP 800-38F.
+/// Key data of any length from 1 byte is padded with zeros to a multiple of 8 bytes, and its length is stored in the initial value.
+/// The wrapped key is between 8 and 15 bytes longer than the key data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KWP {
+    /// The AES core made from the key-encryption key.
+    aes_core: AESCore,
+}
+
+/// Public functions for wrapping and unwrapping keys.
+impl KW {
+    pub fn new(aes_core: AESCore) -> Self {
+        //! Creates a new KW instance with the default initial value `A6A6A6A6A6A6A6A6`.
+        //! # Arguments
+        //! * `aes_core` - The AES core made from the key-encryption key.
+
+        Self {
+            aes_core,
+            iv: DEFAULT_IV,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this KW instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this KW instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn iv(&self) -> [u8; 8] {
+        //! Returns the initial value used by this KW instance.
+
+        self.iv
+    }
+
+    pub fn set_iv(&mut self, iv: [u8; 8]) {
+        //! Changes the initial value used by this KW instance.
+        //! An alternative initial value can be used to bind extra information to the wrapped key (RFC 3394, section 2.2.3.2).
+
+        self.iv = iv;
+    }
+
+    pub fn wrap(&self, key_data: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Wraps the given key data.
+        //! # Arguments
+        //! * `key_data` - The key data, a multiple of 8 bytes long and at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The wrapped key, 8 bytes longer than the key data, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The key data isn't a multiple of 8 bytes long or is shorter than 16 bytes.
+
+        if key_data.len() < 16 || !key_data.len().is_multiple_of(8) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        Ok(wrap(&self.aes_core, &self.iv, key_data))
+    }
+
+    pub fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Unwraps the given wrapped key and checks its integrity.
+        //! # Arguments
+        //! * `wrapped_key` - The wrapped key, a multiple of 8 bytes long and at least 24 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The key data or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The wrapped key isn't a multiple of 8 bytes long or is shorter than 24 bytes.
+        //! * ModeError::IntegrityCheckFailed - The initial value doesn't match, so the wrapped key was modified or the wrong key-encryption key was used.
+
+        if wrapped_key.len() < 24 || !wrapped_key.len().is_multiple_of(8) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let (iv, key_data) = unwrap(&self.aes_core, wrapped_key);
+        if !constant_time_eq(&iv, &self.iv) {
+            return Err(ModeError::IntegrityCheckFailed);
+        }
+
+        Ok(key_data)
+    }
+}
+
+/// Public functions for wrapping and unwrapping keys.
+impl KWP {
+    pub fn new(aes_core: AESCore) -> Self {
+        //! Creates a new KWP instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core made from the key-encryption key.
+
+        Self {
+            aes_core,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this KWP instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this KWP instance.
+
+        self.aes_core = aes_core;
+    }
+
+    pub fn wrap(&self, key_data: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Wraps the given key data.
+        //! # Arguments
+        //! * `key_data` - The key data, between 1 byte and 2^32 - 1 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The wrapped key or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The key data is empty or too long.
+
+        if key_data.is_empty() || key_data.len() as u64 > u32::MAX as u64 {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        // the alternative initial value is the constant followed by the 32-bit length of the key data
+        let mut iv: [u8; 8] = [0; 8];
+        iv[..4].copy_from_slice(&ALTERNATIVE_IV_PREFIX);
+        iv[4..].copy_from_slice(&(key_data.len() as u32).to_be_bytes());
+
+        let mut padded_key_data: Vec<u8> = key_data.to_vec();
+        padded_key_data.resize(key_data.len().div_ceil(8) * 8, 0);
+
+        // a single semiblock is encrypted together with the initial value as one block
+        if padded_key_data.len() == 8 {
+            let mut block: [u8; 16] = [0; 16];
+            block[..8].copy_from_slice(&iv);
+            block[8..].copy_from_slice(&padded_key_data);
+            return Ok(self.aes_core.encrypt(&block).to_vec());
+        }
+
+        Ok(wrap(&self.aes_core, &iv, &padded_key_data))
+    }
+
+    pub fn unwrap(&self, wrapped_key: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Unwraps the given wrapped key and checks its integrity.
+        //! # Arguments
+        //! * `wrapped_key` - The wrapped key, a multiple of 8 bytes long and at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The key data or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The wrapped key isn't a multiple of 8 bytes long or is shorter than 16 bytes.
+        //! * ModeError::IntegrityCheckFailed - The initial value, the length or the padding doesn't match, so the wrapped key was modified or the wrong key-encryption key was used.
+
+        if wrapped_key.len() < 16 || !wrapped_key.len().is_multiple_of(8) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let (iv, mut padded_key_data) = if wrapped_key.len() == 16 {
+            let block: [u8; 16] = self.aes_core.decrypt(wrapped_key.try_into().unwrap());
+            (block[..8].try_into().unwrap(), block[8..].to_vec())
+        } else {
+            unwrap(&self.aes_core, wrapped_key)
+        };
+
+        let length: usize = u32::from_be_bytes(iv[4..].try_into().unwrap()) as usize;
+        let padding_length: usize = padded_key_data.len().wrapping_sub(length);
+        if !constant_time_eq(&iv[..4], &ALTERNATIVE_IV_PREFIX) || padding_length >= 8 {
+            return Err(ModeError::IntegrityCheckFailed);
+        }
+        if !constant_time_eq(&padded_key_data[length..], &[0; 7][..padding_length]) {
+            return Err(ModeError::IntegrityCheckFailed);
+        }
+
+        padded_key_data.truncate(length);
+
+        Ok(padded_key_data)
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn wrap(aes_core: &AESCore, iv: &[u8; 8], key_data: &[u8]) -> Vec<u8> {
+    //! The wrapping process W, which wraps at least two semiblocks (RFC 3394, section 2.2.1).
+
+    let semiblock_count: usize = key_data.len() / 8;
+    let mut output: Vec<u8> = Vec::with_capacity(key_data.len() + 8);
+    output.extend_from_slice(iv);
+    output.extend_from_slice(key_data);
+
+    let mut block: [u8; 16] = [0; 16];
+    block[..8].copy_from_slice(iv);
+    for j in 0..6 {
+        for i in 1..=semiblock_count {
+            block[8..].copy_from_slice(&output[(i * 8)..(i * 8 + 8)]);
+            block = aes_core.encrypt(&block);
+
+            let step: u64 = (semiblock_count * j + i) as u64;
+            for (byte, step_byte) in block[..8].iter_mut().zip(step.to_be_bytes()) {
+                *byte ^= step_byte;
+            }
+            output[(i * 8)..(i * 8 + 8)].copy_from_slice(&block[8..]);
+        }
+    }
+    output[..8].copy_from_slice(&block[..8]);
+
+    output
+}
+
+fn unwrap(aes_core: &AESCore, wrapped_key: &[u8]) -> ([u8; 8], Vec<u8>) {
+    //! The unwrapping process W^-1, which returns the recovered initial value and key data (RFC 3394, section 2.2.2).
+
+    let semiblock_count: usize = wrapped_key.len() / 8 - 1;
+    let mut output: Vec<u8> = wrapped_key[8..].to_vec();
+
+    let mut block: [u8; 16] = [0; 16];
+    block[..8].copy_from_slice(&wrapped_key[..8]);
+    for j in (0..6).rev() {
+        for i in (1..=semiblock_count).rev() {
+            let step: u64 = (semiblock_count * j + i) as u64;
+            for (byte, step_byte) in block[..8].iter_mut().zip(step.to_be_bytes()) {
+                *byte ^= step_byte;
+            }
+            block[8..].copy_from_slice(&output[((i - 1) * 8)..(i * 8)]);
+            block = aes_core.decrypt(&block);
+
+            output[((i - 1) * 8)..(i * 8)].copy_from_slice(&block[8..]);
+        }
+    }
+
+    (block[..8].try_into().unwrap(), output)
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The default initial value of KW.
+const DEFAULT_IV: [u8; 8] = [0xa6; 8];
+
+/// The constant first half of the alternative initial value of KWP.
+const ALTERNATIVE_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEK: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    const KEY_DATA: [u8; 16] = [
+        0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+    ];
+
+    const WRAPPED_KEY: [u8; 24] = [
+        0x1f, 0xa6, 0x8b, 0x0a, 0x81, 0x12, 0xb4, 0x47, 0xae, 0xf3, 0x4b, 0xd8, 0xfb, 0x5a, 0x7b, 0x82,
+        0x9d, 0x3e, 0x86, 0x23, 0x71, 0xd2, 0xcf, 0xe5,
+    ];
+
+    #[test]
+    fn kw() {
+        //! Test wrapping and unwrapping with KW (RFC 3394, section 4.1)
+
+        let kw = KW::new(AESCore::new(AESKey::AES128(KEK)));
+
+        assert_eq!(kw.wrap(&KEY_DATA).unwrap(), WRAPPED_KEY);
+        assert_eq!(kw.unwrap(&WRAPPED_KEY).unwrap(), KEY_DATA);
+    }
+
+    #[test]
+    fn kw_integrity() {
+        //! Test that modified wrapped keys and a different initial value fail the integrity check
+
+        let mut kw = KW::new(AESCore::new(AESKey::AES128(KEK)));
+
+        for i in 0..WRAPPED_KEY.len() {
+            let mut modified: [u8; 24] = WRAPPED_KEY;
+            modified[i] ^= 0x01;
+            assert_eq!(kw.unwrap(&modified), Err(ModeError::IntegrityCheckFailed));
+        }
+
+        kw.set_iv([0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08]);
+        assert_eq!(kw.unwrap(&WRAPPED_KEY), Err(ModeError::IntegrityCheckFailed));
+        let wrapped_key: Vec<u8> = kw.wrap(&KEY_DATA).unwrap();
+        assert_ne!(wrapped_key, WRAPPED_KEY);
+        assert_eq!(kw.unwrap(&wrapped_key).unwrap(), KEY_DATA);
+    }
+
+    #[test]
+    fn kwp() {
+        //! Test wrapping and unwrapping with KWP for every length up to three semiblocks
+
+        let kwp = KWP::new(AESCore::new(AESKey::AES128(KEK)));
+
+        for length in 1..=24 {
+            let wrapped_key: Vec<u8> = kwp.wrap(&[0x5a; 24][..length]).unwrap();
+            assert_eq!(wrapped_key.len(), length.div_ceil(8) * 8 + 8);
+            assert_eq!(kwp.unwrap(&wrapped_key).unwrap(), [0x5a; 24][..length]);
+
+            let mut modified: Vec<u8> = wrapped_key.clone();
+            modified[0] ^= 0x80;
+            assert_eq!(kwp.unwrap(&modified), Err(ModeError::IntegrityCheckFailed));
+        }
+
+        // the same key data wrapped with KW fails the check of the alternative initial value
+        let kw = KW::new(AESCore::new(AESKey::AES128(KEK)));
+        assert_eq!(kwp.unwrap(&kw.wrap(&KEY_DATA).unwrap()), Err(ModeError::IntegrityCheckFailed));
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid key data and wrapped key lengths
+
+        let kw = KW::new(AESCore::new(AESKey::AES128(KEK)));
+        assert_eq!(kw.wrap(&KEY_DATA[..8]), Err(ModeError::InvalidInputSize));
+        assert_eq!(kw.wrap(&KEY_DATA[..15]), Err(ModeError::InvalidInputSize));
+        assert_eq!(kw.unwrap(&WRAPPED_KEY[..16]), Err(ModeError::InvalidInputSize));
+        assert_eq!(kw.unwrap(&WRAPPED_KEY[..23]), Err(ModeError::InvalidInputSize));
+
+        let kwp = KWP::new(AESCore::new(AESKey::AES128(KEK)));
+        assert_eq!(kwp.wrap(&[]), Err(ModeError::InvalidInputSize));
+        assert_eq!(kwp.unwrap(&WRAPPED_KEY[..8]), Err(ModeError::InvalidInputSize));
+        assert_eq!(kwp.unwrap(&WRAPPED_KEY[..20]), Err(ModeError::InvalidInputSize));
+    }
+}
@@ -0,0 +1,366 @@
+//! A module containing the block cipher modes of operation.
+
+
+
+
+
+// DISABLED LINTS
+
+#![allow(clippy::needless_range_loop)]  // better readability
+
+
+
+
+
+// IMPORTS
+
+use std::error::Error;
+use std::fmt;
+
+use tinypool::ThreadPoolError;
+
+use crate::aes_core::AESCore;
+use crate::padding::{
+    Padding,
+    PaddingError,
+    PaddingTypes,
+};
+
+
+
+
+
+// MODULES
+
+pub mod cbc;
+pub mod cbc_cs;
+pub mod cbc_mac;
+pub mod ccm;
+pub mod cmac;
+pub mod cfb;
+pub mod ctr;
+pub mod eax;
+pub mod ecb;
+pub mod gcm;
+pub mod gcm_siv;
+pub mod gmac;
+pub mod key_wrap;
+pub mod ocb;
+pub mod ofb;
+pub mod parallel;
+pub mod siv;
+pub mod stream;
+pub mod xcbc_mac;
+pub mod xts;
+
+#[doc(inline)]
+pub use cbc::*;
+
+#[doc(inline)]
+pub use cbc_cs::*;
+
+#[doc(inline)]
+pub use cbc_mac::*;
+
+#[doc(inline)]
+pub use ccm::*;
+
+#[doc(inline)]
+pub use cmac::*;
+
+#[doc(inline)]
+pub use cfb::*;
+
+#[doc(inline)]
+pub use ctr::*;
+
+#[doc(inline)]
+pub use eax::*;
+
+#[doc(inline)]
+pub use ecb::*;
+
+#[doc(inline)]
+pub use gcm::*;
+
+#[doc(inline)]
+pub use gcm_siv::*;
+
+#[doc(inline)]
+pub use gmac::*;
+
+#[doc(inline)]
+pub use key_wrap::*;
+
+#[doc(inline)]
+pub use ocb::*;
+
+#[doc(inline)]
+pub use ofb::*;
+
+#[doc(inline)]
+pub use parallel::*;
+
+#[doc(inline)]
+pub use siv::*;
+
+#[doc(inline)]
+pub use stream::{
+    Decryptor,
+    Encryptor,
+};
+
+#[doc(inline)]
+pub use xcbc_mac::*;
+
+#[doc(inline)]
+pub use xts::*;
+
+
+
+
+
+// ENUMS
+
+/// The enum with errors of the block cipher modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModeError {
+    /// The input length isn't valid for the mode.
+    /// E.g. the ciphertext isn't a multiple of 16 bytes long.
+    InvalidInputSize,
+    /// The padding couldn't be applied or removed, see the `PaddingError` enum.
+    Padding(PaddingError),
+    /// The counter would wrap around, which would reuse keystream blocks.
+    CounterOverflow,
+    /// The nonce (IV) length isn't valid for the mode.
+    InvalidNonceSize,
+    /// The authentication tag length isn't valid for the mode.
+    InvalidTagLength,
+    /// The key size isn't supported by the mode.
+    InvalidKeySize,
+    /// The two keys of the mode are equal, which the mode doesn't allow.
+    IdenticalKeys,
+    /// The authentication tag doesn't match the data, so the data was modified or the wrong key was used.
+    /// No plaintext is released.
+    AuthenticationFailed,
+    /// The integrity check of an unwrapped key failed, so the wrapped key was modified or the wrong key-encryption key was used.
+    /// No key data is released.
+    IntegrityCheckFailed,
+    /// The chunk size for parallel processing isn't a positive multiple of 16 bytes.
+    InvalidChunkSize,
+    /// The thread pool couldn't spawn a thread or has no threads, see the `Parallel` struct.
+    ThreadPool,
+}
+
+impl From<PaddingError> for ModeError {
+    fn from(error: PaddingError) -> Self {
+        ModeError::Padding(error)
+    }
+}
+
+impl From<ThreadPoolError> for ModeError {
+    fn from(_: ThreadPoolError) -> Self {
+        ModeError::ThreadPool
+    }
+}
+
+impl fmt::Display for ModeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModeError::InvalidInputSize => write!(f, "the input length isn't valid for the mode"),
+            ModeError::Padding(error) => write!(f, "{}", error),
+            ModeError::CounterOverflow => write!(f, "the counter would wrap around"),
+            ModeError::InvalidNonceSize => write!(f, "the nonce length isn't valid for the mode"),
+            ModeError::InvalidTagLength => write!(f, "the tag length isn't valid for the mode"),
+            ModeError::InvalidKeySize => write!(f, "the key size isn't supported by the mode"),
+            ModeError::IdenticalKeys => write!(f, "the two keys of the mode are equal"),
+            ModeError::AuthenticationFailed => write!(f, "the authentication tag doesn't match the data"),
+            ModeError::IntegrityCheckFailed => write!(f, "the integrity check of the unwrapped key failed"),
+            ModeError::InvalidChunkSize => write!(f, "the chunk size isn't a positive multiple of 16 bytes"),
+            ModeError::ThreadPool => write!(f, "the thread pool couldn't spawn a thread or has no threads"),
+        }
+    }
+}
+
+impl Error for ModeError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ModeError::Padding(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+
+
+
+
+// STRUCTS
+
+/// The CBC chaining shared by the MACs built on CBC (CMAC, XCBC-MAC and CBC-MAC).
+/// The last block is kept in the buffer until more data follows it, because it is processed differently.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MacChain {
+    /// The chaining value, the encryption of all processed blocks.
+    state: [u8; 16],
+    /// The data which isn't processed yet, because it could be the last block.
+    buffer: [u8; 16],
+    /// The number of bytes in the buffer.
+    buffer_length: usize,
+}
+
+impl MacChain {
+    fn new() -> Self {
+        //! Creates a new chain with no data.
+
+        Self {
+            state: [0; 16],
+            buffer: [0; 16],
+            buffer_length: 0,
+        }
+    }
+
+    fn update(&mut self, aes_core: &AESCore, data: &[u8]) {
+        //! Adds the next part of the data, processing every block except the last one.
+
+        let mut data: &[u8] = data;
+        while !data.is_empty() {
+            if self.buffer_length == 16 {
+                xor_blocks(&mut self.state, &self.buffer);
+                self.state = aes_core.encrypt(&self.state);
+                self.buffer_length = 0;
+            }
+
+            let length: usize = data.len().min(16 - self.buffer_length);
+            self.buffer[self.buffer_length..(self.buffer_length + length)].copy_from_slice(&data[..length]);
+            self.buffer_length += length;
+            data = &data[length..];
+        }
+    }
+
+    fn finalize(&self, aes_core: &AESCore, complete_subkey: &[u8; 16], padded_subkey: &[u8; 16]) -> [u8; 16] {
+        //! Processes the last block and returns the tag.
+        //! A complete last block is XORed with the first subkey, and a partial one is padded with a single one bit and zeros and XORed with the second subkey.
+
+        let mut last_block: [u8; 16] = [0; 16];
+        last_block[..self.buffer_length].copy_from_slice(&self.buffer[..self.buffer_length]);
+        if self.buffer_length == 16 {
+            xor_blocks(&mut last_block, complete_subkey);
+        } else {
+            last_block[self.buffer_length] = 0x80;
+            xor_blocks(&mut last_block, padded_subkey);
+        }
+
+        let mut tag: [u8; 16] = self.state;
+        xor_blocks(&mut tag, &last_block);
+
+        aes_core.encrypt(&tag)
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn check_mac_tag_length(tag_length: usize) -> Result<(), ModeError> {
+    //! Checks that the length of a (possibly truncated) MAC tag is between 4 and 16 bytes.
+
+    match tag_length {
+        4..=16 => Ok(()),
+        _ => Err(ModeError::InvalidTagLength),
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    //! Compares the two slices in time independent of their contents.
+    //! Used for comparing authentication tags.
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |difference, (x, y)| difference | (x ^ y)) == 0
+}
+
+fn double(block: &[u8; 16]) -> [u8; 16] {
+    //! Multiplies the block by x in GF(2^128), with the bit order used by CMAC.
+    //! Masks are used instead of branches, so the running time doesn't depend on the value.
+
+    let value: u128 = u128::from_be_bytes(*block);
+    ((value << 1) ^ (0x87 & 0u128.wrapping_sub(value >> 127))).to_be_bytes()
+}
+
+fn xor_blocks(block: &mut [u8; 16], other: &[u8; 16]) {
+    //! XORs the other block into the block.
+
+    for i in 0..16 {
+        block[i] ^= other[i];
+    }
+}
+
+fn split_for_padding<'a>(padding: &Padding, input: &'a [u8]) -> Result<(&'a [u8], Option<[u8; 16]>), ModeError> {
+    //! Splits the input into full blocks and the padded last block.
+    //! # Arguments
+    //! * `padding` - The padding applied to the last block.
+    //! * `input` - The input to be split.
+    //! # Returns
+    //! * Result<(&[u8], Option<[u8; 16]>), ModeError> - The full blocks and the padded last block (`None` for `PaddingTypes::None`).
+    //! # Errors
+    //! * ModeError::InvalidInputSize - The padding type is `PaddingTypes::None` and the input isn't a multiple of 16 bytes long.
+
+    if padding.padding_type() == PaddingTypes::None {
+        if !input.len().is_multiple_of(16) {
+            return Err(ModeError::InvalidInputSize);
+        }
+        return Ok((input, None));
+    }
+
+    let (full_blocks, remainder) = input.split_at(input.len() - input.len() % 16);
+
+    Ok((full_blocks, Some(padding.pad(remainder)?)))
+}
+
+fn check_padded_size(padding: &Padding, input: &[u8]) -> Result<(), ModeError> {
+    //! Checks that the input can be the output of a padded block mode.
+    //! # Arguments
+    //! * `padding` - The padding applied to the last block.
+    //! * `input` - The input to be checked.
+    //! # Errors
+    //! * ModeError::InvalidInputSize - The input isn't a multiple of 16 bytes long, or is empty while padding is used.
+
+    if !input.len().is_multiple_of(16) || (input.is_empty() && padding.padding_type() != PaddingTypes::None) {
+        return Err(ModeError::InvalidInputSize);
+    }
+
+    Ok(())
+}
+
+fn remove_padding(padding: &Padding, output: &mut Vec<u8>) -> Result<(), ModeError> {
+    //! Removes the padding from the last block of the output.
+    //! # Arguments
+    //! * `padding` - The padding applied to the last block.
+    //! * `output` - The decrypted output, a non-empty multiple of 16 bytes long (unless no padding is used).
+    //! # Errors
+    //! * ModeError::Padding - The padding is invalid and cannot be removed.
+
+    if padding.padding_type() == PaddingTypes::None {
+        return Ok(());
+    }
+
+    let last_block_start = output.len() - 16;
+    let unpadded_length = last_block_start + padding.de_pad(&output[last_block_start..])?.len();
+    output.truncate(unpadded_length);
+
+    Ok(())
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of blocks given to the AES core at once by the modes which encrypt or decrypt the blocks in batches.
+const BATCH_BLOCKS: usize = 8;
@@ -0,0 +1,519 @@
+//! A module containing the OCB3 (Offset Codebook) authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use super::{
+    ModeError,
+    constant_time_eq,
+    double,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The OCB3 (Offset Codebook) authenticated encryption mode, as defined in RFC 7253.
+/// Every block is encrypted once, XORed before and after with an offset derived from the nonce and the block index,
+/// and the tag is the encryption of the checksum of the plaintext, XORed with the hash of the associated data.
+///
+/// The sealed output is the ciphertext followed by the authentication tag.
+/// The nonce must never be reused with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OCB {
+    /// The AES core used for encryption and decryption.
+    aes_core: AESCore,
+    /// The key-dependent offsets, computed once per key.
+    l_table: LTable,
+    /// The length of the authentication tag in bytes.
+    tag_length: usize,
+}
+
+/// Public functions for sealing and opening data.
+impl OCB {
+    pub fn new(aes_core: AESCore, tag_length: usize) -> Result<Self, ModeError> {
+        //! Creates a new OCB instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used for encryption and decryption.
+        //! * `tag_length` - The length of the authentication tag in bytes, 8, 12 or 16.
+        //! # Returns
+        //! * Result<OCB, ModeError> - The OCB instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't 8, 12 or 16.
+
+        Self::check_tag_length(tag_length)?;
+
+        Ok(Self {
+            aes_core,
+            l_table: LTable::new(&aes_core),
+            tag_length,
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this OCB instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this OCB instance.
+
+        self.aes_core = aes_core;
+        self.l_table = LTable::new(&aes_core);
+    }
+
+    pub fn tag_length(&self) -> usize {
+        //! Returns the length of the authentication tag in bytes.
+
+        self.tag_length
+    }
+
+    pub fn set_tag_length(&mut self, tag_length: usize) -> Result<(), ModeError> {
+        //! Changes the length of the authentication tag in bytes.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag length isn't 8, 12 or 16.
+
+        Self::check_tag_length(tag_length)?;
+        self.tag_length = tag_length;
+
+        Ok(())
+    }
+
+    pub fn seal(&self, nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, and authenticates the associated data.
+        //! # Arguments
+        //! * `nonce` - The nonce, from 1 to 15 bytes long. 12 bytes is recommended.
+        //! * `associated_data` - The data which is authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext followed by the tag, or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't from 1 to 15 bytes long.
+
+        let mut offset: [u8; 16] = self.initial_offset(nonce)?;
+        let mut checksum: [u8; 16] = [0; 16];
+
+        let mut output: Vec<u8> = Vec::with_capacity(plaintext.len() + self.tag_length);
+        let mut chunks = plaintext.chunks_exact(16);
+        for (index, chunk) in (&mut chunks).enumerate() {
+            let block: [u8; 16] = chunk.try_into().unwrap();
+            xor_blocks(&mut offset, self.l_table.get(index + 1));
+            xor_blocks(&mut checksum, &block);
+
+            let mut ciphertext_block: [u8; 16] = block;
+            xor_blocks(&mut ciphertext_block, &offset);
+            ciphertext_block = self.aes_core.encrypt(&ciphertext_block);
+            xor_blocks(&mut ciphertext_block, &offset);
+            output.extend_from_slice(&ciphertext_block);
+        }
+
+        let remainder: &[u8] = chunks.remainder();
+        if !remainder.is_empty() {
+            xor_blocks(&mut offset, &self.l_table.l_star);
+            let pad: [u8; 16] = self.aes_core.encrypt(&offset);
+            output.extend(remainder.iter().zip(pad).map(|(byte, pad_byte)| byte ^ pad_byte));
+            xor_blocks(&mut checksum, &pad_partial(remainder));
+        }
+
+        let tag: [u8; 16] = self.tag(&checksum, &offset, associated_data);
+        output.extend_from_slice(&tag[..self.tag_length]);
+
+        Ok(output)
+    }
+
+    pub fn open(&self, nonce: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the ciphertext and verifies the tag.
+        //! The plaintext is only released if the tag is valid.
+        //! # Arguments
+        //! * `nonce` - The nonce used for sealing.
+        //! * `associated_data` - The associated data used for sealing.
+        //! * `sealed` - The ciphertext followed by the tag.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't from 1 to 15 bytes long.
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than the tag.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if sealed.len() < self.tag_length {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let mut offset: [u8; 16] = self.initial_offset(nonce)?;
+        let mut checksum: [u8; 16] = [0; 16];
+
+        let (ciphertext, tag) = sealed.split_at(sealed.len() - self.tag_length);
+        let mut output: Vec<u8> = Vec::with_capacity(ciphertext.len());
+        let mut chunks = ciphertext.chunks_exact(16);
+        for (index, chunk) in (&mut chunks).enumerate() {
+            xor_blocks(&mut offset, self.l_table.get(index + 1));
+
+            let mut plaintext_block: [u8; 16] = chunk.try_into().unwrap();
+            xor_blocks(&mut plaintext_block, &offset);
+            plaintext_block = self.aes_core.decrypt(&plaintext_block);
+            xor_blocks(&mut plaintext_block, &offset);
+
+            xor_blocks(&mut checksum, &plaintext_block);
+            output.extend_from_slice(&plaintext_block);
+        }
+
+        let remainder: &[u8] = chunks.remainder();
+        if !remainder.is_empty() {
+            xor_blocks(&mut offset, &self.l_table.l_star);
+            let pad: [u8; 16] = self.aes_core.encrypt(&offset);
+            let plaintext_remainder: Vec<u8> = remainder.iter().zip(pad).map(|(byte, pad_byte)| byte ^ pad_byte).collect();
+            xor_blocks(&mut checksum, &pad_partial(&plaintext_remainder));
+            output.extend_from_slice(&plaintext_remainder);
+        }
+
+        let expected_tag: [u8; 16] = self.tag(&checksum, &offset, associated_data);
+        if !constant_time_eq(&expected_tag[..self.tag_length], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(output)
+    }
+}
+
+/// Functions used in the OCB algorithm.
+impl OCB {
+    fn check_tag_length(tag_length: usize) -> Result<(), ModeError> {
+        //! Checks that the tag length is 8, 12 or 16 bytes.
+
+        match tag_length {
+            8 | 12 | 16 => Ok(()),
+            _ => Err(ModeError::InvalidTagLength),
+        }
+    }
+
+    fn initial_offset(&self, nonce: &[u8]) -> Result<[u8; 16], ModeError> {
+        //! Derives the initial offset from the nonce and the tag length.
+        //! # Errors
+        //! * ModeError::InvalidNonceSize - The nonce isn't from 1 to 15 bytes long.
+
+        if !(1..=15).contains(&nonce.len()) {
+            return Err(ModeError::InvalidNonceSize);
+        }
+
+        // the formatted nonce is the tag length in bits (mod 128) in 7 bits, zeros, a single one bit and the nonce
+        let mut formatted_nonce: [u8; 16] = [0; 16];
+        formatted_nonce[0] = (((self.tag_length * 8) % 128) << 1) as u8;
+        formatted_nonce[15 - nonce.len()] |= 0x01;
+        formatted_nonce[(16 - nonce.len())..].copy_from_slice(nonce);
+
+        // the last 6 bits select the bit position in the stretched encryption of the rest of the nonce
+        let bottom: u32 = (formatted_nonce[15] & 0x3f) as u32;
+        formatted_nonce[15] &= 0xc0;
+        let top: u128 = u128::from_be_bytes(self.aes_core.encrypt(&formatted_nonce));
+        let stretch: u128 = ((top >> 64) ^ (top >> 56)) << 64;
+
+        let offset: u128 = if bottom == 0 {
+            top
+        } else {
+            (top << bottom) | (stretch >> (128 - bottom))
+        };
+
+        Ok(offset.to_be_bytes())
+    }
+
+    fn hash(&self, associated_data: &[u8]) -> [u8; 16] {
+        //! Computes the hash of the associated data, the sum of the encryptions of its blocks XORed with the offsets.
+
+        let mut sum: [u8; 16] = [0; 16];
+        let mut offset: [u8; 16] = [0; 16];
+
+        let mut chunks = associated_data.chunks_exact(16);
+        for (index, chunk) in (&mut chunks).enumerate() {
+            xor_blocks(&mut offset, self.l_table.get(index + 1));
+            let mut block: [u8; 16] = chunk.try_into().unwrap();
+            xor_blocks(&mut block, &offset);
+            xor_blocks(&mut sum, &self.aes_core.encrypt(&block));
+        }
+
+        let remainder: &[u8] = chunks.remainder();
+        if !remainder.is_empty() {
+            xor_blocks(&mut offset, &self.l_table.l_star);
+            let mut block: [u8; 16] = pad_partial(remainder);
+            xor_blocks(&mut block, &offset);
+            xor_blocks(&mut sum, &self.aes_core.encrypt(&block));
+        }
+
+        sum
+    }
+
+    fn tag(&self, checksum: &[u8; 16], offset: &[u8; 16], associated_data: &[u8]) -> [u8; 16] {
+        //! Computes the full 16-byte authentication tag from the checksum and the final offset.
+
+        let mut tag: [u8; 16] = *checksum;
+        xor_blocks(&mut tag, offset);
+        xor_blocks(&mut tag, &self.l_table.l_dollar);
+        tag = self.aes_core.encrypt(&tag);
+        xor_blocks(&mut tag, &self.hash(associated_data));
+
+        tag
+    }
+}
+
+/// The key-dependent values L_*, L_$ and L_i used to compute the offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct LTable {
+    /// L_*, the encryption of the zero block, used for the partial last block.
+    l_star: [u8; 16],
+    /// L_$, L_* doubled, used for the tag.
+    l_dollar: [u8; 16],
+    /// L_0, L_1, ..., each value is the previous one doubled.
+    l: [[u8; 16]; L_TABLE_SIZE],
+}
+
+impl LTable {
+    fn new(aes_core: &AESCore) -> Self {
+        //! Computes the table for the key of the AES core.
+
+        let l_star: [u8; 16] = aes_core.encrypt(&[0; 16]);
+        let l_dollar: [u8; 16] = double(&l_star);
+
+        let mut l: [[u8; 16]; L_TABLE_SIZE] = [[0; 16]; L_TABLE_SIZE];
+        l[0] = double(&l_dollar);
+        for i in 1..L_TABLE_SIZE {
+            l[i] = double(&l[i - 1]);
+        }
+
+        Self {
+            l_star,
+            l_dollar,
+            l,
+        }
+    }
+
+    fn get(&self, block_index: usize) -> &[u8; 16] {
+        //! Returns the value added to the offset for the block with the given (1-based) index, L_ntz(index).
+
+        &self.l[block_index.trailing_zeros() as usize]
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn pad_partial(data: &[u8]) -> [u8; 16] {
+    //! Pads the partial block with a single one bit and zeros.
+
+    let mut block: [u8; 16] = [0; 16];
+    block[..data.len()].copy_from_slice(data);
+    block[data.len()] = 0x80;
+
+    block
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The number of the L_i values, enough for any block index which fits into a `usize`.
+const L_TABLE_SIZE: usize = usize::BITS as usize;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    /// The sample results (RFC 7253, appendix A), as (last byte of the nonce, associated data length, plaintext length, ciphertext followed by the tag).
+    /// The nonce is BBAA99887766554433221100 with the last byte replaced, and the data are the bytes 0, 1, 2, ...
+    const SAMPLES: [(u8, usize, usize, &[u8]); 16] = [
+        (0x00, 0, 0, &[0x78, 0x54, 0x07, 0xbf, 0xff, 0xc8, 0xad, 0x9e, 0xdc, 0xc5, 0x52, 0x0a, 0xc9, 0x11, 0x1e, 0xe6]),
+        (0x01, 8, 8, &[
+            0x68, 0x20, 0xb3, 0x65, 0x7b, 0x6f, 0x61, 0x5a, 0x57, 0x25, 0xbd, 0xa0, 0xd3, 0xb4, 0xeb, 0x3a,
+            0x25, 0x7c, 0x9a, 0xf1, 0xf8, 0xf0, 0x30, 0x09,
+        ]),
+        (0x02, 8, 0, &[0x81, 0x01, 0x7f, 0x82, 0x03, 0xf0, 0x81, 0x27, 0x71, 0x52, 0xfa, 0xde, 0x69, 0x4a, 0x0a, 0x00]),
+        (0x03, 0, 8, &[
+            0x45, 0xdd, 0x69, 0xf8, 0xf5, 0xaa, 0xe7, 0x24, 0x14, 0x05, 0x4c, 0xd1, 0xf3, 0x5d, 0x82, 0x76,
+            0x0b, 0x2c, 0xd0, 0x0d, 0x2f, 0x99, 0xbf, 0xa9,
+        ]),
+        (0x04, 16, 16, &[
+            0x57, 0x1d, 0x53, 0x5b, 0x60, 0xb2, 0x77, 0x18, 0x8b, 0xe5, 0x14, 0x71, 0x70, 0xa9, 0xa2, 0x2c,
+            0x3a, 0xd7, 0xa4, 0xff, 0x38, 0x35, 0xb8, 0xc5, 0x70, 0x1c, 0x1c, 0xce, 0xc8, 0xfc, 0x33, 0x58,
+        ]),
+        (0x05, 16, 0, &[0x8c, 0xf7, 0x61, 0xb6, 0x90, 0x2e, 0xf7, 0x64, 0x46, 0x2a, 0xd8, 0x64, 0x98, 0xca, 0x6b, 0x97]),
+        (0x06, 0, 16, &[
+            0x5c, 0xe8, 0x8e, 0xc2, 0xe0, 0x69, 0x27, 0x06, 0xa9, 0x15, 0xc0, 0x0a, 0xeb, 0x8b, 0x23, 0x96,
+            0xf4, 0x0e, 0x1c, 0x74, 0x3f, 0x52, 0x43, 0x6b, 0xdf, 0x06, 0xd8, 0xfa, 0x1e, 0xca, 0x34, 0x3d,
+        ]),
+        (0x07, 24, 24, &[
+            0x1c, 0xa2, 0x20, 0x73, 0x08, 0xc8, 0x7c, 0x01, 0x07, 0x56, 0x10, 0x4d, 0x88, 0x40, 0xce, 0x19,
+            0x52, 0xf0, 0x96, 0x73, 0xa4, 0x48, 0xa1, 0x22, 0xc9, 0x2c, 0x62, 0x24, 0x10, 0x51, 0xf5, 0x73,
+            0x56, 0xd7, 0xf3, 0xc9, 0x0b, 0xb0, 0xe0, 0x7f,
+        ]),
+        (0x08, 24, 0, &[0x6d, 0xc2, 0x25, 0xa0, 0x71, 0xfc, 0x1b, 0x9f, 0x7c, 0x69, 0xf9, 0x3b, 0x0f, 0x1e, 0x10, 0xde]),
+        (0x09, 0, 24, &[
+            0x22, 0x1b, 0xd0, 0xde, 0x7f, 0xa6, 0xfe, 0x99, 0x3e, 0xcc, 0xd7, 0x69, 0x46, 0x0a, 0x0a, 0xf2,
+            0xd6, 0xcd, 0xed, 0x0c, 0x39, 0x5b, 0x1c, 0x3c, 0xe7, 0x25, 0xf3, 0x24, 0x94, 0xb9, 0xf9, 0x14,
+            0xd8, 0x5c, 0x0b, 0x1e, 0xb3, 0x83, 0x57, 0xff,
+        ]),
+        (0x0a, 32, 32, &[
+            0xbd, 0x6f, 0x6c, 0x49, 0x62, 0x01, 0xc6, 0x92, 0x96, 0xc1, 0x1e, 0xfd, 0x13, 0x8a, 0x46, 0x7a,
+            0xbd, 0x3c, 0x70, 0x79, 0x24, 0xb9, 0x64, 0xde, 0xaf, 0xfc, 0x40, 0x31, 0x9a, 0xf5, 0xa4, 0x85,
+            0x40, 0xfb, 0xba, 0x18, 0x6c, 0x55, 0x53, 0xc6, 0x8a, 0xd9, 0xf5, 0x92, 0xa7, 0x9a, 0x42, 0x40,
+        ]),
+        (0x0b, 32, 0, &[0xfe, 0x80, 0x69, 0x0b, 0xee, 0x8a, 0x48, 0x5d, 0x11, 0xf3, 0x29, 0x65, 0xbc, 0x9d, 0x2a, 0x32]),
+        (0x0c, 0, 32, &[
+            0x29, 0x42, 0xbf, 0xc7, 0x73, 0xbd, 0xa2, 0x3c, 0xab, 0xc6, 0xac, 0xfd, 0x9b, 0xfd, 0x58, 0x35,
+            0xbd, 0x30, 0x0f, 0x09, 0x73, 0x79, 0x2e, 0xf4, 0x60, 0x40, 0xc5, 0x3f, 0x14, 0x32, 0xbc, 0xdf,
+            0xb5, 0xe1, 0xdd, 0xe3, 0xbc, 0x18, 0xa5, 0xf8, 0x40, 0xb5, 0x2e, 0x65, 0x34, 0x44, 0xd5, 0xdf,
+        ]),
+        (0x0d, 40, 40, &[
+            0xd5, 0xca, 0x91, 0x74, 0x84, 0x10, 0xc1, 0x75, 0x1f, 0xf8, 0xa2, 0xf6, 0x18, 0x25, 0x5b, 0x68,
+            0xa0, 0xa1, 0x2e, 0x09, 0x3f, 0xf4, 0x54, 0x60, 0x6e, 0x59, 0xf9, 0xc1, 0xd0, 0xdd, 0xc5, 0x4b,
+            0x65, 0xe8, 0x62, 0x8e, 0x56, 0x8b, 0xad, 0x7a, 0xed, 0x07, 0xba, 0x06, 0xa4, 0xa6, 0x94, 0x83,
+            0xa7, 0x03, 0x54, 0x90, 0xc5, 0x76, 0x9e, 0x60,
+        ]),
+        (0x0e, 40, 0, &[0xc5, 0xcd, 0x9d, 0x18, 0x50, 0xc1, 0x41, 0xe3, 0x58, 0x64, 0x99, 0x94, 0xee, 0x70, 0x1b, 0x68]),
+        (0x0f, 0, 40, &[
+            0x44, 0x12, 0x92, 0x34, 0x93, 0xc5, 0x7d, 0x5d, 0xe0, 0xd7, 0x00, 0xf7, 0x53, 0xcc, 0xe0, 0xd1,
+            0xd2, 0xd9, 0x50, 0x60, 0x12, 0x2e, 0x9f, 0x15, 0xa5, 0xdd, 0xbf, 0xc5, 0x78, 0x7e, 0x50, 0xb5,
+            0xcc, 0x55, 0xee, 0x50, 0x7b, 0xcb, 0x08, 0x4e, 0x47, 0x9a, 0xd3, 0x63, 0xac, 0x36, 0x6b, 0x95,
+            0xa9, 0x8c, 0xa5, 0xf3, 0x00, 0x0b, 0x14, 0x79,
+        ]),
+    ];
+
+    #[test]
+    fn seal() {
+        //! Test sealing and opening (RFC 7253, appendix A, sample results)
+
+        let ocb = OCB::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let data: Vec<u8> = (0..40).collect();
+
+        for (nonce_byte, associated_data_length, plaintext_length, sealed) in SAMPLES {
+            let nonce: [u8; 12] = [0xbb, 0xaa, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, nonce_byte];
+
+            assert_eq!(ocb.seal(&nonce, &data[..associated_data_length], &data[..plaintext_length]).unwrap(), sealed);
+            assert_eq!(ocb.open(&nonce, &data[..associated_data_length], sealed).unwrap(), data[..plaintext_length]);
+        }
+    }
+
+    #[test]
+    fn seal_96_bit_tag() {
+        //! Test sealing and opening with a 96-bit tag (RFC 7253, appendix A)
+
+        let key: [u8; 16] = [
+            0x0f, 0x0e, 0x0d, 0x0c, 0x0b, 0x0a, 0x09, 0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00,
+        ];
+        let ocb = OCB::new(AESCore::new(AESKey::AES128(key)), 12).unwrap();
+        let nonce: [u8; 12] = [0xbb, 0xaa, 0x99, 0x88, 0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x0d];
+        let data: Vec<u8> = (0..40).collect();
+        let sealed: [u8; 52] = [
+            0x17, 0x92, 0xa4, 0xe3, 0x1e, 0x07, 0x55, 0xfb, 0x03, 0xe3, 0x1b, 0x22, 0x11, 0x6e, 0x6c, 0x2d,
+            0xdf, 0x9e, 0xfd, 0x6e, 0x33, 0xd5, 0x36, 0xf1, 0xa0, 0x12, 0x4b, 0x0a, 0x55, 0xba, 0xe8, 0x84,
+            0xed, 0x93, 0x48, 0x15, 0x29, 0xc7, 0x6b, 0x6a, 0xd0, 0xc5, 0x15, 0xf4, 0xd1, 0xcd, 0xd4, 0xfd,
+            0xac, 0x4f, 0x02, 0xaa,
+        ];
+
+        assert_eq!(ocb.seal(&nonce, &data, &data).unwrap(), sealed);
+        assert_eq!(ocb.open(&nonce, &data, &sealed).unwrap(), data);
+    }
+
+    #[test]
+    fn key_sizes_and_tag_lengths() {
+        //! Test all key sizes and tag lengths with the iterated test (RFC 7253, appendix A)
+
+        let outputs: [(usize, usize, &[u8]); 9] = [
+            (16, 16, &[0x67, 0xe9, 0x44, 0xd2, 0x32, 0x56, 0xc5, 0xe0, 0xb6, 0xc6, 0x1f, 0xa2, 0x2f, 0xdf, 0x1e, 0xa2]),
+            (24, 16, &[0xf6, 0x73, 0xf2, 0xc3, 0xe7, 0x17, 0x4a, 0xae, 0x7b, 0xae, 0x98, 0x6c, 0xa9, 0xf2, 0x9e, 0x17]),
+            (32, 16, &[0xd9, 0x0e, 0xb8, 0xe9, 0xc9, 0x77, 0xc8, 0x8b, 0x79, 0xdd, 0x79, 0x3d, 0x7f, 0xfa, 0x16, 0x1c]),
+            (16, 12, &[0x77, 0xa3, 0xd8, 0xe7, 0x35, 0x89, 0x15, 0x8d, 0x25, 0xd0, 0x12, 0x09]),
+            (24, 12, &[0x05, 0xd5, 0x6e, 0xad, 0x27, 0x52, 0xc8, 0x6b, 0xe6, 0x93, 0x2c, 0x5e]),
+            (32, 12, &[0x54, 0x58, 0x35, 0x9a, 0xc2, 0x3b, 0x0c, 0xba, 0x9e, 0x63, 0x30, 0xdd]),
+            (16, 8, &[0x19, 0x2c, 0x9b, 0x7b, 0xd9, 0x0b, 0xa0, 0x6a]),
+            (24, 8, &[0x00, 0x66, 0xbc, 0x6e, 0x0e, 0xf3, 0x4e, 0x24]),
+            (32, 8, &[0x7d, 0x4e, 0xa5, 0xd4, 0x45, 0x50, 0x1c, 0xbe]),
+        ];
+
+        for (key_length, tag_length, output) in outputs {
+            let mut key: Vec<u8> = vec![0; key_length];
+            key[key_length - 1] = (tag_length * 8) as u8;
+            let aes_core: AESCore = match key_length {
+                16 => AESCore::new(AESKey::AES128(key.try_into().unwrap())),
+                24 => AESCore::new(AESKey::AES192(key.try_into().unwrap())),
+                _ => AESCore::new(AESKey::AES256(key.try_into().unwrap())),
+            };
+            let ocb = OCB::new(aes_core, tag_length).unwrap();
+
+            let nonce = |i: u32| -> [u8; 12] {
+                let mut nonce: [u8; 12] = [0; 12];
+                nonce[8..].copy_from_slice(&i.to_be_bytes());
+                nonce
+            };
+
+            let mut ciphertext: Vec<u8> = Vec::new();
+            for i in 0..128 {
+                let data: Vec<u8> = vec![0; i as usize];
+                ciphertext.extend(ocb.seal(&nonce(3 * i + 1), &data, &data).unwrap());
+                ciphertext.extend(ocb.seal(&nonce(3 * i + 2), &[], &data).unwrap());
+                ciphertext.extend(ocb.seal(&nonce(3 * i + 3), &data, &[]).unwrap());
+            }
+
+            assert_eq!(ocb.seal(&nonce(385), &ciphertext, &[]).unwrap(), output);
+        }
+    }
+
+    #[test]
+    fn nonce_length() {
+        //! Test all nonce lengths from 8 to 120 bits
+
+        let ocb = OCB::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let data: Vec<u8> = (0..100).collect();
+
+        for nonce_length in 1..=15 {
+            let sealed: Vec<u8> = ocb.seal(&data[..nonce_length], &data[..7], &data).unwrap();
+            assert_eq!(ocb.open(&data[..nonce_length], &data[..7], &sealed).unwrap(), data);
+        }
+
+        assert_eq!(ocb.seal(&[], &[], &data), Err(ModeError::InvalidNonceSize));
+        assert_eq!(ocb.seal(&data[..16], &[], &data), Err(ModeError::InvalidNonceSize));
+        assert_eq!(ocb.open(&data[..16], &[], &[0; 16]), Err(ModeError::InvalidNonceSize));
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that any modification is detected
+
+        let mut ocb = OCB::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let nonce: [u8; 12] = [0; 12];
+        let sealed: Vec<u8> = ocb.seal(&nonce, b"header", b"authenticated data").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(ocb.open(&nonce, b"header", &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(ocb.open(&nonce, b"Header", &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(ocb.open(&nonce, b"header", &sealed[..15]), Err(ModeError::InvalidInputSize));
+
+        for tag_length in [0, 4, 10, 15, 17] {
+            assert_eq!(ocb.set_tag_length(tag_length), Err(ModeError::InvalidTagLength));
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! A module containing the OFB (Output Feedback) mode of operation.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::{
+    Padding,
+    PaddingTypes,
+};
+use super::{
+    Decryptor,
+    Encryptor,
+    stream::StreamState,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The OFB (Output Feedback) mode of operation.
+/// The keystream is made by repeatedly encrypting the IV and is XORed with the data.
+/// No padding is needed, so data of any length is supported, and encryption and decryption are the same operation.
+///
+/// Data can be processed in several parts with `update`, which continues the keystream where the previous call stopped.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OFB {
+    /// The AES core used to make the keystream.
+    aes_core: AESCore,
+    /// The initialization vector.
+    iv: [u8; 16],
+    /// The current keystream block.
+    keystream_block: [u8; 16],
+    /// The number of bytes of the current keystream block that were already used.
+    keystream_position: usize,
+}
+
+/// Public functions for encrypting and decrypting data.
+impl OFB {
+    pub fn new(aes_core: AESCore, iv: [u8; 16]) -> Self {
+        //! Creates a new OFB instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core used to make the keystream.
+        //! * `iv` - The initialization vector. It must never be reused with the same key.
+
+        Self {
+            aes_core,
+            iv,
+            keystream_block: iv,
+            keystream_position: 16,
+        }
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core used by this OFB instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) {
+        //! Changes the AES core used by this OFB instance.
+        //! The keystream is restarted from the IV.
+
+        self.aes_core = aes_core;
+        self.reset();
+    }
+
+    pub fn iv(&self) -> [u8; 16] {
+        //! Returns the initialization vector used by this OFB instance.
+
+        self.iv
+    }
+
+    pub fn set_iv(&mut self, iv: [u8; 16]) {
+        //! Changes the initialization vector used by this OFB instance.
+        //! The keystream is restarted from the new IV.
+
+        self.iv = iv;
+        self.reset();
+    }
+
+    pub fn reset(&mut self) {
+        //! Restarts the keystream from the IV, discarding the progress of previous `update` calls.
+
+        self.keystream_block = self.iv;
+        self.keystream_position = 16;
+    }
+
+    pub fn encrypt(&self, input: &[u8]) -> Vec<u8> {
+        //! Encrypts the given data from the start of the keystream.
+        //! The progress of `update` calls isn't affected.
+        //! # Arguments
+        //! * `input` - The plaintext of any length.
+        //! # Returns
+        //! * Vec<u8> - The ciphertext.
+
+        let mut ofb: OFB = OFB::new(self.aes_core, self.iv);
+        ofb.update(input)
+    }
+
+    pub fn decrypt(&self, input: &[u8]) -> Vec<u8> {
+        //! Decrypts the given data from the start of the keystream.
+        //! The progress of `update` calls isn't affected.
+        //! # Arguments
+        //! * `input` - The ciphertext of any length.
+        //! # Returns
+        //! * Vec<u8> - The plaintext.
+
+        self.encrypt(input)
+    }
+
+    pub fn update(&mut self, input: &[u8]) -> Vec<u8> {
+        //! Encrypts or decrypts the next part of the data, continuing the keystream.
+        //! # Arguments
+        //! * `input` - The next part of the data, of any length.
+        //! # Returns
+        //! * Vec<u8> - The encrypted or decrypted part.
+
+        let mut output: Vec<u8> = input.to_vec();
+        self.apply_keystream(&mut output);
+        output
+    }
+
+    pub fn apply_keystream(&mut self, buffer: &mut [u8]) {
+        //! Encrypts or decrypts the next part of the data in place, continuing the keystream.
+        //! # Arguments
+        //! * `buffer` - The next part of the data, of any length.
+
+        // the rest of the current keystream block, the full blocks and then the start of a new keystream block
+        let length: usize = buffer.len().min(16 - self.keystream_position);
+        let (current, rest) = buffer.split_at_mut(length);
+        for (byte, keystream_byte) in current.iter_mut().zip(self.keystream_block[self.keystream_position..].iter()) {
+            *byte ^= keystream_byte;
+        }
+        self.keystream_position += length;
+
+        let (blocks, tail) = rest.as_chunks_mut::<16>();
+        for block in blocks.iter_mut() {
+            self.keystream_block = self.aes_core.encrypt(&self.keystream_block);
+            xor_blocks(block, &self.keystream_block);
+        }
+
+        if !tail.is_empty() {
+            self.keystream_block = self.aes_core.encrypt(&self.keystream_block);
+            for (byte, keystream_byte) in tail.iter_mut().zip(self.keystream_block.iter()) {
+                *byte ^= keystream_byte;
+            }
+            self.keystream_position = tail.len();
+        }
+    }
+
+    pub fn encryptor(&self) -> Encryptor {
+        //! Returns a streaming encryptor for encrypting the data in parts, see the `Encryptor` struct.
+        //! The keystream starts from the IV, independently of the progress of `update` calls.
+
+        Encryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::ofb(OFB::new(self.aes_core, self.iv)))
+    }
+
+    pub fn decryptor(&self) -> Decryptor {
+        //! Returns a streaming decryptor for decrypting the data in parts, see the `Decryptor` struct.
+        //! The keystream starts from the IV, independently of the progress of `update` calls.
+
+        Decryptor::new(self.aes_core, Padding::new(PaddingTypes::None), StreamState::ofb(OFB::new(self.aes_core, self.iv)))
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b,
+        0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    const PLAINTEXT: [u8; 32] = [
+        0x6b, 0xc1, 0xbe, 0xe2, 0x2e, 0x40, 0x9f, 0x96, 0xe9, 0x3d, 0x7e, 0x11, 0x73, 0x93, 0x17, 0x2a,
+        0xae, 0x2d, 0x8a, 0x57, 0x1e, 0x03, 0xac, 0x9c, 0x9e, 0xb7, 0x6f, 0xac, 0x45, 0xaf, 0x8e, 0x51,
+    ];
+
+    const CIPHERTEXT: [u8; 32] = [
+        0x3b, 0x3f, 0xd9, 0x2e, 0xb7, 0x2d, 0xad, 0x20, 0x33, 0x34, 0x49, 0xf8, 0xe8, 0x3c, 0xfb, 0x4a,
+        0x77, 0x89, 0x50, 0x8d, 0x16, 0x91, 0x8f, 0x03, 0xf5, 0x3c, 0x52, 0xda, 0xc5, 0x4e, 0xd8, 0x25,
+    ];
+
+    #[test]
+    fn encrypt() {
+        //! Test encryption of two blocks (SP 800-38A, F.4.1)
+
+        let ofb = OFB::new(AESCore::new(AESKey::AES128(KEY)), IV);
+
+        assert_eq!(ofb.encrypt(&PLAINTEXT), CIPHERTEXT);
+        assert_eq!(ofb.decrypt(&CIPHERTEXT), PLAINTEXT);
+        assert_eq!(ofb.encrypt(&PLAINTEXT[..21]), CIPHERTEXT[..21]);
+    }
+
+    #[test]
+    fn update() {
+        //! Test that the keystream continues across update calls
+
+        let mut ofb = OFB::new(AESCore::new(AESKey::AES128(KEY)), IV);
+
+        let mut ciphertext: Vec<u8> = Vec::new();
+        for part in [&PLAINTEXT[..3], &PLAINTEXT[3..3], &PLAINTEXT[3..19], &PLAINTEXT[19..]] {
+            ciphertext.extend(ofb.update(part));
+        }
+        assert_eq!(ciphertext, CIPHERTEXT);
+
+        // the one-shot functions always start from the IV
+        assert_eq!(ofb.encrypt(&PLAINTEXT), CIPHERTEXT);
+
+        let mut buffer: [u8; 32] = CIPHERTEXT;
+        ofb.reset();
+        ofb.apply_keystream(&mut buffer[..7]);
+        ofb.apply_keystream(&mut buffer[7..]);
+        assert_eq!(buffer, PLAINTEXT);
+    }
+
+    #[test]
+    fn set_iv() {
+        //! Test that changing the IV restarts the keystream
+
+        let mut ofb = OFB::new(AESCore::new(AESKey::AES128(KEY)), [0; 16]);
+        ofb.update(&PLAINTEXT[..5]);
+
+        ofb.set_iv(IV);
+        assert_eq!(ofb.iv(), IV);
+        assert_eq!(ofb.update(&PLAINTEXT), CIPHERTEXT);
+    }
+}
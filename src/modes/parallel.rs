@@ -0,0 +1,423 @@
+//! A module containing the thread pool used for encrypting and decrypting large buffers in parallel.
+
+
+
+
+
+// IMPORTS
+
+use std::fmt;
+use std::panic::{
+    self,
+    AssertUnwindSafe,
+};
+use std::sync::{
+    Arc,
+    mpsc,
+};
+
+use tinypool::ThreadPool;
+
+use super::ModeError;
+
+
+
+
+
+// STRUCTS
+
+/// The thread pool and the chunk size used by the parallel functions of the modes (e.g. `ECB::encrypt_parallel`).
+/// The data is split into chunks, which are processed by the threads of the pool, and the results are joined in order.
+/// The output is always the same as the output of the single-threaded functions.
+///
+/// The threads are kept between the calls, so the same instance should be reused for many buffers.
+/// A job which panics is reported as `ModeError::ThreadPool`, and its thread is kept.
+pub struct Parallel {
+    /// The thread pool processing the chunks.
+    thread_pool: ThreadPool,
+    /// The size of a chunk in bytes, a positive multiple of 16.
+    chunk_size: usize,
+}
+
+/// A chunk of a buffer given to a job of the thread pool.
+/// The buffer outlives the job, because `Parallel::execute_jobs` waits for all jobs before returning.
+struct ChunkPointer {
+    /// The start of the chunk.
+    pointer: *mut u8,
+    /// The length of the chunk in bytes.
+    length: usize,
+}
+
+/// Public functions for configuring the parallel processing.
+impl Parallel {
+    pub fn new(thread_count: usize, chunk_size: usize) -> Result<Self, ModeError> {
+        //! Creates a new thread pool for parallel processing.
+        //! # Arguments
+        //! * `thread_count` - The number of threads. Use `0` to use the number of available CPU cores.
+        //! * `chunk_size` - The size of a chunk in bytes, a positive multiple of 16. A chunk of 64 KiB or more is recommended.
+        //! # Returns
+        //! * Result<Parallel, ModeError> - The Parallel instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidChunkSize - The chunk size isn't a positive multiple of 16 bytes.
+        //! * ModeError::ThreadPool - A thread couldn't be spawned.
+
+        Self::check_chunk_size(chunk_size)?;
+
+        Ok(Self {
+            thread_pool: ThreadPool::new(thread_count)?,
+            chunk_size,
+        })
+    }
+
+    pub fn thread_count(&self) -> usize {
+        //! Returns the number of threads.
+
+        self.thread_pool.size()
+    }
+
+    pub fn set_thread_count(&mut self, thread_count: usize) -> Result<(), ModeError> {
+        //! Changes the number of threads.
+        //! # Arguments
+        //! * `thread_count` - The number of threads. Use `0` to use the number of available CPU cores.
+        //! # Errors
+        //! * ModeError::ThreadPool - A thread couldn't be spawned.
+
+        self.thread_pool.set_size(thread_count)?;
+
+        Ok(())
+    }
+
+    pub fn chunk_size(&self) -> usize {
+        //! Returns the size of a chunk in bytes.
+
+        self.chunk_size
+    }
+
+    pub fn set_chunk_size(&mut self, chunk_size: usize) -> Result<(), ModeError> {
+        //! Changes the size of a chunk in bytes.
+        //! # Errors
+        //! * ModeError::InvalidChunkSize - The chunk size isn't a positive multiple of 16 bytes.
+
+        Self::check_chunk_size(chunk_size)?;
+        self.chunk_size = chunk_size;
+
+        Ok(())
+    }
+}
+
+/// Functions for running the jobs.
+impl Parallel {
+    fn check_chunk_size(chunk_size: usize) -> Result<(), ModeError> {
+        //! Checks that the chunk size is a positive multiple of 16 bytes.
+
+        if chunk_size == 0 || !chunk_size.is_multiple_of(16) {
+            return Err(ModeError::InvalidChunkSize);
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn map_chunks<T, F>(&self, data: &[u8], chunk_size: usize, job: F) -> Result<Vec<T>, ModeError>
+    where
+        T: Send + 'static,
+        F: Fn(usize, &[u8]) -> Result<T, ModeError> + Send + Sync + 'static,
+    {
+        //! Runs the job on every chunk of the data on the thread pool, and returns the results in the order of the chunks.
+        //! The job gets the index and the chunk.
+        //! # Errors
+        //! * The error of the first chunk whose job failed.
+        //! * ModeError::ThreadPool - The job couldn't be added to the thread pool, or panicked.
+
+        let mut results: Vec<Result<T, ModeError>> = (0..data.len().div_ceil(chunk_size))
+            .map(|_| Err(ModeError::ThreadPool))
+            .collect();
+
+        // SAFETY: the jobs only read the data, which is borrowed until the function returns
+        unsafe {
+            self.execute_jobs(data.as_ptr().cast_mut(), data.len(), chunk_size, move |index, chunk| {
+                job(index, chunk.as_slice())
+            }, |index, result| results[index] = result)?;
+        }
+
+        results.into_iter().collect()
+    }
+
+    pub(super) fn map_buffer<F>(&self, data: &[u8], chunk_size: usize, job: F) -> Result<Vec<u8>, ModeError>
+    where
+        F: Fn(usize, &mut [u8]) -> Result<(), ModeError> + Send + Sync + 'static,
+    {
+        //! Transforms every chunk of a copy of the data in place on the thread pool, and returns the copy.
+        //! # Errors
+        //! * The error of the first chunk whose job failed.
+        //! * ModeError::ThreadPool - The job couldn't be added to the thread pool, or panicked.
+
+        let mut output: Vec<u8> = data.to_vec();
+        let mut results: Vec<Result<(), ModeError>> = (0..data.len().div_ceil(chunk_size))
+            .map(|_| Err(ModeError::ThreadPool))
+            .collect();
+
+        // SAFETY: the output is borrowed until the function returns, and every job gets its own chunk of it
+        unsafe {
+            self.execute_jobs(output.as_mut_ptr(), output.len(), chunk_size, move |index, mut chunk| {
+                job(index, chunk.as_mut_slice())
+            }, |index, result| results[index] = result)?;
+        }
+        results.into_iter().collect::<Result<(), ModeError>>()?;
+
+        Ok(output)
+    }
+
+    unsafe fn execute_jobs<T, F, R>(&self, buffer: *mut u8, length: usize, chunk_size: usize, job: F, mut receive: R) -> Result<(), ModeError>
+    where
+        T: Send + 'static,
+        F: Fn(usize, ChunkPointer) -> Result<T, ModeError> + Send + Sync + 'static,
+        R: FnMut(usize, Result<T, ModeError>),
+    {
+        //! Runs the job on every chunk of the buffer on the thread pool, and gives the index and the result of every chunk to `receive` once it is done.
+        //! The results come in the order in which the jobs finish. A job which panics gives `ModeError::ThreadPool`.
+        //! # Safety
+        //! The buffer must be valid for `length` bytes until the function returns, and only be written by the jobs if it is borrowed mutably.
+        //! # Errors
+        //! * ModeError::ThreadPool - The job couldn't be added to the thread pool.
+
+        let job: Arc<F> = Arc::new(job);
+        let (sender, receiver) = mpsc::channel::<(usize, Result<T, ModeError>)>();
+
+        let mut result: Result<(), ModeError> = Ok(());
+        for (index, start) in (0..length).step_by(chunk_size).enumerate() {
+            let job: Arc<F> = Arc::clone(&job);
+            let sender: mpsc::Sender<(usize, Result<T, ModeError>)> = sender.clone();
+            // SAFETY: the chunk is inside the buffer
+            let chunk: ChunkPointer = ChunkPointer { pointer: unsafe { buffer.add(start) }, length: chunk_size.min(length - start) };
+
+            // a panic is caught, so the thread is kept and every job sends its result
+            let added: Result<(), ModeError> = self.thread_pool.execute(move || {
+                let result: Result<T, ModeError> = panic::catch_unwind(AssertUnwindSafe(|| job(index, chunk)))
+                    .unwrap_or(Err(ModeError::ThreadPool));
+                let _ = sender.send((index, result));
+            }).map_err(ModeError::from);
+            if added.is_err() {
+                result = added;
+                break;
+            }
+        }
+        drop(sender);
+
+        // every job holds a sender until it has run, so all chunks are released once the receiver is empty
+        for (index, result) in receiver {
+            receive(index, result);
+        }
+
+        result
+    }
+}
+
+impl fmt::Debug for Parallel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parallel")
+            .field("thread_count", &self.thread_count())
+            .field("chunk_size", &self.chunk_size)
+            .finish()
+    }
+}
+
+// SAFETY: the chunks of a buffer are disjoint, so every chunk is only accessed by the thread of its job
+unsafe impl Send for ChunkPointer {}
+
+/// Functions for accessing the chunk.
+impl ChunkPointer {
+    fn as_slice(&self) -> &[u8] {
+        //! Returns the chunk.
+
+        // SAFETY: the buffer is valid while the job runs, see `Parallel::execute_jobs`
+        unsafe { std::slice::from_raw_parts(self.pointer, self.length) }
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        //! Returns the chunk mutably. The buffer must be borrowed mutably by the caller of `Parallel::execute_jobs`.
+
+        // SAFETY: the buffer is valid while the job runs and no other job accesses the chunk, see `Parallel::execute_jobs`
+        unsafe { std::slice::from_raw_parts_mut(self.pointer, self.length) }
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::{
+        AESCore,
+        AESKey,
+    };
+    use crate::modes::{
+        CBC,
+        CTR,
+        CounterLayout,
+        ECB,
+        GCM,
+        XTS,
+    };
+    use crate::padding::{
+        Padding,
+        PaddingTypes,
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    /// The lengths of the data, chosen around the multiples of the chunk sizes used by the tests.
+    const LENGTHS: [usize; 8] = [0, 1, 16, 47, 48, 64, 1000, 4099];
+
+    fn parallel_instances() -> Vec<Parallel> {
+        //! Returns thread pools with different numbers of threads and chunk sizes.
+
+        vec![
+            Parallel::new(1, 16).unwrap(),
+            Parallel::new(3, 48).unwrap(),
+            Parallel::new(4, 1024).unwrap(),
+            Parallel::new(0, 64).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn ecb_and_cbc() {
+        //! Test that parallel ECB and CBC decryption give the same output as the single-threaded functions
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        for parallel in parallel_instances() {
+            for padding_type in [PaddingTypes::PKCS7, PaddingTypes::None] {
+                let ecb = ECB::new(aes_core, Padding::new(padding_type));
+                let cbc = CBC::new(aes_core, Padding::new(padding_type), [0x0f; 16]);
+
+                for length in LENGTHS {
+                    let plaintext: Vec<u8> = (0..length).map(|i| i as u8).collect();
+                    assert_eq!(ecb.encrypt_parallel(&parallel, &plaintext), ecb.encrypt(&plaintext));
+
+                    let ciphertext: Vec<u8> = match ecb.encrypt(&plaintext) {
+                        Ok(ciphertext) => ciphertext,
+                        Err(_) => continue,
+                    };
+                    assert_eq!(ecb.decrypt_parallel(&parallel, &ciphertext).unwrap(), plaintext);
+
+                    let ciphertext: Vec<u8> = cbc.encrypt(&plaintext).unwrap();
+                    assert_eq!(cbc.decrypt_parallel(&parallel, &ciphertext).unwrap(), plaintext);
+                }
+
+                assert_eq!(ecb.decrypt_parallel(&parallel, &[0; 17]), Err(ModeError::InvalidInputSize));
+                assert_eq!(cbc.decrypt_parallel(&parallel, &[0; 17]), Err(ModeError::InvalidInputSize));
+            }
+        }
+    }
+
+    #[test]
+    fn ctr() {
+        //! Test that parallel CTR gives the same output as the single-threaded functions
+
+        let aes_core = AESCore::new(AESKey::AES192([0x24; 24]));
+        let ctr = CTR::new(aes_core, CounterLayout::Nonce96Counter32, [0xf0; 16]);
+        for parallel in parallel_instances() {
+            for length in LENGTHS {
+                let plaintext: Vec<u8> = (0..length).map(|i| i as u8).collect();
+                let ciphertext: Vec<u8> = ctr.encrypt(&plaintext).unwrap();
+                assert_eq!(ctr.encrypt_parallel(&parallel, &plaintext).unwrap(), ciphertext);
+                assert_eq!(ctr.decrypt_parallel(&parallel, &ciphertext).unwrap(), plaintext);
+            }
+        }
+
+        // the counter overflows in the last chunk
+        let ctr = CTR::new(aes_core, CounterLayout::Nonce96Counter32, [0xff; 16]);
+        let parallel = Parallel::new(2, 16).unwrap();
+        assert_eq!(ctr.encrypt_parallel(&parallel, &[0; 17]), Err(ModeError::CounterOverflow));
+    }
+
+    #[test]
+    fn xts() {
+        //! Test that parallel XTS gives the same output as encrypting the sectors one by one
+
+        let xts = XTS::new(AESCore::new(AESKey::AES256([0x11; 32])), AESCore::new(AESKey::AES256([0x22; 32]))).unwrap();
+        let plaintext: Vec<u8> = (0..5000).map(|i| i as u8).collect();
+        for parallel in parallel_instances() {
+            for (sector_size, length) in [(16, 4096), (512, 5000), (100, 1020), (4096, 4096)] {
+                let expected: Vec<u8> = plaintext[..length].chunks(sector_size)
+                    .enumerate()
+                    .flat_map(|(i, sector)| xts.encrypt(7 + i as u128, sector).unwrap())
+                    .collect();
+
+                let ciphertext: Vec<u8> = xts.encrypt_sectors_parallel(&parallel, 7, sector_size, &plaintext[..length]).unwrap();
+                assert_eq!(ciphertext, expected);
+                assert_eq!(xts.decrypt_sectors_parallel(&parallel, 7, sector_size, &ciphertext).unwrap(), plaintext[..length]);
+            }
+
+            // the last sector is shorter than a block
+            assert_eq!(xts.encrypt_sectors_parallel(&parallel, 0, 512, &plaintext[..520]), Err(ModeError::InvalidInputSize));
+            assert_eq!(xts.encrypt_sectors_parallel(&parallel, 0, 15, &plaintext), Err(ModeError::InvalidInputSize));
+        }
+    }
+
+    #[test]
+    fn gcm() {
+        //! Test that parallel GCM gives the same output as the single-threaded functions
+
+        let gcm = GCM::new(AESCore::new(AESKey::AES128(KEY)), 16).unwrap();
+        let associated_data: Vec<u8> = (0..37).collect();
+        for parallel in parallel_instances() {
+            for length in LENGTHS {
+                let plaintext: Vec<u8> = (0..length).map(|i| i as u8).collect();
+                for iv in [&[0xca; 12][..], &[0xfe; 7][..]] {
+                    let sealed: Vec<u8> = gcm.seal(iv, &associated_data, &plaintext).unwrap();
+                    assert_eq!(gcm.seal_parallel(&parallel, iv, &associated_data, &plaintext).unwrap(), sealed);
+                    assert_eq!(gcm.open_parallel(&parallel, iv, &associated_data, &sealed).unwrap(), plaintext);
+
+                    let mut modified: Vec<u8> = sealed.clone();
+                    modified[0] ^= 0x01;
+                    assert_eq!(gcm.open_parallel(&parallel, iv, &associated_data, &modified), Err(ModeError::AuthenticationFailed));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn panicking_job() {
+        //! Test that a job which panics gives an error, and that the threads are kept
+
+        for thread_count in [1, 2] {
+            let parallel = Parallel::new(thread_count, 16).unwrap();
+            let result: Result<Vec<u8>, ModeError> = parallel.map_buffer(&[0; 64], 16, |index, _| {
+                assert_ne!(index, 2, "the job of the third chunk panics");
+                Ok(())
+            });
+            assert_eq!(result, Err(ModeError::ThreadPool));
+
+            let result: Result<Vec<usize>, ModeError> = parallel.map_chunks(&[0; 64], 16, |index, _| Ok(index));
+            assert_eq!(result, Ok(vec![0, 1, 2, 3]));
+        }
+    }
+
+    #[test]
+    fn configuration() {
+        //! Test changing the number of threads and the chunk size, and the errors returned on an invalid chunk size
+
+        let mut parallel = Parallel::new(2, 32).unwrap();
+        assert_eq!(parallel.thread_count(), 2);
+        assert_eq!(parallel.chunk_size(), 32);
+
+        parallel.set_thread_count(5).unwrap();
+        assert_eq!(parallel.thread_count(), 5);
+        parallel.set_chunk_size(4096).unwrap();
+        assert_eq!(parallel.chunk_size(), 4096);
+
+        for chunk_size in [0, 8, 17, 1000] {
+            assert_eq!(parallel.set_chunk_size(chunk_size), Err(ModeError::InvalidChunkSize));
+            assert!(matches!(Parallel::new(1, chunk_size), Err(ModeError::InvalidChunkSize)));
+        }
+    }
+}
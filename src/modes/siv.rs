@@ -0,0 +1,370 @@
+//! A module containing the SIV (Synthetic Initialization Vector) authenticated encryption mode.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::{
+    AESCore,
+    AESKey,
+    Implementation,
+};
+use super::{
+    CMAC,
+    CTR,
+    CounterLayout,
+    ModeError,
+    constant_time_eq,
+    double,
+    xor_blocks,
+};
+
+
+
+
+
+// ENUMS
+
+/// The double-length key used by the SIV mode.
+/// The first half is the key of S2V (CMAC), and the second half is the key of the counter mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SIVKey {
+    AES128([u8; 32]),
+    AES192([u8; 48]),
+    AES256([u8; 64]),
+}
+
+
+
+
+
+// STRUCTS
+
+/// The SIV (Synthetic Initialization Vector) authenticated encryption mode, as defined in RFC 5297.
+/// The synthetic IV is computed with S2V over the associated data and the plaintext, and is used both as the tag and as the counter block.
+///
+/// The sealed output is the synthetic IV followed by the ciphertext.
+/// Sealing is deterministic: the same associated data and plaintext always give the same output, which only reveals whether they repeated.
+/// To use a nonce, pass it as the last associated data component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SIV {
+    /// The double-length key.
+    key: SIVKey,
+    /// The AES core used by S2V, made from the first half of the key.
+    mac_core: AESCore,
+    /// The AES core used by the counter mode, made from the second half of the key.
+    ctr_core: AESCore,
+    /// The implementation of both AES cores.
+    implementation: Implementation,
+}
+
+/// Public functions for sealing and opening data.
+impl SIV {
+    pub fn new(key: SIVKey) -> Self {
+        //! Creates a new SIV instance with the given double-length key.
+        //! The AES cores use the default implementation, see `set_implementation`.
+
+        let implementation: Implementation = Implementation::Hardware;
+        let (mac_core, ctr_core) = Self::split_key(&key, implementation);
+
+        Self {
+            key,
+            mac_core,
+            ctr_core,
+            implementation,
+        }
+    }
+
+    pub fn key(&self) -> SIVKey {
+        //! Returns the key used by this SIV instance.
+
+        self.key
+    }
+
+    pub fn set_key(&mut self, key: SIVKey) {
+        //! Changes the key used by this SIV instance.
+
+        (self.mac_core, self.ctr_core) = Self::split_key(&key, self.implementation);
+        self.key = key;
+    }
+
+    pub fn implementation(&self) -> Implementation {
+        //! Returns the implementation of the AES cores used by this SIV instance.
+
+        self.implementation
+    }
+
+    pub fn set_implementation(&mut self, implementation: Implementation) {
+        //! Changes the implementation of the AES cores used by this SIV instance.
+
+        (self.mac_core, self.ctr_core) = Self::split_key(&self.key, implementation);
+        self.implementation = implementation;
+    }
+
+    pub fn seal(&self, associated_data: &[&[u8]], plaintext: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts and authenticates the plaintext, and authenticates the associated data.
+        //! # Arguments
+        //! * `associated_data` - The components of the associated data (at most 126), which are authenticated, but not encrypted.
+        //! * `plaintext` - The data which is encrypted and authenticated.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The synthetic IV followed by the ciphertext, or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - There are more than 126 associated data components.
+
+        if associated_data.len() > MAX_ASSOCIATED_DATA_COMPONENTS {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let synthetic_iv: [u8; 16] = self.s2v(associated_data, plaintext);
+
+        let mut output: Vec<u8> = synthetic_iv.to_vec();
+        output.extend(self.ctr(&synthetic_iv, plaintext)?);
+
+        Ok(output)
+    }
+
+    pub fn open(&self, associated_data: &[&[u8]], sealed: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the ciphertext and verifies the synthetic IV.
+        //! The plaintext is only released if the synthetic IV is valid.
+        //! # Arguments
+        //! * `associated_data` - The components of the associated data used for sealing.
+        //! * `sealed` - The synthetic IV followed by the ciphertext.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sealed data is shorter than 16 bytes or there are more than 126 associated data components.
+        //! * ModeError::AuthenticationFailed - The synthetic IV doesn't match the data.
+
+        if sealed.len() < 16 || associated_data.len() > MAX_ASSOCIATED_DATA_COMPONENTS {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let (synthetic_iv, ciphertext) = sealed.split_at(16);
+        let synthetic_iv: [u8; 16] = synthetic_iv.try_into().unwrap();
+        let plaintext: Vec<u8> = self.ctr(&synthetic_iv, ciphertext)?;
+
+        if !constant_time_eq(&self.s2v(associated_data, &plaintext), &synthetic_iv) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(plaintext)
+    }
+}
+
+/// Functions used in the SIV algorithm.
+impl SIV {
+    fn split_key(key: &SIVKey, implementation: Implementation) -> (AESCore, AESCore) {
+        //! Makes the AES cores of S2V and of the counter mode from the two halves of the key.
+
+        let (mac_key, ctr_key) = match key {
+            SIVKey::AES128(key) => (
+                AESKey::AES128(key[..16].try_into().unwrap()),
+                AESKey::AES128(key[16..].try_into().unwrap()),
+            ),
+            SIVKey::AES192(key) => (
+                AESKey::AES192(key[..24].try_into().unwrap()),
+                AESKey::AES192(key[24..].try_into().unwrap()),
+            ),
+            SIVKey::AES256(key) => (
+                AESKey::AES256(key[..32].try_into().unwrap()),
+                AESKey::AES256(key[32..].try_into().unwrap()),
+            ),
+        };
+
+        let mut mac_core: AESCore = AESCore::new(mac_key);
+        mac_core.set_implementation(implementation);
+        let mut ctr_core: AESCore = AESCore::new(ctr_key);
+        ctr_core.set_implementation(implementation);
+
+        (mac_core, ctr_core)
+    }
+
+    fn cmac(&self, data: &[u8]) -> [u8; 16] {
+        //! Computes the CMAC of the data with the S2V key.
+
+        let mut cmac: CMAC = CMAC::new(self.mac_core);
+        cmac.update(data);
+        cmac.finalize()
+    }
+
+    fn s2v(&self, associated_data: &[&[u8]], plaintext: &[u8]) -> [u8; 16] {
+        //! Computes the synthetic IV with S2V over the associated data components and the plaintext.
+
+        let mut d: [u8; 16] = self.cmac(&[0; 16]);
+        for component in associated_data {
+            d = double(&d);
+            xor_blocks(&mut d, &self.cmac(component));
+        }
+
+        if plaintext.len() >= 16 {
+            // XOR the value into the last 16 bytes of the plaintext
+            let mut t: Vec<u8> = plaintext.to_vec();
+            let last_block_start: usize = t.len() - 16;
+            for (byte, d_byte) in t[last_block_start..].iter_mut().zip(d) {
+                *byte ^= d_byte;
+            }
+            self.cmac(&t)
+        } else {
+            let mut t: [u8; 16] = [0; 16];
+            t[..plaintext.len()].copy_from_slice(plaintext);
+            t[plaintext.len()] = 0x80;
+            xor_blocks(&mut t, &double(&d));
+            self.cmac(&t)
+        }
+    }
+
+    fn ctr(&self, synthetic_iv: &[u8; 16], input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts or decrypts the data in counter mode with the synthetic IV as the counter block.
+        //! The 31st and 63rd bits (from the right) are cleared, so the counter can't wrap around.
+
+        let mut counter_block: [u8; 16] = *synthetic_iv;
+        counter_block[8] &= 0x7f;
+        counter_block[12] &= 0x7f;
+
+        CTR::new(self.ctr_core, CounterLayout::Counter128, counter_block).encrypt(input)
+    }
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The maximum number of associated data components (S2V accepts at most 127 components, including the plaintext).
+const MAX_ASSOCIATED_DATA_COMPONENTS: usize = 126;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic() {
+        //! Test deterministic sealing and opening (RFC 5297, A.1)
+
+        let siv = SIV::new(SIVKey::AES128([
+            0xff, 0xfe, 0xfd, 0xfc, 0xfb, 0xfa, 0xf9, 0xf8, 0xf7, 0xf6, 0xf5, 0xf4, 0xf3, 0xf2, 0xf1, 0xf0,
+            0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+        ]));
+        let associated_data: [u8; 24] = [
+            0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d, 0x1e, 0x1f,
+            0x20, 0x21, 0x22, 0x23, 0x24, 0x25, 0x26, 0x27,
+        ];
+        let plaintext: [u8; 14] = [0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+        let sealed: [u8; 30] = [
+            0x85, 0x63, 0x2d, 0x07, 0xc6, 0xe8, 0xf3, 0x7f, 0x95, 0x0a, 0xcd, 0x32, 0x0a, 0x2e, 0xcc, 0x93,
+            0x40, 0xc0, 0x2b, 0x96, 0x90, 0xc4, 0xdc, 0x04, 0xda, 0xef, 0x7f, 0x6a, 0xfe, 0x5c,
+        ];
+
+        assert_eq!(siv.seal(&[&associated_data], &plaintext).unwrap(), sealed);
+        assert_eq!(siv.open(&[&associated_data], &sealed).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn implementation() {
+        //! Test that both AES cores use the chosen implementation, also after changing the key
+
+        let mut siv = SIV::new(SIVKey::AES128([0x12; 32]));
+        let expected: Vec<u8> = siv.seal(&[b"header"], b"secret message").unwrap();
+        assert_eq!(siv.implementation(), Implementation::Hardware);
+
+        siv.set_implementation(Implementation::Bitsliced);
+        assert_eq!(siv.implementation(), Implementation::Bitsliced);
+        assert_eq!(siv.mac_core.implementation(), Implementation::Bitsliced);
+        assert_eq!(siv.ctr_core.implementation(), Implementation::Bitsliced);
+        assert_eq!(siv.seal(&[b"header"], b"secret message").unwrap(), expected);
+
+        siv.set_key(SIVKey::AES256([0x48; 64]));
+        assert_eq!(siv.mac_core.implementation(), Implementation::Bitsliced);
+        assert_eq!(siv.ctr_core.implementation(), Implementation::Bitsliced);
+    }
+
+    #[test]
+    fn nonce_based() {
+        //! Test sealing and opening with several associated data components and a nonce (RFC 5297, A.2)
+
+        let siv = SIV::new(SIVKey::AES128([
+            0x7f, 0x7e, 0x7d, 0x7c, 0x7b, 0x7a, 0x79, 0x78, 0x77, 0x76, 0x75, 0x74, 0x73, 0x72, 0x71, 0x70,
+            0x40, 0x41, 0x42, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49, 0x4a, 0x4b, 0x4c, 0x4d, 0x4e, 0x4f,
+        ]));
+        let associated_data_1: [u8; 40] = [
+            0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x66, 0x77, 0x88, 0x99, 0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff,
+            0xde, 0xad, 0xda, 0xda, 0xde, 0xad, 0xda, 0xda, 0xff, 0xee, 0xdd, 0xcc, 0xbb, 0xaa, 0x99, 0x88,
+            0x77, 0x66, 0x55, 0x44, 0x33, 0x22, 0x11, 0x00,
+        ];
+        let associated_data_2: [u8; 10] = [0x10, 0x20, 0x30, 0x40, 0x50, 0x60, 0x70, 0x80, 0x90, 0xa0];
+        let nonce: [u8; 16] = [
+            0x09, 0xf9, 0x11, 0x02, 0x9d, 0x74, 0xe3, 0x5b, 0xd8, 0x41, 0x56, 0xc5, 0x63, 0x56, 0x88, 0xc0,
+        ];
+        let plaintext: &[u8] = b"this is some plaintext to encrypt using SIV-AES";
+        let sealed: [u8; 63] = [
+            0x7b, 0xdb, 0x6e, 0x3b, 0x43, 0x26, 0x67, 0xeb, 0x06, 0xf4, 0xd1, 0x4b, 0xff, 0x2f, 0xbd, 0x0f,
+            0xcb, 0x90, 0x0f, 0x2f, 0xdd, 0xbe, 0x40, 0x43, 0x26, 0x60, 0x19, 0x65, 0xc8, 0x89, 0xbf, 0x17,
+            0xdb, 0xa7, 0x7c, 0xeb, 0x09, 0x4f, 0xa6, 0x63, 0xb7, 0xa3, 0xf7, 0x48, 0xba, 0x8a, 0xf8, 0x29,
+            0xea, 0x64, 0xad, 0x54, 0x4a, 0x27, 0x2e, 0x9c, 0x48, 0x5b, 0x62, 0xa3, 0xfd, 0x5c, 0x0d,
+        ];
+        let associated_data: [&[u8]; 3] = [&associated_data_1, &associated_data_2, &nonce];
+
+        assert_eq!(siv.seal(&associated_data, plaintext).unwrap(), sealed);
+        assert_eq!(siv.open(&associated_data, &sealed).unwrap(), plaintext);
+
+        // the order of the components matters
+        let swapped: [&[u8]; 3] = [&associated_data_2, &associated_data_1, &nonce];
+        assert_eq!(siv.open(&swapped, &sealed), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn key_sizes() {
+        //! Test sealing and opening with all key sizes
+
+        let mut siv = SIV::new(SIVKey::AES192([0x24; 48]));
+        for key in [SIVKey::AES128([0x12; 32]), SIVKey::AES192([0x24; 48]), SIVKey::AES256([0x48; 64])] {
+            siv.set_key(key);
+            assert_eq!(siv.key(), key);
+
+            for length in [0, 1, 15, 16, 17, 40] {
+                let plaintext: Vec<u8> = (0..length).collect();
+                let sealed: Vec<u8> = siv.seal(&[b"header"], &plaintext).unwrap();
+                assert_eq!(sealed.len(), 16 + plaintext.len());
+                assert_eq!(siv.open(&[b"header"], &sealed).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn authentication() {
+        //! Test that any modification is detected
+
+        let siv = SIV::new(SIVKey::AES128([0x12; 32]));
+        let sealed: Vec<u8> = siv.seal(&[b"header"], b"authenticated").unwrap();
+
+        for i in 0..sealed.len() {
+            let mut modified: Vec<u8> = sealed.clone();
+            modified[i] ^= 0x01;
+            assert_eq!(siv.open(&[b"header"], &modified), Err(ModeError::AuthenticationFailed));
+        }
+        assert_eq!(siv.open(&[b"Header"], &sealed), Err(ModeError::AuthenticationFailed));
+        assert_eq!(siv.open(&[], &sealed), Err(ModeError::AuthenticationFailed));
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let siv = SIV::new(SIVKey::AES128([0x12; 32]));
+        let component: &[u8] = b"component";
+
+        assert!(siv.seal(&[component; 126], b"").is_ok());
+        assert_eq!(siv.seal(&[component; 127], b""), Err(ModeError::InvalidInputSize));
+        assert_eq!(siv.open(&[component; 127], &[0; 16]), Err(ModeError::InvalidInputSize));
+        assert_eq!(siv.open(&[], &[0; 15]), Err(ModeError::InvalidInputSize));
+    }
+}
@@ -0,0 +1,723 @@
+//! A module containing the streaming encryptor and decryptor of the modes of operation.
+//!
+//! Streaming is available for ECB, CBC, CTR, CFB and OFB. The other modes are out of scope:
+//! * GCM, CCM, EAX and OCB must verify the tag before any plaintext is released, so decrypting in parts would return unauthenticated data.
+//! * SIV and GCM-SIV derive the IV from the whole plaintext, and CCM needs the length of the data before the first block.
+//! * XTS and CBC-CS steal ciphertext from the last block of a data unit, which is meant to be processed at once (e.g. a disk sector).
+//! * KW and KWP wrap a single key, which is small enough to be processed at once.
+
+
+
+
+
+// DISABLED LINTS
+
+#![allow(clippy::upper_case_acronyms)]  // the names of the modes of operation
+#![allow(clippy::large_enum_variant)]  // the stream modes are kept inline rather than boxed
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::AESCore;
+use crate::padding::{
+    Padding,
+    PaddingTypes,
+};
+use super::{
+    BATCH_BLOCKS,
+    CTR,
+    ModeError,
+    OFB,
+    SegmentSize,
+    cbc,
+    xor_blocks,
+};
+
+
+
+
+
+// ENUMS
+
+/// The state of a mode of operation while the data is streamed through it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum StreamState {
+    /// A mode which processes whole (padded) blocks.
+    Block(BlockMode),
+    /// A mode which processes any number of bytes without padding.
+    Stream(StreamMode),
+}
+
+/// The state of a block mode, which is only used for whole blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum BlockMode {
+    /// ECB has no state between the blocks.
+    ECB,
+    /// CBC chains with the previous ciphertext block.
+    CBC {
+        /// The previous ciphertext block (the IV for the first block).
+        previous_block: [u8; 16],
+    },
+}
+
+/// The state of a stream mode, which is used for any number of bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum StreamMode {
+    /// CTR continues the keystream from the last used counter block.
+    CTR {
+        /// The CTR instance making the keystream.
+        ctr: CTR,
+        /// The number of keystream blocks made so far.
+        block_offset: u128,
+        /// The current keystream block.
+        keystream_block: [u8; 16],
+        /// The number of bytes of the current keystream block that were already used.
+        keystream_position: usize,
+    },
+    /// CFB shifts the ciphertext into the register.
+    CFB {
+        /// The segment size.
+        segment_size: SegmentSize,
+        /// The shift register.
+        register: [u8; 16],
+        /// The encryption of the register, used by `SegmentSize::CFB128`.
+        keystream_block: [u8; 16],
+        /// The number of bytes of the current segment that were already processed, used by `SegmentSize::CFB128`.
+        segment_position: usize,
+    },
+    /// OFB continues its own keystream.
+    OFB(OFB),
+}
+
+impl StreamState {
+    pub(super) fn ecb() -> Self {
+        //! Creates the state of the ECB mode.
+
+        Self::Block(BlockMode::ECB)
+    }
+
+    pub(super) fn cbc(iv: [u8; 16]) -> Self {
+        //! Creates the state of the CBC mode with the IV as the previous block.
+
+        Self::Block(BlockMode::CBC { previous_block: iv })
+    }
+
+    pub(super) fn ctr(ctr: CTR) -> Self {
+        //! Creates the state of the CTR mode at the start of the keystream.
+
+        Self::Stream(StreamMode::CTR {
+            ctr,
+            block_offset: 0,
+            keystream_block: [0; 16],
+            keystream_position: 16,
+        })
+    }
+
+    pub(super) fn cfb(segment_size: SegmentSize, iv: [u8; 16]) -> Self {
+        //! Creates the state of the CFB mode with the IV in the register.
+
+        Self::Stream(StreamMode::CFB {
+            segment_size,
+            register: iv,
+            keystream_block: [0; 16],
+            segment_position: 16,
+        })
+    }
+
+    pub(super) fn ofb(ofb: OFB) -> Self {
+        //! Creates the state of the OFB mode, continuing the keystream of the OFB instance.
+
+        Self::Stream(StreamMode::OFB(ofb))
+    }
+}
+
+impl BlockMode {
+    fn encrypt_blocks(&mut self, aes_core: &AESCore, buffer: &mut [u8]) {
+        //! Encrypts the next blocks in place, in batches where the mode allows it.
+        //! The buffer must be a multiple of 16 bytes long.
+
+        match self {
+            Self::ECB => aes_core.encrypt_blocks(buffer.as_chunks_mut().0),
+            Self::CBC { previous_block } => {
+                cbc::encrypt_blocks(aes_core, previous_block, buffer);
+                if let Some(ciphertext_block) = buffer.as_chunks::<16>().0.last() {
+                    *previous_block = *ciphertext_block;
+                }
+            }
+        }
+    }
+
+    fn decrypt_blocks(&mut self, aes_core: &AESCore, buffer: &mut [u8]) {
+        //! Decrypts the next blocks in place in batches.
+        //! The buffer must be a multiple of 16 bytes long.
+
+        match self {
+            Self::ECB => aes_core.decrypt_blocks(buffer.as_chunks_mut().0),
+            Self::CBC { previous_block } => {
+                let last_block: Option<[u8; 16]> = buffer.as_chunks::<16>().0.last().copied();
+                cbc::decrypt_blocks(aes_core, previous_block, buffer);
+                if let Some(ciphertext_block) = last_block {
+                    *previous_block = ciphertext_block;
+                }
+            }
+        }
+    }
+}
+
+impl StreamMode {
+    fn process_bytes(&mut self, aes_core: &AESCore, input: &[u8], output: &mut Vec<u8>, encrypt: bool) -> Result<(), ModeError> {
+        //! Encrypts or decrypts the bytes.
+        //! Nothing is added to the output and the state is left unchanged if an error is returned.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The CTR counter would wrap around.
+
+        let start: usize = output.len();
+        output.extend_from_slice(input);
+        let result: Result<(), ModeError> = self.process_buffer(aes_core, &mut output[start..], encrypt);
+        if result.is_err() {
+            output.truncate(start);
+        }
+
+        result
+    }
+
+    fn process_buffer(&mut self, aes_core: &AESCore, buffer: &mut [u8], encrypt: bool) -> Result<(), ModeError> {
+        //! Encrypts or decrypts the bytes in place.
+        //! The rest of the current keystream block is used first, then the full blocks, then the start of a new block.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The CTR counter would wrap around. The state is left unchanged.
+
+        match self {
+            Self::CTR { ctr, block_offset, keystream_block, keystream_position } => {
+                let (current, rest) = split_at_block_end(buffer, *keystream_position);
+                xor_keystream(current, &keystream_block[*keystream_position..]);
+                let mut new_position: usize = *keystream_position + current.len();
+
+                // the state is only changed once the whole buffer was processed, so the stream stays usable after an overflow
+                let (blocks, tail) = rest.split_at_mut(rest.len() / 16 * 16);
+                ctr.apply_keystream(*block_offset, blocks)?;
+                let mut new_offset: u128 = *block_offset + (blocks.len() / 16) as u128;
+                let mut new_block: [u8; 16] = *keystream_block;
+                if !tail.is_empty() {
+                    new_block = [0; 16];
+                    ctr.apply_keystream(new_offset, &mut new_block)?;
+                    xor_keystream(tail, &new_block);
+                    new_offset += 1;
+                    new_position = tail.len();
+                }
+
+                *block_offset = new_offset;
+                *keystream_block = new_block;
+                *keystream_position = new_position;
+            }
+            Self::CFB { segment_size: SegmentSize::CFB128, register, keystream_block, segment_position } => {
+                let (current, rest) = split_at_block_end(buffer, *segment_position);
+                shift_segment(current, register, keystream_block, *segment_position, encrypt);
+                *segment_position += current.len();
+
+                let (blocks, tail) = rest.as_chunks_mut::<16>();
+                if encrypt {
+                    for block in blocks.iter_mut() {
+                        xor_blocks(block, &aes_core.encrypt(register));
+                        *register = *block;
+                    }
+                } else {
+                    // the registers of the whole batch are known from the ciphertext, so they are encrypted together
+                    for batch in blocks.chunks_mut(BATCH_BLOCKS) {
+                        let mut keystream: [[u8; 16]; BATCH_BLOCKS] = [[0; 16]; BATCH_BLOCKS];
+                        keystream[0] = *register;
+                        keystream[1..batch.len()].copy_from_slice(&batch[..(batch.len() - 1)]);
+                        aes_core.encrypt_blocks(&mut keystream[..batch.len()]);
+
+                        *register = batch[batch.len() - 1];
+                        for (block, keystream_block) in batch.iter_mut().zip(keystream.iter()) {
+                            xor_blocks(block, keystream_block);
+                        }
+                    }
+                }
+
+                if !tail.is_empty() {
+                    *keystream_block = aes_core.encrypt(register);
+                    shift_segment(tail, register, keystream_block, 0, encrypt);
+                    *segment_position = tail.len();
+                }
+            }
+            Self::CFB { segment_size: SegmentSize::CFB8, register, .. } => {
+                for byte in buffer.iter_mut() {
+                    let input: u8 = *byte;
+                    *byte ^= aes_core.encrypt(register)[0];
+                    register.copy_within(1.., 0);
+                    register[15] = if encrypt { *byte } else { input };
+                }
+            }
+            Self::CFB { segment_size: SegmentSize::CFB1, register, .. } => {
+                for byte in buffer.iter_mut() {
+                    let mut output: u8 = 0;
+                    for i in (0..8).rev() {
+                        let input_bit: u8 = (*byte >> i) & 1;
+                        let output_bit: u8 = input_bit ^ (aes_core.encrypt(register)[0] >> 7);
+                        output |= output_bit << i;
+
+                        let ciphertext_bit: u8 = if encrypt { output_bit } else { input_bit };
+                        for j in 0..15 {
+                            register[j] = (register[j] << 1) | (register[j + 1] >> 7);
+                        }
+                        register[15] = (register[15] << 1) | ciphertext_bit;
+                    }
+                    *byte = output;
+                }
+            }
+            Self::OFB(ofb) => ofb.apply_keystream(buffer),
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// STRUCTS
+
+/// The streaming encryptor of a mode of operation, created with the `encryptor` function of the mode.
+/// The plaintext is given in parts of any length with `update`, and the last (padded) block is written by `finalize`.
+/// Only as much data as fits into a single block is kept between the calls, so data of any size can be encrypted.
+///
+/// The output is the same as the output of the `encrypt` function of the mode for the whole data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Encryptor {
+    /// The AES core used to encrypt the blocks.
+    aes_core: AESCore,
+    /// The padding applied to the last block by the block modes.
+    padding: Padding,
+    /// The state of the mode.
+    state: StreamState,
+    /// The data which doesn't fill a block yet.
+    buffer: [u8; 16],
+    /// The number of bytes in the buffer.
+    buffer_length: usize,
+}
+
+/// Public functions for encrypting data in parts.
+impl Encryptor {
+    pub(super) fn new(aes_core: AESCore, padding: Padding, state: StreamState) -> Self {
+        //! Creates a new encryptor at the start of the data.
+
+        Self {
+            aes_core,
+            padding,
+            state,
+            buffer: [0; 16],
+            buffer_length: 0,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), ModeError> {
+        //! Encrypts the next part of the plaintext.
+        //! The block modes (ECB and CBC) keep a partial block until the next call.
+        //! # Arguments
+        //! * `input` - The next part of the plaintext, of any length.
+        //! * `output` - The vector to which the ciphertext is appended.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The CTR counter would wrap around. Nothing is appended to the output and the state is unchanged.
+
+        let block_mode: &mut BlockMode = match &mut self.state {
+            StreamState::Block(block_mode) => block_mode,
+            StreamState::Stream(stream_mode) => return stream_mode.process_bytes(&self.aes_core, input, output, true),
+        };
+
+        // the buffered partial block is completed first
+        let length: usize = input.len().min(16 - self.buffer_length);
+        self.buffer[self.buffer_length..(self.buffer_length + length)].copy_from_slice(&input[..length]);
+        self.buffer_length += length;
+        if self.buffer_length < 16 {
+            return Ok(());
+        }
+
+        // the completed block and the whole blocks of the input are encrypted together, the rest is kept
+        let (blocks, rest) = input[length..].split_at((input.len() - length) / 16 * 16);
+        let start: usize = output.len();
+        output.extend_from_slice(&self.buffer);
+        output.extend_from_slice(blocks);
+        block_mode.encrypt_blocks(&self.aes_core, &mut output[start..]);
+
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_length = rest.len();
+
+        Ok(())
+    }
+
+    pub fn finalize(mut self, output: &mut Vec<u8>) -> Result<(), ModeError> {
+        //! Finishes the encryption, appending the padded last block for the block modes.
+        //! # Arguments
+        //! * `output` - The vector to which the rest of the ciphertext is appended.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - No padding is used and the plaintext isn't a multiple of 16 bytes long.
+
+        let StreamState::Block(block_mode) = &mut self.state else {
+            return Ok(());
+        };
+
+        if self.padding.padding_type() == PaddingTypes::None {
+            return match self.buffer_length {
+                0 => Ok(()),
+                _ => Err(ModeError::InvalidInputSize),
+            };
+        }
+
+        let mut block: [u8; 16] = self.padding.pad(&self.buffer[..self.buffer_length])?;
+        block_mode.encrypt_blocks(&self.aes_core, &mut block);
+        output.extend_from_slice(&block);
+
+        Ok(())
+    }
+}
+
+/// The streaming decryptor of a mode of operation, created with the `decryptor` function of the mode.
+/// The ciphertext is given in parts of any length with `update`, and the last (unpadded) block is written by `finalize`.
+/// The block modes (ECB and CBC) hold back the last complete block until more data follows it, so the padding can be removed by `finalize`.
+///
+/// The output is the same as the output of the `decrypt` function of the mode for the whole data.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Decryptor {
+    /// The AES core used to decrypt the blocks.
+    aes_core: AESCore,
+    /// The padding removed from the last block by the block modes.
+    padding: Padding,
+    /// The state of the mode.
+    state: StreamState,
+    /// The data which isn't decrypted yet, a partial block or the block which could be the last one.
+    buffer: [u8; 16],
+    /// The number of bytes in the buffer.
+    buffer_length: usize,
+}
+
+/// Public functions for decrypting data in parts.
+impl Decryptor {
+    pub(super) fn new(aes_core: AESCore, padding: Padding, state: StreamState) -> Self {
+        //! Creates a new decryptor at the start of the data.
+
+        Self {
+            aes_core,
+            padding,
+            state,
+            buffer: [0; 16],
+            buffer_length: 0,
+        }
+    }
+
+    pub fn update(&mut self, input: &[u8], output: &mut Vec<u8>) -> Result<(), ModeError> {
+        //! Decrypts the next part of the ciphertext.
+        //! The block modes (ECB and CBC) keep the last block, complete or not, until the next call.
+        //! # Arguments
+        //! * `input` - The next part of the ciphertext, of any length.
+        //! * `output` - The vector to which the plaintext is appended.
+        //! # Errors
+        //! * ModeError::CounterOverflow - The CTR counter would wrap around. Nothing is appended to the output and the state is unchanged.
+
+        let block_mode: &mut BlockMode = match &mut self.state {
+            StreamState::Block(block_mode) => block_mode,
+            StreamState::Stream(stream_mode) => return stream_mode.process_bytes(&self.aes_core, input, output, false),
+        };
+
+        // the buffered block is completed first, and only decrypted once more data follows it
+        let length: usize = input.len().min(16 - self.buffer_length);
+        self.buffer[self.buffer_length..(self.buffer_length + length)].copy_from_slice(&input[..length]);
+        self.buffer_length += length;
+        if length == input.len() {
+            return Ok(());
+        }
+
+        // the buffered block and the whole blocks of the input are decrypted together,
+        // except for the last 1 to 16 bytes, which are kept as they could end with the padded block
+        let (blocks, rest) = input[length..].split_at((input.len() - length - 1) / 16 * 16);
+        let start: usize = output.len();
+        output.extend_from_slice(&self.buffer);
+        output.extend_from_slice(blocks);
+        block_mode.decrypt_blocks(&self.aes_core, &mut output[start..]);
+
+        self.buffer[..rest.len()].copy_from_slice(rest);
+        self.buffer_length = rest.len();
+
+        Ok(())
+    }
+
+    pub fn finalize(mut self, output: &mut Vec<u8>) -> Result<(), ModeError> {
+        //! Finishes the decryption, appending the last block without the padding for the block modes.
+        //! # Arguments
+        //! * `output` - The vector to which the rest of the plaintext is appended.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The ciphertext isn't a multiple of 16 bytes long, or is empty while padding is used.
+        //! * ModeError::Padding - The padding of the last block is invalid.
+
+        let StreamState::Block(block_mode) = &mut self.state else {
+            return Ok(());
+        };
+
+        let no_padding: bool = self.padding.padding_type() == PaddingTypes::None;
+        if no_padding && self.buffer_length == 0 {
+            return Ok(());
+        }
+        if self.buffer_length != 16 {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let mut block: [u8; 16] = self.buffer;
+        block_mode.decrypt_blocks(&self.aes_core, &mut block);
+        if no_padding {
+            output.extend_from_slice(&block);
+        } else {
+            output.extend_from_slice(self.padding.de_pad(&block)?);
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn split_at_block_end(buffer: &mut [u8], position: usize) -> (&mut [u8], &mut [u8]) {
+    //! Splits the buffer into the bytes which fit into the rest of the current block and the bytes after them.
+    //! # Arguments
+    //! * `buffer` - The buffer to be split.
+    //! * `position` - The number of bytes of the current block that were already used.
+
+    let length: usize = buffer.len().min(16 - position);
+    buffer.split_at_mut(length)
+}
+
+fn xor_keystream(buffer: &mut [u8], keystream: &[u8]) {
+    //! XORs the start of the keystream into the buffer, which mustn't be longer than the keystream.
+
+    for (byte, keystream_byte) in buffer.iter_mut().zip(keystream.iter()) {
+        *byte ^= keystream_byte;
+    }
+}
+
+fn shift_segment(buffer: &mut [u8], register: &mut [u8; 16], keystream_block: &[u8; 16], position: usize, encrypt: bool) {
+    //! Encrypts or decrypts the bytes of a CFB128 segment in place, starting at the given position of the segment.
+    //! The register was already encrypted into the keystream block, so its bytes are replaced by the ciphertext one by one.
+
+    for (i, byte) in buffer.iter_mut().enumerate() {
+        let input: u8 = *byte;
+        *byte ^= keystream_block[position + i];
+        register[position + i] = if encrypt { *byte } else { input };
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::AESKey;
+    use crate::modes::{
+        CBC,
+        CFB,
+        CounterLayout,
+        ECB,
+    };
+
+    const KEY: [u8; 16] = [
+        0x2b, 0x7e, 0x15, 0x16,
+        0x28, 0xae, 0xd2, 0xa6,
+        0xab, 0xf7, 0x15, 0x88,
+        0x09, 0xcf, 0x4f, 0x3c,
+    ];
+
+    const IV: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03,
+        0x04, 0x05, 0x06, 0x07,
+        0x08, 0x09, 0x0a, 0x0b,
+        0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    /// The sizes of the parts the data is split into, repeated until the data runs out.
+    const PART_SIZES: [usize; 6] = [1, 0, 15, 16, 33, 7];
+
+    fn encrypt_in_parts(mut encryptor: Encryptor, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the input split into parts of different sizes.
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut input: &[u8] = input;
+        for part_size in PART_SIZES.iter().cycle() {
+            let (part, rest) = input.split_at((*part_size).min(input.len()));
+            encryptor.update(part, &mut output)?;
+            input = rest;
+            if input.is_empty() {
+                break;
+            }
+        }
+        encryptor.finalize(&mut output)?;
+
+        Ok(output)
+    }
+
+    fn decrypt_in_parts(mut decryptor: Decryptor, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the input split into parts of different sizes.
+
+        let mut output: Vec<u8> = Vec::new();
+        let mut input: &[u8] = input;
+        for part_size in PART_SIZES.iter().cycle() {
+            let (part, rest) = input.split_at((*part_size).min(input.len()));
+            decryptor.update(part, &mut output)?;
+            input = rest;
+            if input.is_empty() {
+                break;
+            }
+        }
+        decryptor.finalize(&mut output)?;
+
+        Ok(output)
+    }
+
+    #[test]
+    fn block_modes() {
+        //! Test that ECB and CBC give the same output as the one-shot functions for every padding and input length
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        for padding_type in [PaddingTypes::PKCS7, PaddingTypes::ISO78164, PaddingTypes::X923, PaddingTypes::None] {
+            let padding = Padding::new(padding_type);
+            let ecb = ECB::new(aes_core, padding);
+            let cbc = CBC::new(aes_core, padding, IV);
+
+            for length in 0..100 {
+                if padding_type == PaddingTypes::None && length % 16 != 0 {
+                    assert_eq!(encrypt_in_parts(ecb.encryptor(), &vec![0; length]), Err(ModeError::InvalidInputSize));
+                    assert_eq!(decrypt_in_parts(cbc.decryptor(), &vec![0; length]), Err(ModeError::InvalidInputSize));
+                    continue;
+                }
+
+                let plaintext: Vec<u8> = (0..length as u8).collect();
+
+                let ciphertext: Vec<u8> = encrypt_in_parts(ecb.encryptor(), &plaintext).unwrap();
+                assert_eq!(ciphertext, ecb.encrypt(&plaintext).unwrap());
+                assert_eq!(decrypt_in_parts(ecb.decryptor(), &ciphertext).unwrap(), plaintext);
+
+                let ciphertext: Vec<u8> = encrypt_in_parts(cbc.encryptor(), &plaintext).unwrap();
+                assert_eq!(ciphertext, cbc.encrypt(&plaintext).unwrap());
+                assert_eq!(decrypt_in_parts(cbc.decryptor(), &ciphertext).unwrap(), plaintext);
+            }
+        }
+    }
+
+    #[test]
+    fn stream_modes() {
+        //! Test that CTR, CFB and OFB give the same output as the one-shot functions
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        let plaintext: Vec<u8> = (0..100).collect();
+
+        let ctr = CTR::new(aes_core, CounterLayout::Counter128, IV);
+        let ciphertext: Vec<u8> = encrypt_in_parts(ctr.encryptor(), &plaintext).unwrap();
+        assert_eq!(ciphertext, ctr.encrypt(&plaintext).unwrap());
+        assert_eq!(decrypt_in_parts(ctr.decryptor(), &ciphertext).unwrap(), plaintext);
+
+        for segment_size in [SegmentSize::CFB1, SegmentSize::CFB8, SegmentSize::CFB128] {
+            let cfb = CFB::new(aes_core, segment_size, IV);
+            let ciphertext: Vec<u8> = encrypt_in_parts(cfb.encryptor(), &plaintext).unwrap();
+            assert_eq!(ciphertext, cfb.encrypt(&plaintext));
+            assert_eq!(decrypt_in_parts(cfb.decryptor(), &ciphertext).unwrap(), plaintext);
+        }
+
+        let ofb = OFB::new(aes_core, IV);
+        let ciphertext: Vec<u8> = encrypt_in_parts(ofb.encryptor(), &plaintext).unwrap();
+        assert_eq!(ciphertext, ofb.encrypt(&plaintext));
+        assert_eq!(decrypt_in_parts(ofb.decryptor(), &ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn long_parts() {
+        //! Test that the modes give the same output as the one-shot functions for parts of many blocks
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        let plaintext: Vec<u8> = (0..300).map(|i| i as u8).collect();
+
+        let ctr = CTR::new(aes_core, CounterLayout::Counter128, IV);
+        let cfb = CFB::new(aes_core, SegmentSize::CFB128, IV);
+        let ofb = OFB::new(aes_core, IV);
+        for (mut encryptor, mut decryptor, expected) in [
+            (ctr.encryptor(), ctr.decryptor(), ctr.encrypt(&plaintext).unwrap()),
+            (cfb.encryptor(), cfb.decryptor(), cfb.encrypt(&plaintext)),
+            (ofb.encryptor(), ofb.decryptor(), ofb.encrypt(&plaintext)),
+        ] {
+            let mut ciphertext: Vec<u8> = Vec::new();
+            encryptor.update(&plaintext[..5], &mut ciphertext).unwrap();
+            encryptor.update(&plaintext[5..], &mut ciphertext).unwrap();
+            assert_eq!(ciphertext, expected);
+
+            let mut decrypted: Vec<u8> = Vec::new();
+            decryptor.update(&ciphertext[..5], &mut decrypted).unwrap();
+            decryptor.update(&ciphertext[5..], &mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+
+        // the block modes process the whole blocks of a part in batches
+        let padding = Padding::new(PaddingTypes::PKCS7);
+        let ecb = ECB::new(aes_core, padding);
+        let cbc = CBC::new(aes_core, padding, IV);
+        for (mut encryptor, mut decryptor, expected) in [
+            (ecb.encryptor(), ecb.decryptor(), ecb.encrypt(&plaintext).unwrap()),
+            (cbc.encryptor(), cbc.decryptor(), cbc.encrypt(&plaintext).unwrap()),
+        ] {
+            let mut ciphertext: Vec<u8> = Vec::new();
+            encryptor.update(&plaintext[..5], &mut ciphertext).unwrap();
+            encryptor.update(&plaintext[5..], &mut ciphertext).unwrap();
+            encryptor.finalize(&mut ciphertext).unwrap();
+            assert_eq!(ciphertext, expected);
+
+            let mut decrypted: Vec<u8> = Vec::new();
+            decryptor.update(&ciphertext[..5], &mut decrypted).unwrap();
+            decryptor.update(&ciphertext[5..], &mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext[..288]);
+            decryptor.finalize(&mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid input
+
+        let aes_core = AESCore::new(AESKey::AES128(KEY));
+        let cbc = CBC::new(aes_core, Padding::new(PaddingTypes::PKCS7), IV);
+
+        // an empty or truncated ciphertext
+        assert_eq!(decrypt_in_parts(cbc.decryptor(), &[]), Err(ModeError::InvalidInputSize));
+        let ciphertext: Vec<u8> = cbc.encrypt(b"streaming").unwrap();
+        assert_eq!(decrypt_in_parts(cbc.decryptor(), &ciphertext[..15]), Err(ModeError::InvalidInputSize));
+
+        // a modified first block flips the first byte of the padding in the second block
+        let mut modified: Vec<u8> = cbc.encrypt(&[0; 16]).unwrap();
+        modified[0] ^= 0x01;
+        assert!(matches!(decrypt_in_parts(cbc.decryptor(), &modified), Err(ModeError::Padding(_))));
+
+        // the counter overflows after the last counter block
+        let ctr = CTR::new(aes_core, CounterLayout::Nonce96Counter32, [0xff; 16]);
+        let mut encryptor: Encryptor = ctr.encryptor();
+        let mut output: Vec<u8> = Vec::new();
+        encryptor.update(&[0; 16], &mut output).unwrap();
+        assert_eq!(encryptor.update(&[0; 2], &mut output), Err(ModeError::CounterOverflow));
+        assert_eq!(output, ctr.encrypt(&[0; 16]).unwrap());
+
+        // the state is unchanged by the overflow, so the rest of the last keystream block can still be used
+        let mut encryptor: Encryptor = ctr.encryptor();
+        let mut output: Vec<u8> = Vec::new();
+        encryptor.update(&[0; 10], &mut output).unwrap();
+        assert_eq!(encryptor.update(&[0; 10], &mut output), Err(ModeError::CounterOverflow));
+        encryptor.update(&[0; 6], &mut output).unwrap();
+        assert_eq!(output, ctr.encrypt(&[0; 16]).unwrap());
+    }
+}
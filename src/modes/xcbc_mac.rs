@@ -0,0 +1,216 @@
+//! A module containing the AES-XCBC-MAC message authentication code.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::{
+    AESCore,
+    AESKey,
+};
+use super::{
+    MacChain,
+    ModeError,
+    constant_time_eq,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The AES-XCBC-MAC message authentication code, as defined in RFC 3566 for IPsec.
+/// Three keys are derived from the key: the first is used for the CBC chaining, and the other two are XORed with a complete or a padded last block.
+///
+/// Only 128-bit keys are allowed by RFC 3566.
+/// IPsec uses the tag truncated to 96 bits (AES-XCBC-MAC-96), which is returned by `finalize_96`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct XCBCMAC {
+    /// The AES core made from the key, used for deriving the other keys.
+    aes_core: AESCore,
+    /// The AES core made from the first derived key (K1), used for the CBC chaining.
+    chain_core: AESCore,
+    /// The second derived key (K2), XORed with a complete last block.
+    second_key: [u8; 16],
+    /// The third derived key (K3), XORed with a padded last block.
+    third_key: [u8; 16],
+    /// The CBC chaining of the data given so far.
+    chain: MacChain,
+}
+
+/// Public functions for computing and verifying tags.
+impl XCBCMAC {
+    pub fn new(aes_core: AESCore) -> Result<Self, ModeError> {
+        //! Creates a new XCBC-MAC instance.
+        //! # Arguments
+        //! * `aes_core` - The AES core made from the 128-bit key.
+        //! # Returns
+        //! * Result<XCBCMAC, ModeError> - The XCBC-MAC instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - The key isn't 128 bits long.
+
+        if !matches!(aes_core.key(), AESKey::AES128(_)) {
+            return Err(ModeError::InvalidKeySize);
+        }
+
+        let mut chain_core: AESCore = AESCore::new(AESKey::AES128(aes_core.encrypt(&[0x01; 16])));
+        chain_core.set_implementation(aes_core.implementation());
+
+        Ok(Self {
+            aes_core,
+            chain_core,
+            second_key: aes_core.encrypt(&[0x02; 16]),
+            third_key: aes_core.encrypt(&[0x03; 16]),
+            chain: MacChain::new(),
+        })
+    }
+
+    pub fn aes_core(&self) -> AESCore {
+        //! Returns the AES core made from the key of this XCBC-MAC instance.
+
+        self.aes_core
+    }
+
+    pub fn set_aes_core(&mut self, aes_core: AESCore) -> Result<(), ModeError> {
+        //! Changes the AES core used by this XCBC-MAC instance.
+        //! The data given so far is discarded.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - The key isn't 128 bits long.
+
+        *self = Self::new(aes_core)?;
+
+        Ok(())
+    }
+
+    pub fn reset(&mut self) {
+        //! Discards the data given so far, so a new tag can be computed with the same key.
+
+        self.chain = MacChain::new();
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        //! Adds the next part of the data.
+        //! # Arguments
+        //! * `data` - The next part of the data, of any length.
+
+        self.chain.update(&self.chain_core, data);
+    }
+
+    pub fn finalize(&self) -> [u8; 16] {
+        //! Returns the full 16-byte tag of the data given so far.
+
+        self.chain.finalize(&self.chain_core, &self.second_key, &self.third_key)
+    }
+
+    pub fn finalize_96(&self) -> [u8; 12] {
+        //! Returns the tag of the data given so far, truncated to 96 bits as in AES-XCBC-MAC-96.
+
+        self.finalize()[..12].try_into().unwrap()
+    }
+
+    pub fn verify(&self, tag: &[u8]) -> Result<(), ModeError> {
+        //! Verifies the given full or 96-bit tag against the data given so far, in constant time.
+        //! # Arguments
+        //! * `tag` - The tag, 16 or 12 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidTagLength - The tag isn't 16 or 12 bytes long.
+        //! * ModeError::AuthenticationFailed - The tag doesn't match the data.
+
+        if tag.len() != 16 && tag.len() != 12 {
+            return Err(ModeError::InvalidTagLength);
+        }
+
+        if !constant_time_eq(&self.finalize()[..tag.len()], tag) {
+            return Err(ModeError::AuthenticationFailed);
+        }
+
+        Ok(())
+    }
+}
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aes_core::Implementation;
+
+    const KEY: [u8; 16] = [
+        0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+    ];
+
+    const TAGS: [(usize, [u8; 16]); 6] = [
+        (0, [0x75, 0xf0, 0x25, 0x1d, 0x52, 0x8a, 0xc0, 0x1c, 0x45, 0x73, 0xdf, 0xd5, 0x84, 0xd7, 0x9f, 0x29]),
+        (3, [0x5b, 0x37, 0x65, 0x80, 0xae, 0x2f, 0x19, 0xaf, 0xe7, 0x21, 0x9c, 0xee, 0xf1, 0x72, 0x75, 0x6f]),
+        (16, [0xd2, 0xa2, 0x46, 0xfa, 0x34, 0x9b, 0x68, 0xa7, 0x99, 0x98, 0xa4, 0x39, 0x4f, 0xf7, 0xa2, 0x63]),
+        (20, [0x47, 0xf5, 0x1b, 0x45, 0x64, 0x96, 0x62, 0x15, 0xb8, 0x98, 0x5c, 0x63, 0x05, 0x5e, 0xd3, 0x08]),
+        (32, [0xf5, 0x4f, 0x0e, 0xc8, 0xd2, 0xb9, 0xf3, 0xd3, 0x68, 0x07, 0x73, 0x4b, 0xd5, 0x28, 0x3f, 0xd4]),
+        (34, [0xbe, 0xcb, 0xb3, 0xbc, 0xcd, 0xb5, 0x18, 0xa3, 0x06, 0x77, 0xd5, 0x48, 0x1f, 0xb6, 0xb4, 0xd8]),
+    ];
+
+    #[test]
+    fn finalize() {
+        //! Test the tags of the test cases with the incrementing message (RFC 3566, section 4.6)
+
+        let message: Vec<u8> = (0..34).collect();
+        for (length, tag) in TAGS {
+            let mut xcbc_mac = XCBCMAC::new(AESCore::new(AESKey::AES128(KEY))).unwrap();
+            xcbc_mac.update(&message[..length]);
+            assert_eq!(xcbc_mac.finalize(), tag);
+            assert_eq!(xcbc_mac.finalize_96(), tag[..12]);
+            assert!(xcbc_mac.verify(&tag).is_ok());
+            assert!(xcbc_mac.verify(&tag[..12]).is_ok());
+        }
+    }
+
+    #[test]
+    fn update() {
+        //! Test a long message given in parts (RFC 3566, section 4.6, test case 7)
+
+        let mut xcbc_mac = XCBCMAC::new(AESCore::new(AESKey::AES128(KEY))).unwrap();
+        for _ in 0..10 {
+            xcbc_mac.update(&[0; 100]);
+        }
+        assert_eq!(
+            xcbc_mac.finalize(),
+            [0xf0, 0xda, 0xfe, 0xe8, 0x95, 0xdb, 0x30, 0x25, 0x37, 0x61, 0x10, 0x3b, 0x5d, 0x84, 0x52, 0x8f],
+        );
+
+        xcbc_mac.reset();
+        assert_eq!(xcbc_mac.finalize(), TAGS[0].1);
+    }
+
+    #[test]
+    fn implementation() {
+        //! Test that the core made from the first derived key keeps the implementation of the AES core
+
+        let mut aes_core: AESCore = AESCore::new(AESKey::AES128(KEY));
+        aes_core.set_implementation(Implementation::Bitsliced);
+
+        let mut xcbc_mac = XCBCMAC::new(aes_core).unwrap();
+        assert_eq!(xcbc_mac.chain_core.implementation(), Implementation::Bitsliced);
+        xcbc_mac.update(&(0..34).collect::<Vec<u8>>());
+        assert_eq!(xcbc_mac.finalize(), TAGS[5].1);
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid keys and tags
+
+        assert_eq!(XCBCMAC::new(AESCore::new(AESKey::AES256([0; 32]))), Err(ModeError::InvalidKeySize));
+
+        let mut xcbc_mac = XCBCMAC::new(AESCore::new(AESKey::AES128(KEY))).unwrap();
+        assert_eq!(xcbc_mac.set_aes_core(AESCore::new(AESKey::AES192([0; 24]))), Err(ModeError::InvalidKeySize));
+        assert_eq!(xcbc_mac.aes_core(), AESCore::new(AESKey::AES128(KEY)));
+
+        assert_eq!(xcbc_mac.verify(&TAGS[0].1[..8]), Err(ModeError::InvalidTagLength));
+        assert_eq!(xcbc_mac.verify(&TAGS[1].1[..12]), Err(ModeError::AuthenticationFailed));
+    }
+}
@@ -0,0 +1,354 @@
+//! A module containing the XTS-AES mode for encrypting sectors of storage devices.
+
+
+
+
+
+// IMPORTS
+
+use crate::aes_core::{
+    AESCore,
+    AESKey,
+};
+use super::{
+    ModeError,
+    Parallel,
+    xor_blocks,
+};
+
+
+
+
+
+// STRUCTS
+
+/// The XTS-AES tweakable block cipher mode, as defined in IEEE Std 1619 and NIST SP 800-38E.
+/// Each sector (data unit) is encrypted with a tweak derived from its sector number, so equal sectors at different positions encrypt differently.
+/// A sector which isn't a multiple of 16 bytes long is handled with ciphertext stealing, so the ciphertext is as long as the plaintext.
+///
+/// Two AES cores with different keys of the same size are used: one for the data and one for the tweak.
+/// Only 128-bit and 256-bit keys are supported, which gives XTS-AES-128 and XTS-AES-256.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct XTS {
+    /// The AES core used to encrypt the data (Key1).
+    data_core: AESCore,
+    /// The AES core used to encrypt the tweak (Key2).
+    tweak_core: AESCore,
+}
+
+/// Public functions for encrypting and decrypting sectors.
+impl XTS {
+    pub fn new(data_core: AESCore, tweak_core: AESCore) -> Result<Self, ModeError> {
+        //! Creates a new XTS instance.
+        //! # Arguments
+        //! * `data_core` - The AES core used to encrypt the data (Key1).
+        //! * `tweak_core` - The AES core used to encrypt the tweak (Key2).
+        //! # Returns
+        //! * Result<XTS, ModeError> - The XTS instance or an error.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - A key is 192 bits long, or the keys have different sizes.
+        //! * ModeError::IdenticalKeys - The two keys are equal.
+
+        Self::check_keys(&data_core, &tweak_core)?;
+
+        Ok(Self {
+            data_core,
+            tweak_core,
+        })
+    }
+
+    pub fn data_core(&self) -> AESCore {
+        //! Returns the AES core used to encrypt the data.
+
+        self.data_core
+    }
+
+    pub fn tweak_core(&self) -> AESCore {
+        //! Returns the AES core used to encrypt the tweak.
+
+        self.tweak_core
+    }
+
+    pub fn set_aes_cores(&mut self, data_core: AESCore, tweak_core: AESCore) -> Result<(), ModeError> {
+        //! Changes both AES cores used by this XTS instance.
+        //! # Errors
+        //! * ModeError::InvalidKeySize - A key is 192 bits long, or the keys have different sizes.
+        //! * ModeError::IdenticalKeys - The two keys are equal.
+
+        Self::check_keys(&data_core, &tweak_core)?;
+        self.data_core = data_core;
+        self.tweak_core = tweak_core;
+
+        Ok(())
+    }
+
+    pub fn encrypt(&self, sector_number: u128, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts the given sector.
+        //! # Arguments
+        //! * `sector_number` - The number of the sector, used as the tweak.
+        //! * `input` - The plaintext of the sector, between 16 bytes and 2^24 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector is shorter than 16 bytes or longer than 2^20 blocks.
+
+        let mut output: Vec<u8> = input.to_vec();
+        self.encrypt_sector(sector_number, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn decrypt(&self, sector_number: u128, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts the given sector.
+        //! # Arguments
+        //! * `sector_number` - The number of the sector, used as the tweak.
+        //! * `input` - The ciphertext of the sector, between 16 bytes and 2^24 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector is shorter than 16 bytes or longer than 2^20 blocks.
+
+        let mut output: Vec<u8> = input.to_vec();
+        self.decrypt_sector(sector_number, &mut output)?;
+
+        Ok(output)
+    }
+
+    pub fn encrypt_sector(&self, sector_number: u128, sector: &mut [u8]) -> Result<(), ModeError> {
+        //! Encrypts the given sector in place.
+        //! # Arguments
+        //! * `sector_number` - The number of the sector, used as the tweak.
+        //! * `sector` - The plaintext of the sector, between 16 bytes and 2^24 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector is shorter than 16 bytes or longer than 2^20 blocks.
+
+        self.process(sector_number, sector, true)
+    }
+
+    pub fn decrypt_sector(&self, sector_number: u128, sector: &mut [u8]) -> Result<(), ModeError> {
+        //! Decrypts the given sector in place.
+        //! # Arguments
+        //! * `sector_number` - The number of the sector, used as the tweak.
+        //! * `sector` - The ciphertext of the sector, between 16 bytes and 2^24 bytes long.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector is shorter than 16 bytes or longer than 2^20 blocks.
+
+        self.process(sector_number, sector, false)
+    }
+
+    pub fn encrypt_sectors_parallel(&self, parallel: &Parallel, first_sector_number: u128, sector_size: usize, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts consecutive sectors, splitting them into chunks of whole sectors which are encrypted in parallel.
+        //! The output is the same as encrypting every sector with `encrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct. A chunk holds at least one sector.
+        //! * `first_sector_number` - The number of the first sector. The following sectors have the following numbers.
+        //! * `sector_size` - The length of a sector, between 16 bytes and 2^24 bytes.
+        //! * `input` - The plaintext of the sectors. The last sector may be shorter, but must be at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The ciphertext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector size or the length of the last sector isn't valid.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        self.process_sectors_parallel(parallel, first_sector_number, sector_size, input, true)
+    }
+
+    pub fn decrypt_sectors_parallel(&self, parallel: &Parallel, first_sector_number: u128, sector_size: usize, input: &[u8]) -> Result<Vec<u8>, ModeError> {
+        //! Decrypts consecutive sectors, splitting them into chunks of whole sectors which are decrypted in parallel.
+        //! The output is the same as decrypting every sector with `decrypt`.
+        //! # Arguments
+        //! * `parallel` - The thread pool and the chunk size, see the `Parallel` struct. A chunk holds at least one sector.
+        //! * `first_sector_number` - The number of the first sector. The following sectors have the following numbers.
+        //! * `sector_size` - The length of a sector, between 16 bytes and 2^24 bytes.
+        //! * `input` - The ciphertext of the sectors. The last sector may be shorter, but must be at least 16 bytes long.
+        //! # Returns
+        //! * Result<Vec<u8>, ModeError> - The plaintext or an error.
+        //! # Errors
+        //! * ModeError::InvalidInputSize - The sector size or the length of the last sector isn't valid.
+        //! * ModeError::ThreadPool - The thread pool has no threads.
+
+        self.process_sectors_parallel(parallel, first_sector_number, sector_size, input, false)
+    }
+}
+
+/// Functions used in the XTS algorithm.
+impl XTS {
+    fn check_keys(data_core: &AESCore, tweak_core: &AESCore) -> Result<(), ModeError> {
+        //! Checks that the keys have the same supported size and that they differ.
+
+        match (data_core.key(), tweak_core.key()) {
+            (AESKey::AES128(_), AESKey::AES128(_)) | (AESKey::AES256(_), AESKey::AES256(_)) => {}
+            _ => return Err(ModeError::InvalidKeySize),
+        }
+        if data_core.key() == tweak_core.key() {
+            return Err(ModeError::IdenticalKeys);
+        }
+
+        Ok(())
+    }
+
+    fn process(&self, sector_number: u128, sector: &mut [u8], encrypt: bool) -> Result<(), ModeError> {
+        //! Encrypts or decrypts the sector in place, stealing ciphertext for a partial last block.
+
+        if sector.len() < 16 || sector.len() > MAX_SECTOR_LENGTH {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let mut tweak: [u8; 16] = self.tweak_core.encrypt(&sector_number.to_le_bytes());
+        let remainder: usize = sector.len() % 16;
+
+        // with ciphertext stealing, the last full block is handled together with the partial block
+        let full_blocks: usize = sector.len() / 16 - if remainder == 0 { 0 } else { 1 };
+        for block in sector[..(full_blocks * 16)].chunks_exact_mut(16) {
+            let block: &mut [u8; 16] = block.try_into().unwrap();
+            self.process_block(&tweak, block, encrypt);
+            tweak = multiply_by_alpha(&tweak);
+        }
+
+        if remainder != 0 {
+            // the last full block uses the later tweak when decrypting, because it was encrypted last
+            let next_tweak: [u8; 16] = multiply_by_alpha(&tweak);
+            let (first_tweak, second_tweak) = if encrypt { (tweak, next_tweak) } else { (next_tweak, tweak) };
+
+            let (last_full_block, partial_block) = sector[(full_blocks * 16)..].split_at_mut(16);
+            let last_full_block: &mut [u8; 16] = last_full_block.try_into().unwrap();
+
+            self.process_block(&first_tweak, last_full_block, encrypt);
+            let mut stolen_block: [u8; 16] = *last_full_block;
+            stolen_block[..remainder].copy_from_slice(partial_block);
+            partial_block.copy_from_slice(&last_full_block[..remainder]);
+
+            self.process_block(&second_tweak, &mut stolen_block, encrypt);
+            *last_full_block = stolen_block;
+        }
+
+        Ok(())
+    }
+
+    fn process_sectors_parallel(&self, parallel: &Parallel, first_sector_number: u128, sector_size: usize, input: &[u8], encrypt: bool) -> Result<Vec<u8>, ModeError> {
+        //! Encrypts or decrypts consecutive sectors on the thread pool, with as many whole sectors in a chunk as fit into the chunk size.
+
+        if !(16..=MAX_SECTOR_LENGTH).contains(&sector_size) {
+            return Err(ModeError::InvalidInputSize);
+        }
+
+        let xts: XTS = *self;
+        let sectors_per_chunk: usize = (parallel.chunk_size() / sector_size).max(1);
+        parallel.map_buffer(input, sectors_per_chunk * sector_size, move |index, chunk| {
+            for (i, sector) in chunk.chunks_mut(sector_size).enumerate() {
+                let sector_number: u128 = first_sector_number.wrapping_add((index * sectors_per_chunk + i) as u128);
+                xts.process(sector_number, sector, encrypt)?;
+            }
+
+            Ok(())
+        })
+    }
+
+    fn process_block(&self, tweak: &[u8; 16], block: &mut [u8; 16], encrypt: bool) {
+        //! Encrypts or decrypts a single block with the given tweak.
+
+        xor_blocks(block, tweak);
+        *block = if encrypt { self.data_core.encrypt(block) } else { self.data_core.decrypt(block) };
+        xor_blocks(block, tweak);
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+fn multiply_by_alpha(tweak: &[u8; 16]) -> [u8; 16] {
+    //! Multiplies the tweak by the primitive element alpha in GF(2^128), with the little-endian bit order used by XTS.
+    //! Masks are used instead of branches, so the running time doesn't depend on the value.
+
+    let value: u128 = u128::from_le_bytes(*tweak);
+    ((value << 1) ^ (0x87 & 0u128.wrapping_sub(value >> 127))).to_le_bytes()
+}
+
+
+
+
+
+// CONSTANTS
+
+/// The maximum length of a sector in bytes (2^20 blocks).
+const MAX_SECTOR_LENGTH: usize = 1 << 24;
+
+
+
+
+
+// TESTS
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xts() -> XTS {
+        XTS::new(
+            AESCore::new(AESKey::AES128([0x11; 16])),
+            AESCore::new(AESKey::AES128([0x22; 16])),
+        ).unwrap()
+    }
+
+    #[test]
+    fn encrypt_sector() {
+        //! Test encryption of a sector in place (IEEE 1619, vector 2)
+
+        let ciphertext: [u8; 32] = [
+            0xc4, 0x54, 0x18, 0x5e, 0x6a, 0x16, 0x93, 0x6e, 0x39, 0x33, 0x40, 0x38, 0xac, 0xef, 0x83, 0x8b,
+            0xfb, 0x18, 0x6f, 0xff, 0x74, 0x80, 0xad, 0xc4, 0x28, 0x93, 0x82, 0xec, 0xd6, 0xd3, 0x94, 0xf0,
+        ];
+
+        let mut sector: [u8; 32] = [0x44; 32];
+        xts().encrypt_sector(0x3333333333, &mut sector).unwrap();
+        assert_eq!(sector, ciphertext);
+
+        xts().decrypt_sector(0x3333333333, &mut sector).unwrap();
+        assert_eq!(sector, [0x44; 32]);
+    }
+
+    #[test]
+    fn ciphertext_stealing() {
+        //! Test that sectors of every length round-trip and that only the last two blocks depend on the stolen bytes
+
+        let xts = xts();
+        let plaintext: Vec<u8> = (0..64).collect();
+
+        for length in 16..64 {
+            let ciphertext: Vec<u8> = xts.encrypt(7, &plaintext[..length]).unwrap();
+            assert_eq!(ciphertext.len(), length);
+            assert_eq!(xts.decrypt(7, &ciphertext).unwrap(), plaintext[..length]);
+
+            let unaffected_length: usize = (length / 16 - 1) * 16;
+            assert_eq!(ciphertext[..unaffected_length], xts.encrypt(7, &plaintext[..48]).unwrap()[..unaffected_length]);
+        }
+
+        // the sector number changes the ciphertext
+        assert_ne!(xts.encrypt(7, &plaintext).unwrap(), xts.encrypt(8, &plaintext).unwrap());
+    }
+
+    #[test]
+    fn errors() {
+        //! Test the errors returned on invalid keys and sector sizes
+
+        let key_128 = AESCore::new(AESKey::AES128([0x11; 16]));
+        let key_256 = AESCore::new(AESKey::AES256([0x11; 32]));
+        let key_192 = AESCore::new(AESKey::AES192([0x11; 24]));
+
+        assert_eq!(XTS::new(key_128, key_128), Err(ModeError::IdenticalKeys));
+        assert_eq!(XTS::new(key_256, key_256), Err(ModeError::IdenticalKeys));
+        assert_eq!(XTS::new(key_128, key_256), Err(ModeError::InvalidKeySize));
+        assert_eq!(XTS::new(key_192, AESCore::new(AESKey::AES192([0x22; 24]))), Err(ModeError::InvalidKeySize));
+
+        let mut xts = xts();
+        assert_eq!(xts.set_aes_cores(key_128, key_128), Err(ModeError::IdenticalKeys));
+        assert_eq!(xts.data_core(), key_128);
+        assert_eq!(xts.tweak_core(), AESCore::new(AESKey::AES128([0x22; 16])));
+
+        assert_eq!(xts.encrypt(0, &[0; 15]), Err(ModeError::InvalidInputSize));
+        assert_eq!(xts.decrypt_sector(0, &mut [0; 0]), Err(ModeError::InvalidInputSize));
+    }
+}
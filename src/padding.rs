@@ -12,6 +12,15 @@
 
 
 
+// IMPORTS
+
+use std::error::Error;
+use std::fmt;
+
+
+
+
+
 // ENUMS
 
 /// The enum with padding errors.
@@ -28,6 +37,19 @@ pub enum PaddingError {
     NonePadding,
 }
 
+impl fmt::Display for PaddingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaddingError::InvalidPadding => write!(f, "the padding is invalid and cannot be removed"),
+            PaddingError::InvalidSize => write!(f, "the input to be padded is 16 or more bytes long"),
+            PaddingError::InvalidPaddedSize => write!(f, "the padded input isn't 16 bytes long"),
+            PaddingError::NonePadding => write!(f, "no padding can be applied or removed with PaddingTypes::None"),
+        }
+    }
+}
+
+impl Error for PaddingError {}
+
 /// The enum with padding types.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PaddingTypes {
@@ -153,7 +175,7 @@ impl Padding {
             PaddingTypes::PKCS7 => {
                 let padding_length = input[input.len() - 1];
 
-                if padding_length > 16 || padding_length as usize > input.len() {
+                if padding_length == 0 || padding_length > 16 || padding_length as usize > input.len() {
                     return Err(PaddingError::InvalidPadding);
                 }
 
@@ -166,13 +188,13 @@ impl Padding {
                 input.len() - padding_length as usize
             }
             PaddingTypes::ISO78164 => {
-                let mut curr_index: usize = input.len() - 1;
+                // a block of only zeros has no marker, which is invalid
+                let curr_index: usize = match input.iter().rposition(|&byte| byte != 0) {
+                    Some(index) => index,
+                    None => return Err(PaddingError::InvalidPadding),
+                };
 
-                while input[curr_index] == 0 {
-                    curr_index -= 1;
-                }
-
-                if input[curr_index] != 0x80 || input.len() - curr_index > 16{
+                if input[curr_index] != 0x80 {
                     return Err(PaddingError::InvalidPadding);
                 }
 
@@ -180,7 +202,7 @@ impl Padding {
             }
             PaddingTypes::X923 => {
                 let padding_length = input[input.len() - 1] as usize;
-                if padding_length > 16 {
+                if padding_length == 0 || padding_length > 16 {
                     return Err(PaddingError::InvalidPadding);
                 }
 
@@ -389,6 +411,12 @@ mod tests {
 
         padded_input[15] = 0x05;
         assert_eq!(padding.de_pad(&padded_input), Err(PaddingError::InvalidPadding));
+        assert_eq!(padding.de_pad(&[0; 16]), Err(PaddingError::InvalidPadding));
+
+        // blocks without padding marker
+        assert_eq!(Padding::new(PaddingTypes::ISO78164).de_pad(&[0; 16]), Err(PaddingError::InvalidPadding));
+        assert_eq!(Padding::new(PaddingTypes::ISO78164).de_pad(&[0x01; 16]), Err(PaddingError::InvalidPadding));
+        assert_eq!(Padding::new(PaddingTypes::X923).de_pad(&[0; 16]), Err(PaddingError::InvalidPadding));
 
         let new_padding = Padding::new(PaddingTypes::None);
         assert_eq!(new_padding.pad(&input), Err(PaddingError::NonePadding));
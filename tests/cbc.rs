@@ -0,0 +1,74 @@
+//! Tests of the CBC mode against the NIST test vectors.
+
+mod common;
+
+use common::Category;
+use tinyaes::{
+    CBC,
+    Implementation,
+    Padding,
+    PaddingTypes,
+};
+
+fn cbc(test_case: &common::TestCase, implementation: Implementation) -> CBC {
+    CBC::new(common::aes_core(test_case.key(), implementation), Padding::new(PaddingTypes::None), test_case.block("IV"))
+}
+
+#[test]
+fn known_answer() {
+    let file_names: Vec<String> = ["GFSbox", "KeySbox", "VarKey", "VarTxt"]
+        .iter()
+        .flat_map(|test| [128, 192, 256].map(|bits| format!("CBC{}{}.rsp", test, bits)))
+        .collect();
+    let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::KAT, &file_names) {
+            let cbc = cbc(&test_case, implementation);
+            if test_case.encrypt {
+                assert_eq!(cbc.encrypt(&test_case.hex("PLAINTEXT")).unwrap(), test_case.hex("CIPHERTEXT"), "{:?}", implementation);
+            } else {
+                assert_eq!(cbc.decrypt(&test_case.hex("CIPHERTEXT")).unwrap(), test_case.hex("PLAINTEXT"), "{:?}", implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn multiblock_message() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MMT, &["CBCMMT128.rsp", "CBCMMT192.rsp", "CBCMMT256.rsp"]) {
+            let cbc = cbc(&test_case, implementation);
+            if test_case.encrypt {
+                assert_eq!(cbc.encrypt(&test_case.hex("PLAINTEXT")).unwrap(), test_case.hex("CIPHERTEXT"), "{:?}", implementation);
+            } else {
+                assert_eq!(cbc.decrypt(&test_case.hex("CIPHERTEXT")).unwrap(), test_case.hex("PLAINTEXT"), "{:?}", implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn monte_carlo() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MCT, &["CBCMCT128.rsp", "CBCMCT192.rsp", "CBCMCT256.rsp"]) {
+            let mut cbc = cbc(&test_case, implementation);
+            let (mut input, expected) = if test_case.encrypt {
+                (test_case.block("PLAINTEXT"), test_case.hex("CIPHERTEXT"))
+            } else {
+                (test_case.block("CIPHERTEXT"), test_case.hex("PLAINTEXT"))
+            };
+
+            // the next input is the IV for the first iteration, and the previous output afterwards
+            let mut previous_output: [u8; 16] = cbc.iv();
+            let mut output: [u8; 16] = [0; 16];
+            for _ in 0..1000 {
+                output = if test_case.encrypt { cbc.encrypt(&input) } else { cbc.decrypt(&input) }.unwrap().try_into().unwrap();
+                cbc.set_iv(if test_case.encrypt { output } else { input });
+                input = previous_output;
+                previous_output = output;
+            }
+            assert_eq!(output.to_vec(), expected, "{:?}", implementation);
+        }
+    }
+}
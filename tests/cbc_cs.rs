@@ -0,0 +1,22 @@
+//! Tests of the CBC-CS3 mode against the test vectors of RFC 3962.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    CBCCS,
+    CiphertextStealing,
+};
+
+#[test]
+fn kerberos_vectors() {
+    let test_cases = common::load_from("CBC-CS", "RFC3962.rsp");
+    assert!(!test_cases.is_empty());
+
+    for test_case in test_cases {
+        let cbc_cs = CBCCS::new(AESCore::new(test_case.key()), CiphertextStealing::CS3, test_case.block("IV"));
+
+        assert_eq!(cbc_cs.encrypt(&test_case.hex("PLAINTEXT")).unwrap(), test_case.hex("CIPHERTEXT"));
+        assert_eq!(cbc_cs.decrypt(&test_case.hex("CIPHERTEXT")).unwrap(), test_case.hex("PLAINTEXT"));
+    }
+}
@@ -0,0 +1,33 @@
+//! Tests of the CCM mode against the packet vectors of RFC 3610.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    CCM,
+    ModeError,
+};
+
+#[test]
+fn packet_vectors() {
+    let test_cases = common::load_from("CCM", "RFC3610.rsp");
+    assert_eq!(test_cases.len(), 24);
+
+    for test_case in test_cases {
+        let nonce = test_case.hex("Nonce");
+        let ccm = CCM::new(
+            AESCore::new(common::aes_key(&test_case.hex("Key"))),
+            nonce.len(),
+            test_case.field("Tlen").parse().unwrap(),
+        ).unwrap();
+        let associated_data = test_case.hex("Adata");
+        let sealed = test_case.hex("CT");
+
+        assert_eq!(ccm.seal(&nonce, &associated_data, &test_case.hex("Payload")).unwrap(), sealed);
+        assert_eq!(ccm.open(&nonce, &associated_data, &sealed).unwrap(), test_case.hex("Payload"));
+
+        let mut modified = sealed.clone();
+        *modified.last_mut().unwrap() ^= 0x01;
+        assert_eq!(ccm.open(&nonce, &associated_data, &modified), Err(ModeError::AuthenticationFailed));
+    }
+}
@@ -0,0 +1,137 @@
+//! Tests of the CFB mode against the NIST test vectors.
+
+mod common;
+
+use common::Category;
+use tinyaes::{
+    CFB,
+    Implementation,
+    SegmentSize,
+};
+
+const SEGMENT_SIZES: [(SegmentSize, &str, u32); 3] = [
+    (SegmentSize::CFB1, "CFB1", 1),
+    (SegmentSize::CFB8, "CFB8", 8),
+    (SegmentSize::CFB128, "CFB128", 128),
+];
+
+fn cfb(test_case: &common::TestCase, segment_size: SegmentSize, implementation: Implementation) -> CFB {
+    CFB::new(common::aes_core(test_case.key(), implementation), segment_size, test_case.block("IV"))
+}
+
+fn data(test_case: &common::TestCase, name: &str, segment_size: SegmentSize) -> (Vec<u8>, usize) {
+    //! Returns the data and its length in bits. The CFB1 data is written as a string of bits.
+
+    let value = test_case.field(name);
+    if segment_size != SegmentSize::CFB1 {
+        return (common::hex(value), value.len() * 4);
+    }
+
+    let mut data: Vec<u8> = vec![0; value.len().div_ceil(8)];
+    for (i, bit) in value.chars().enumerate() {
+        if bit == '1' {
+            data[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+    (data, value.len())
+}
+
+fn check(test_case: &common::TestCase, segment_size: SegmentSize, implementation: Implementation) {
+    let cfb = cfb(test_case, segment_size, implementation);
+    let (plaintext, bit_length) = data(test_case, "PLAINTEXT", segment_size);
+    let (ciphertext, _) = data(test_case, "CIPHERTEXT", segment_size);
+
+    if test_case.encrypt {
+        assert_eq!(cfb.encrypt_bits(&plaintext, bit_length).unwrap(), ciphertext, "{:?}", implementation);
+    } else {
+        assert_eq!(cfb.decrypt_bits(&ciphertext, bit_length).unwrap(), plaintext, "{:?}", implementation);
+    }
+}
+
+#[test]
+fn known_answer() {
+    for (segment_size, name, _) in SEGMENT_SIZES {
+        let file_names: Vec<String> = ["GFSbox", "KeySbox", "VarKey", "VarTxt"]
+            .iter()
+            .flat_map(|test| [128, 192, 256].map(|bits| format!("{}{}{}.rsp", name, test, bits)))
+            .collect();
+        let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+        for implementation in common::implementations() {
+            for test_case in common::load_all(Category::KAT, &file_names) {
+                check(&test_case, segment_size, implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn multiblock_message() {
+    for (segment_size, name, _) in SEGMENT_SIZES {
+        let file_names: Vec<String> = [128, 192, 256].iter().map(|bits| format!("{}MMT{}.rsp", name, bits)).collect();
+        let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+        for implementation in common::implementations() {
+            for test_case in common::load_all(Category::MMT, &file_names) {
+                check(&test_case, segment_size, implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn monte_carlo() {
+    for (segment_size, name, segment_bits) in SEGMENT_SIZES {
+        let file_names: Vec<String> = [128, 192, 256].iter().map(|bits| format!("{}MCT{}.rsp", name, bits)).collect();
+        let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+        // the segments are kept as integers, so that they can be shifted into the register
+        let segment_count: usize = (128 / segment_bits) as usize;
+        let to_bytes = |segment: u128| -> Vec<u8> {
+            match segment_size {
+                SegmentSize::CFB1 => vec![(segment as u8) << 7],
+                SegmentSize::CFB8 => vec![segment as u8],
+                SegmentSize::CFB128 => segment.to_be_bytes().to_vec(),
+            }
+        };
+        let from_bytes = |bytes: &[u8]| -> u128 {
+            match segment_size {
+                SegmentSize::CFB1 => (bytes[0] >> 7) as u128,
+                SegmentSize::CFB8 => bytes[0] as u128,
+                SegmentSize::CFB128 => u128::from_be_bytes(bytes.try_into().unwrap()),
+            }
+        };
+
+        for implementation in common::implementations() {
+            for test_case in common::load_all(Category::MCT, &file_names) {
+                let mut cfb = cfb(&test_case, segment_size, implementation);
+                let (input, _) = data(&test_case, if test_case.encrypt { "PLAINTEXT" } else { "CIPHERTEXT" }, segment_size);
+                let (expected, _) = data(&test_case, if test_case.encrypt { "CIPHERTEXT" } else { "PLAINTEXT" }, segment_size);
+
+                // the next input is the matching IV segment for the first iterations, and an earlier output afterwards
+                let iv: u128 = u128::from_be_bytes(cfb.iv());
+                let mut input: u128 = from_bytes(&input);
+                let mut outputs: Vec<u128> = Vec::new();
+                for j in 0..1000 {
+                    let output: u128 = from_bytes(&if test_case.encrypt {
+                        cfb.encrypt_bits(&to_bytes(input), segment_bits as usize)
+                    } else {
+                        cfb.decrypt_bits(&to_bytes(input), segment_bits as usize)
+                    }.unwrap());
+
+                    let ciphertext: u128 = if test_case.encrypt { output } else { input };
+                    let register: u128 = u128::from_be_bytes(cfb.iv()).checked_shl(segment_bits).unwrap_or(0) | ciphertext;
+                    cfb.set_iv(register.to_be_bytes());
+
+                    outputs.push(output);
+                    input = if j < segment_count {
+                        iv.checked_shl(segment_bits * j as u32).unwrap_or(0) >> (128 - segment_bits)
+                    } else {
+                        outputs[j - segment_count]
+                    };
+                }
+                assert_eq!(to_bytes(outputs[999]), expected, "{:?}", implementation);
+            }
+        }
+    }
+}
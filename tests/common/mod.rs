@@ -0,0 +1,186 @@
+//! Helpers for reading the NIST CAVP test vectors (`.rsp` files) in `tests/test_vectors`.
+
+
+
+
+
+// DISABLED LINTS
+
+#![allow(dead_code)]  // not every test file uses every helper
+#![allow(clippy::upper_case_acronyms)]  // the names of the test categories used by NIST
+
+
+
+
+
+// IMPORTS
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use tinyaes::{
+    AESCore,
+    AESKey,
+    Implementation,
+};
+
+
+
+
+
+// ENUMS
+
+/// The categories of the NIST test vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    /// Known Answer Test.
+    KAT,
+    /// Multiblock Message Test.
+    MMT,
+    /// Monte Carlo Test.
+    MCT,
+}
+
+
+
+
+
+// STRUCTS
+
+/// A single test case from a `.rsp` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// Whether the test case is in the `[ENCRYPT]` or the `[DECRYPT]` section.
+    pub encrypt: bool,
+    /// The `NAME = value` pairs of the test case.
+    fields: HashMap<String, String>,
+}
+
+impl TestCase {
+    pub fn field(&self, name: &str) -> &str {
+        //! Returns the raw value of the given field.
+
+        self.fields.get(name).unwrap_or_else(|| panic!("missing field {}", name))
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        //! Returns whether the test case has the given field or flag.
+
+        self.fields.contains_key(name)
+    }
+
+    pub fn hex(&self, name: &str) -> Vec<u8> {
+        //! Returns the value of the given field decoded from hex.
+
+        hex(self.field(name))
+    }
+
+    pub fn block(&self, name: &str) -> [u8; 16] {
+        //! Returns the value of the given field as a single block.
+
+        self.hex(name).try_into().unwrap()
+    }
+
+    pub fn key(&self) -> AESKey {
+        //! Returns the `KEY` field as an AES key.
+
+        aes_key(&self.hex("KEY"))
+    }
+}
+
+
+
+
+
+// FUNCTIONS
+
+pub fn load(category: Category, file_name: &str) -> Vec<TestCase> {
+    //! Loads all test cases from the given file.
+
+    let directory = match category {
+        Category::KAT => "Known Answer Test (KAT)",
+        Category::MMT => "Multiblock Message Test (MMT)",
+        Category::MCT => "Monte Carlo Test (MCT)",
+    };
+    load_from(directory, file_name)
+}
+
+pub fn load_from(directory: &str, file_name: &str) -> Vec<TestCase> {
+    //! Loads all test cases from the given file in the given directory of `tests/test_vectors`.
+
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "test_vectors", directory, file_name].iter().collect();
+    parse(&fs::read_to_string(&path).unwrap_or_else(|_| panic!("cannot read {}", path.display())))
+}
+
+pub fn load_all(category: Category, file_names: &[&str]) -> Vec<TestCase> {
+    //! Loads all test cases from all given files.
+
+    file_names.iter().flat_map(|file_name| load(category, file_name)).collect()
+}
+
+pub fn parse(contents: &str) -> Vec<TestCase> {
+    //! Parses the contents of a `.rsp` file into test cases.
+
+    let mut test_cases: Vec<TestCase> = Vec::new();
+    let mut encrypt: bool = true;
+    let mut fields: HashMap<String, String> = HashMap::new();
+
+    for line in contents.lines().map(str::trim).chain([""]) {
+        if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+            if !fields.is_empty() {
+                test_cases.push(TestCase { encrypt, fields: std::mem::take(&mut fields) });
+            }
+            match line {
+                "[ENCRYPT]" => encrypt = true,
+                "[DECRYPT]" => encrypt = false,
+                _ => (),
+            }
+        } else if let Some((name, value)) = line.split_once('=') {
+            fields.insert(name.trim().to_string(), value.trim().to_string());
+        } else {
+            // flags without a value, e.g. `FAIL`
+            fields.insert(line.to_string(), String::new());
+        }
+    }
+
+    test_cases
+}
+
+pub fn hex(value: &str) -> Vec<u8> {
+    //! Decodes a hex string.
+
+    (0..value.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&value[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+pub fn aes_key(key: &[u8]) -> AESKey {
+    //! Converts the key bytes into an AES key of the matching size.
+
+    match key.len() {
+        16 => AESKey::AES128(key.try_into().unwrap()),
+        24 => AESKey::AES192(key.try_into().unwrap()),
+        32 => AESKey::AES256(key.try_into().unwrap()),
+        length => panic!("invalid key length {}", length),
+    }
+}
+
+pub fn implementations() -> Vec<Implementation> {
+    //! Returns all implementations of the AES algorithm to test.
+    //! The hardware implementation is skipped if the processor doesn't support it, as it would only repeat the fallback.
+
+    [Implementation::Hardware, Implementation::Bitsliced, Implementation::TTable, Implementation::Lookup]
+        .into_iter()
+        .filter(|&implementation| implementation != Implementation::Hardware || AESCore::is_hardware_supported())
+        .collect()
+}
+
+pub fn aes_core(key: AESKey, implementation: Implementation) -> AESCore {
+    //! Creates an AES instance with the given key using the given implementation.
+
+    let mut aes_core: AESCore = AESCore::new(key);
+    aes_core.set_implementation(implementation);
+    aes_core
+}
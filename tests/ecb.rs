@@ -0,0 +1,68 @@
+//! Tests of the ECB mode against the NIST test vectors.
+
+mod common;
+
+use common::Category;
+use tinyaes::{
+    ECB,
+    Implementation,
+    Padding,
+    PaddingTypes,
+};
+
+fn ecb(test_case: &common::TestCase, implementation: Implementation) -> ECB {
+    ECB::new(common::aes_core(test_case.key(), implementation), Padding::new(PaddingTypes::None))
+}
+
+#[test]
+fn known_answer() {
+    let file_names: Vec<String> = ["GFSbox", "KeySbox", "VarKey", "VarTxt"]
+        .iter()
+        .flat_map(|test| [128, 192, 256].map(|bits| format!("ECB{}{}.rsp", test, bits)))
+        .collect();
+    let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::KAT, &file_names) {
+            let ecb = ecb(&test_case, implementation);
+            if test_case.encrypt {
+                assert_eq!(ecb.encrypt(&test_case.hex("PLAINTEXT")).unwrap(), test_case.hex("CIPHERTEXT"), "{:?}", implementation);
+            } else {
+                assert_eq!(ecb.decrypt(&test_case.hex("CIPHERTEXT")).unwrap(), test_case.hex("PLAINTEXT"), "{:?}", implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn multiblock_message() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MMT, &["ECBMMT128.rsp", "ECBMMT192.rsp", "ECBMMT256.rsp"]) {
+            let ecb = ecb(&test_case, implementation);
+            if test_case.encrypt {
+                assert_eq!(ecb.encrypt(&test_case.hex("PLAINTEXT")).unwrap(), test_case.hex("CIPHERTEXT"), "{:?}", implementation);
+            } else {
+                assert_eq!(ecb.decrypt(&test_case.hex("CIPHERTEXT")).unwrap(), test_case.hex("PLAINTEXT"), "{:?}", implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn monte_carlo() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MCT, &["ECBMCT128.rsp", "ECBMCT192.rsp", "ECBMCT256.rsp"]) {
+            let ecb = ecb(&test_case, implementation);
+            let (mut block, expected) = if test_case.encrypt {
+                (test_case.hex("PLAINTEXT"), test_case.hex("CIPHERTEXT"))
+            } else {
+                (test_case.hex("CIPHERTEXT"), test_case.hex("PLAINTEXT"))
+            };
+
+            for _ in 0..1000 {
+                block = if test_case.encrypt { ecb.encrypt(&block) } else { ecb.decrypt(&block) }.unwrap();
+            }
+            assert_eq!(block, expected, "{:?}", implementation);
+        }
+    }
+}
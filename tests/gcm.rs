@@ -0,0 +1,29 @@
+//! Tests of the GCM mode against the test cases of the GCM specification (McGrew and Viega), not the NIST CAVP vectors.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    GCM,
+};
+
+#[test]
+fn specification() {
+    let test_cases = common::load_from("GCM", "GCMSpec.rsp");
+    assert!(!test_cases.is_empty());
+
+    for test_case in test_cases {
+        let tag = test_case.hex("Tag");
+        let gcm = GCM::new(AESCore::new(common::aes_key(&test_case.hex("Key"))), tag.len()).unwrap();
+        let iv = test_case.hex("IV");
+        let associated_data = test_case.hex("AAD");
+        let mut sealed = test_case.hex("CT");
+        sealed.extend(tag);
+
+        if test_case.encrypt {
+            assert_eq!(gcm.seal(&iv, &associated_data, &test_case.hex("PT")).unwrap(), sealed);
+        } else {
+            assert_eq!(gcm.open(&iv, &associated_data, &sealed).unwrap(), test_case.hex("PT"));
+        }
+    }
+}
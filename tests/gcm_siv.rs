@@ -0,0 +1,29 @@
+//! Tests of the GCM-SIV mode against the test vectors of RFC 8452.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    GCMSIV,
+    ModeError,
+};
+
+#[test]
+fn rfc_vectors() {
+    let test_cases = common::load_from("GCM-SIV", "RFC8452.rsp");
+    assert_eq!(test_cases.len(), 50);
+
+    for test_case in test_cases {
+        let gcm_siv = GCMSIV::new(AESCore::new(common::aes_key(&test_case.hex("Key")))).unwrap();
+        let nonce = test_case.hex("Nonce");
+        let associated_data = test_case.hex("AAD");
+        let sealed = test_case.hex("CT");
+
+        assert_eq!(gcm_siv.seal(&nonce, &associated_data, &test_case.hex("PT")).unwrap(), sealed);
+        assert_eq!(gcm_siv.open(&nonce, &associated_data, &sealed).unwrap(), test_case.hex("PT"));
+
+        let mut modified = sealed.clone();
+        modified[0] ^= 0x01;
+        assert_eq!(gcm_siv.open(&nonce, &associated_data, &modified), Err(ModeError::AuthenticationFailed));
+    }
+}
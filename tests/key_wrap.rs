@@ -0,0 +1,46 @@
+//! Tests of the KW and KWP modes against the test vectors of RFC 3394 and RFC 5649.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    KW,
+    KWP,
+    ModeError,
+};
+
+#[test]
+fn kw_vectors() {
+    let test_cases = common::load_from("KW", "RFC3394.rsp");
+    assert!(!test_cases.is_empty());
+
+    for test_case in test_cases {
+        let kw = KW::new(AESCore::new(common::aes_key(&test_case.hex("K"))));
+        let wrapped_key = test_case.hex("C");
+
+        assert_eq!(kw.wrap(&test_case.hex("P")).unwrap(), wrapped_key);
+        assert_eq!(kw.unwrap(&wrapped_key).unwrap(), test_case.hex("P"));
+
+        let mut modified = wrapped_key.clone();
+        *modified.last_mut().unwrap() ^= 0x01;
+        assert_eq!(kw.unwrap(&modified), Err(ModeError::IntegrityCheckFailed));
+    }
+}
+
+#[test]
+fn kwp_vectors() {
+    let test_cases = common::load_from("KW", "RFC5649.rsp");
+    assert!(!test_cases.is_empty());
+
+    for test_case in test_cases {
+        let kwp = KWP::new(AESCore::new(common::aes_key(&test_case.hex("K"))));
+        let wrapped_key = test_case.hex("C");
+
+        assert_eq!(kwp.wrap(&test_case.hex("P")).unwrap(), wrapped_key);
+        assert_eq!(kwp.unwrap(&wrapped_key).unwrap(), test_case.hex("P"));
+
+        let mut modified = wrapped_key.clone();
+        *modified.last_mut().unwrap() ^= 0x01;
+        assert_eq!(kwp.unwrap(&modified), Err(ModeError::IntegrityCheckFailed));
+    }
+}
@@ -0,0 +1,80 @@
+//! Tests of the OFB mode against the NIST test vectors.
+
+mod common;
+
+use common::Category;
+use tinyaes::{
+    Implementation,
+    OFB,
+};
+
+fn ofb(test_case: &common::TestCase, implementation: Implementation) -> OFB {
+    OFB::new(common::aes_core(test_case.key(), implementation), test_case.block("IV"))
+}
+
+#[test]
+fn known_answer() {
+    let file_names: Vec<String> = ["GFSbox", "KeySbox", "VarKey", "VarTxt"]
+        .iter()
+        .flat_map(|test| [128, 192, 256].map(|bits| format!("OFB{}{}.rsp", test, bits)))
+        .collect();
+    let file_names: Vec<&str> = file_names.iter().map(String::as_str).collect();
+
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::KAT, &file_names) {
+            let ofb = ofb(&test_case, implementation);
+            if test_case.encrypt {
+                assert_eq!(ofb.encrypt(&test_case.hex("PLAINTEXT")), test_case.hex("CIPHERTEXT"), "{:?}", implementation);
+            } else {
+                assert_eq!(ofb.decrypt(&test_case.hex("CIPHERTEXT")), test_case.hex("PLAINTEXT"), "{:?}", implementation);
+            }
+        }
+    }
+}
+
+#[test]
+fn multiblock_message() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MMT, &["OFBMMT128.rsp", "OFBMMT192.rsp", "OFBMMT256.rsp"]) {
+            let mut ofb = ofb(&test_case, implementation);
+            let (input, expected) = if test_case.encrypt {
+                (test_case.hex("PLAINTEXT"), test_case.hex("CIPHERTEXT"))
+            } else {
+                (test_case.hex("CIPHERTEXT"), test_case.hex("PLAINTEXT"))
+            };
+            assert_eq!(ofb.encrypt(&input), expected, "{:?}", implementation);
+
+            // the same result when streamed in uneven parts
+            let mut output: Vec<u8> = Vec::new();
+            for part in input.chunks(7) {
+                output.extend(ofb.update(part));
+            }
+            assert_eq!(output, expected, "{:?}", implementation);
+        }
+    }
+}
+
+#[test]
+fn monte_carlo() {
+    for implementation in common::implementations() {
+        for test_case in common::load_all(Category::MCT, &["OFBMCT128.rsp", "OFBMCT192.rsp", "OFBMCT256.rsp"]) {
+            let mut ofb = ofb(&test_case, implementation);
+            let (mut input, expected) = if test_case.encrypt {
+                (test_case.hex("PLAINTEXT"), test_case.hex("CIPHERTEXT"))
+            } else {
+                (test_case.hex("CIPHERTEXT"), test_case.hex("PLAINTEXT"))
+            };
+
+            // the keystream continues across the iterations
+            // the next input is the IV for the first iteration, and the previous output afterwards
+            let mut previous_output: Vec<u8> = ofb.iv().to_vec();
+            let mut output: Vec<u8> = Vec::new();
+            for _ in 0..1000 {
+                output = ofb.update(&input);
+                input = previous_output;
+                previous_output = output.clone();
+            }
+            assert_eq!(output, expected, "{:?}", implementation);
+        }
+    }
+}
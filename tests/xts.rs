@@ -0,0 +1,29 @@
+//! Tests of the XTS mode against the test vectors of IEEE Std 1619.
+
+mod common;
+
+use tinyaes::{
+    AESCore,
+    XTS,
+};
+
+#[test]
+fn ieee_vectors() {
+    let test_cases = common::load_from("XTS", "IEEE1619.rsp");
+    assert!(!test_cases.is_empty());
+
+    for test_case in test_cases {
+        let xts = XTS::new(
+            AESCore::new(common::aes_key(&test_case.hex("Key1"))),
+            AESCore::new(common::aes_key(&test_case.hex("Key2"))),
+        ).unwrap();
+        let sector_number = u128::from_str_radix(test_case.field("DataUnitSeqNumber"), 16).unwrap();
+
+        let mut sector = test_case.hex("PT");
+        xts.encrypt_sector(sector_number, &mut sector).unwrap();
+        assert_eq!(sector, test_case.hex("CT"));
+
+        xts.decrypt_sector(sector_number, &mut sector).unwrap();
+        assert_eq!(sector, test_case.hex("PT"));
+    }
+}